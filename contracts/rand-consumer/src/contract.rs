@@ -0,0 +1,264 @@
+#[cfg(not(feature = "library"))]
+use cosmwasm_std::entry_point;
+use cosmwasm_std::{
+    to_binary, Binary, Deps, DepsMut, Env, IbcMsg, IbcTimeout, MessageInfo, Reply, Response,
+    Storage,
+};
+use cw2::set_contract_version;
+use rand::contract::{callback_msg, validate_network};
+
+use crate::errors::ContractError;
+use crate::msg::{
+    ChannelResponse, ExecuteMsg, GetResponse, InstantiateMsg, NetworkInfo, QueryMsg, RandPacket,
+};
+use crate::state::{
+    beacons_storage_read, channel_read, config, config_read, jobs_storage, jobs_storage_read,
+    networks_storage, networks_storage_read, Config, Job, Network,
+};
+
+const CONTRACT_NAME: &str = "crates.io:rand-consumer";
+const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// Seconds after which a request packet times out
+const REQUEST_TIMEOUT: u64 = 60 * 60;
+
+/// Upper bound for the number of pending jobs of a round. Together with the callback gas limit
+/// of the rand contract this caps the callback gas of a delivery at 5M.
+pub(crate) const MAX_JOBS_PER_ROUND: usize = 10;
+
+/// Upper bound for the pending jobs of a single requester in a round, such that one requester
+/// cannot take all job slots of a round
+pub(crate) const MAX_JOBS_PER_REQUESTER: usize = 2;
+
+/// Seconds after which a round can no longer be requested. The rand contract only delivers
+/// rounds for a limited time, so older requests would never be answered.
+const MAX_ROUND_AGE: u64 = 24 * 60 * 60;
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn instantiate(
+    deps: DepsMut,
+    _env: Env,
+    _info: MessageInfo,
+    msg: InstantiateMsg,
+) -> Result<Response, ContractError> {
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+    config(deps.storage).save(&Config {
+        rand_port: msg.rand_port,
+        rand_connection: msg.rand_connection,
+    })?;
+    for network in msg.networks {
+        validate_network(&network)?;
+        let NetworkInfo {
+            chain_hash,
+            pubkey,
+            scheme,
+            genesis_time,
+            period,
+            ..
+        } = network;
+        if networks_storage_read(deps.storage)
+            .may_load(chain_hash.as_bytes())?
+            .is_some()
+        {
+            return Err(ContractError::DuplicateNetwork { chain_hash });
+        }
+        networks_storage(deps.storage).save(
+            chain_hash.as_bytes(),
+            &Network {
+                pubkey,
+                scheme,
+                genesis_time,
+                period,
+            },
+        )?;
+    }
+    Ok(Response::default())
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn execute(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    msg: ExecuteMsg,
+) -> Result<Response, ContractError> {
+    match msg {
+        ExecuteMsg::RequestBeacon {
+            network,
+            round,
+            job_id,
+        } => try_request_beacon(deps, env, info, network, round, job_id),
+    }
+}
+
+pub fn try_request_beacon(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    network: String,
+    round: u64,
+    job_id: String,
+) -> Result<Response, ContractError> {
+    let network_info = load_network(deps.storage, &network)?;
+    if round == 0 {
+        return Err(ContractError::InvalidRound { round });
+    }
+
+    // Known rounds are answered right away
+    let randomness = beacons_storage_read(deps.storage, &network).may_load(&round.to_be_bytes())?;
+    if let Some(randomness) = randomness {
+        let callback = callback_msg(info.sender, job_id, network, round, randomness)?;
+        return Ok(Response::new().add_submessage(callback));
+    }
+
+    let min_time = env
+        .block
+        .time
+        .minus_seconds(MAX_ROUND_AGE.min(env.block.time.seconds()));
+    let min_round = network_info.round_at(min_time) + 1;
+    if round < min_round {
+        return Err(ContractError::RoundTooOld { round, min_round });
+    }
+
+    let channel_id = channel_read(deps.storage)
+        .may_load()?
+        .ok_or(ContractError::NoChannel {})?;
+    let mut jobs = jobs_storage_read(deps.storage, &network)
+        .may_load(&round.to_be_bytes())?
+        .unwrap_or_default();
+    if jobs.len() >= MAX_JOBS_PER_ROUND {
+        return Err(ContractError::TooManyJobs {
+            round,
+            limit: MAX_JOBS_PER_ROUND as u64,
+        });
+    }
+    let requested = jobs
+        .iter()
+        .filter(|job| job.requester == info.sender)
+        .count();
+    if requested >= MAX_JOBS_PER_REQUESTER {
+        return Err(ContractError::TooManyJobsOfRequester {
+            round,
+            limit: MAX_JOBS_PER_REQUESTER as u64,
+        });
+    }
+    jobs.push(Job {
+        requester: info.sender,
+        job_id,
+    });
+    jobs_storage(deps.storage, &network).save(&round.to_be_bytes(), &jobs)?;
+
+    // The rand contract answers every request, so repeating a request of another job is harmless
+    // and helps if an earlier request timed out
+    let packet = RandPacket::RequestBeacon { network, round };
+    Ok(Response::new()
+        .add_attribute("round", round.to_string())
+        .add_message(IbcMsg::SendPacket {
+            channel_id,
+            data: to_binary(&packet)?,
+            timeout: IbcTimeout::with_timestamp(env.block.time.plus_seconds(REQUEST_TIMEOUT)),
+        }))
+}
+
+pub(crate) fn load_network(storage: &dyn Storage, network: &str) -> Result<Network, ContractError> {
+    networks_storage_read(storage)
+        .may_load(network.as_bytes())?
+        .ok_or_else(|| ContractError::UnknownNetwork {
+            network: network.to_string(),
+        })
+}
+
+/// Handles failed job callbacks like the rand contract does
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn reply(deps: DepsMut, env: Env, msg: Reply) -> Result<Response, ContractError> {
+    Ok(rand::contract::reply(deps, env, msg)?)
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> Result<Binary, ContractError> {
+    let response = match msg {
+        QueryMsg::Channel {} => {
+            let Config {
+                rand_port,
+                rand_connection,
+            } = config_read(deps.storage).load()?;
+            to_binary(&ChannelResponse {
+                channel_id: channel_read(deps.storage).may_load()?,
+                rand_port,
+                rand_connection,
+            })?
+        }
+        QueryMsg::Get { network, round } => to_binary(&query_get(deps, network, round)?)?,
+    };
+    Ok(response)
+}
+
+fn query_get(deps: Deps, network: String, round: u64) -> Result<GetResponse, ContractError> {
+    load_network(deps.storage, &network)?;
+    let randomness = beacons_storage_read(deps.storage, &network)
+        .may_load(&round.to_be_bytes())?
+        .unwrap_or_default();
+    Ok(GetResponse { randomness })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
+    use rand::errors::ContractError as RandError;
+    use rand::fixtures;
+    use rand::msg::Scheme;
+
+    const MAINNET: &str = "8990e7a9aaed2ffed73dbd7092123d6f289930540d7651336225dc172e51b2ce";
+
+    fn mainnet() -> NetworkInfo {
+        fixtures::network(fixtures::MAINNET).network_info()
+    }
+
+    fn instantiate_with(networks: Vec<NetworkInfo>) -> Result<Response, ContractError> {
+        let mut deps = mock_dependencies();
+        let msg = InstantiateMsg {
+            networks,
+            rand_port: "wasm.rand".into(),
+            rand_connection: "connection-0".into(),
+        };
+        instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg)
+    }
+
+    #[test]
+    fn instantiate_validates_networks() {
+        instantiate_with(vec![mainnet()]).unwrap();
+
+        let invalid_hash = NetworkInfo {
+            chain_hash: MAINNET.to_uppercase(),
+            ..mainnet()
+        };
+        match instantiate_with(vec![invalid_hash]).unwrap_err() {
+            ContractError::Rand(RandError::InvalidChainHash { .. }) => {}
+            err => panic!("Unexpected error: {:?}", err),
+        }
+
+        let zero_period = NetworkInfo {
+            period: 0,
+            ..mainnet()
+        };
+        match instantiate_with(vec![zero_period]).unwrap_err() {
+            ContractError::Rand(RandError::InvalidPeriod {}) => {}
+            err => panic!("Unexpected error: {:?}", err),
+        }
+
+        let wrong_scheme = NetworkInfo {
+            scheme: Scheme::BlsUnchainedG1Rfc9380,
+            ..mainnet()
+        };
+        match instantiate_with(vec![wrong_scheme]).unwrap_err() {
+            ContractError::Rand(RandError::MalformedPubkey { .. }) => {}
+            err => panic!("Unexpected error: {:?}", err),
+        }
+
+        match instantiate_with(vec![mainnet(), mainnet()]).unwrap_err() {
+            ContractError::DuplicateNetwork { chain_hash } => assert_eq!(chain_hash, MAINNET),
+            err => panic!("Unexpected error: {:?}", err),
+        }
+    }
+}
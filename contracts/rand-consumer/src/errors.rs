@@ -0,0 +1,40 @@
+use cosmwasm_std::StdError;
+use rand::errors::ContractError as RandError;
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum ContractError {
+    #[error("StdError: {0}")]
+    StdError(#[from] StdError),
+    #[error("{0}")]
+    Rand(#[from] RandError),
+    #[error("Network registered more than once: {chain_hash}")]
+    DuplicateNetwork { chain_hash: String },
+    #[error("Unknown drand network: {network}")]
+    UnknownNetwork { network: String },
+    #[error("Signature verification failed")]
+    InvalidSignature {},
+    #[error("Randomness does not match the signature")]
+    InvalidRandomness {},
+    #[error("Round {round} has no representable publish time")]
+    InvalidRound { round: u64 },
+    #[error(
+        "Round {round} is too old to be requested. The oldest round accepting jobs is {min_round}."
+    )]
+    RoundTooOld { round: u64, min_round: u64 },
+    #[error("Round {round} has the maximum of {limit} pending jobs")]
+    TooManyJobs { round: u64, limit: u64 },
+    #[error("Round {round} has the maximum of {limit} pending jobs of this requester")]
+    TooManyJobsOfRequester { round: u64, limit: u64 },
+    #[error("Not connected to a rand contract")]
+    NoChannel {},
+    #[error("Already connected to a rand contract via {channel_id}")]
+    ChannelExists { channel_id: String },
+    #[error("Channel to {port_id} via {connection_id} does not lead to the rand contract")]
+    InvalidCounterparty {
+        port_id: String,
+        connection_id: String,
+    },
+    #[error("Unsupported packet")]
+    UnsupportedPacket {},
+}
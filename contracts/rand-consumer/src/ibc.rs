@@ -0,0 +1,519 @@
+//! IBC entry points receiving beacons from a rand contract.
+//!
+//! The contract connects to exactly one rand contract using an unordered channel with
+//! version [`IBC_VERSION`]. Every delivered beacon is verified against the pubkey of its
+//! network before it is stored and passed on to the requesters, such that neither the
+//! remote chain nor the IBC relayers need to be trusted with the randomness.
+
+#[cfg(not(feature = "library"))]
+use cosmwasm_std::entry_point;
+use cosmwasm_std::{
+    from_binary, DepsMut, Env, IbcBasicResponse, IbcChannel, IbcChannelCloseMsg,
+    IbcChannelConnectMsg, IbcChannelOpenMsg, IbcPacketAckMsg, IbcPacketReceiveMsg,
+    IbcPacketTimeoutMsg, IbcReceiveResponse, StdResult, Storage, SubMsg,
+};
+use drand_verify::derive_randomness;
+use rand::contract::callback_msg;
+use rand::ibc::{ack_error, ack_success};
+use rand::verify::verify_beacon;
+
+use crate::contract::load_network;
+use crate::errors::ContractError;
+use crate::msg::{RandAck, RandPacket};
+use crate::state::{
+    beacons_storage, channel, channel_read, config_read, jobs_storage, jobs_storage_read, Job,
+};
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn ibc_channel_open(
+    deps: DepsMut,
+    _env: Env,
+    msg: IbcChannelOpenMsg,
+) -> Result<(), ContractError> {
+    validate_channel(deps.storage, msg.channel(), msg.counterparty_version())
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn ibc_channel_connect(
+    deps: DepsMut,
+    _env: Env,
+    msg: IbcChannelConnectMsg,
+) -> Result<IbcBasicResponse, ContractError> {
+    let channel_id = &msg.channel().endpoint.channel_id;
+    validate_channel(deps.storage, msg.channel(), msg.counterparty_version())?;
+    channel(deps.storage).save(channel_id)?;
+
+    Ok(IbcBasicResponse::new()
+        .add_attribute("action", "ibc_connect")
+        .add_attribute("channel_id", channel_id))
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn ibc_channel_close(
+    deps: DepsMut,
+    _env: Env,
+    msg: IbcChannelCloseMsg,
+) -> Result<IbcBasicResponse, ContractError> {
+    let channel_id = &msg.channel().endpoint.channel_id;
+    if channel_read(deps.storage).may_load()?.as_ref() == Some(channel_id) {
+        channel(deps.storage).remove();
+    }
+
+    Ok(IbcBasicResponse::new()
+        .add_attribute("action", "ibc_close")
+        .add_attribute("channel_id", channel_id))
+}
+
+/// Failures are returned as error acknowledgements rather than errors, such that the
+/// rand contract learns about them.
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn ibc_packet_receive(
+    deps: DepsMut,
+    _env: Env,
+    msg: IbcPacketReceiveMsg,
+) -> Result<IbcReceiveResponse, ContractError> {
+    let result = from_binary(&msg.packet.data)
+        .map_err(ContractError::from)
+        .and_then(|packet| match packet {
+            RandPacket::DeliverBeacon {
+                network,
+                round,
+                previous_signature,
+                signature,
+                randomness,
+            } => receive_beacon(
+                deps,
+                network,
+                round,
+                &previous_signature,
+                &signature,
+                &randomness,
+            ),
+            RandPacket::RequestBeacon { .. } => Err(ContractError::UnsupportedPacket {}),
+        });
+
+    Ok(result.unwrap_or_else(|err| {
+        IbcReceiveResponse::new()
+            .set_ack(ack_error(err.to_string()))
+            .add_attribute("action", "ibc_receive")
+            .add_attribute("error", err.to_string())
+    }))
+}
+
+fn receive_beacon(
+    deps: DepsMut,
+    network: String,
+    round: u64,
+    previous_signature: &[u8],
+    signature: &[u8],
+    randomness: &[u8],
+) -> Result<IbcReceiveResponse, ContractError> {
+    let network_info = load_network(deps.storage, &network)?;
+    let valid = verify_beacon(
+        network_info.scheme,
+        &network_info.pubkey,
+        round,
+        previous_signature,
+        signature,
+    )
+    .unwrap_or(false);
+    if !valid {
+        return Err(ContractError::InvalidSignature {});
+    }
+    let verified_randomness = derive_randomness(signature);
+    if randomness != verified_randomness {
+        return Err(ContractError::InvalidRandomness {});
+    }
+
+    beacons_storage(deps.storage, &network)
+        .save(&round.to_be_bytes(), &verified_randomness.into())?;
+    let callbacks = take_callbacks(deps.storage, &network, round, &verified_randomness)?;
+
+    Ok(IbcReceiveResponse::new()
+        .set_ack(ack_success())
+        .add_attribute("action", "ibc_receive")
+        .add_attribute("round", round.to_string())
+        .add_submessages(callbacks))
+}
+
+/// Creates the callbacks of all jobs waiting for the round and removes the jobs
+fn take_callbacks(
+    storage: &mut dyn Storage,
+    network: &str,
+    round: u64,
+    randomness: &[u8; 32],
+) -> StdResult<Vec<SubMsg>> {
+    let jobs = jobs_storage_read(storage, network)
+        .may_load(&round.to_be_bytes())?
+        .unwrap_or_default();
+    jobs_storage(storage, network).remove(&round.to_be_bytes());
+    jobs.into_iter()
+        .map(|Job { requester, job_id }| {
+            callback_msg(
+                requester,
+                job_id,
+                network.to_string(),
+                round,
+                randomness.into(),
+            )
+        })
+        .collect()
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn ibc_packet_ack(
+    _deps: DepsMut,
+    _env: Env,
+    msg: IbcPacketAckMsg,
+) -> Result<IbcBasicResponse, ContractError> {
+    // Jobs of failed requests keep waiting, a new request for the round sends the packet again
+    let response = IbcBasicResponse::new().add_attribute("action", "ibc_ack");
+    Ok(match from_binary(&msg.acknowledgement.data)? {
+        RandAck::Result(_) => response,
+        RandAck::Error(err) => response.add_attribute("error", err),
+    })
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn ibc_packet_timeout(
+    _deps: DepsMut,
+    _env: Env,
+    _msg: IbcPacketTimeoutMsg,
+) -> Result<IbcBasicResponse, ContractError> {
+    Ok(IbcBasicResponse::new().add_attribute("action", "ibc_timeout"))
+}
+
+/// Accepts a single channel to the configured rand contract
+fn validate_channel(
+    storage: &dyn Storage,
+    channel: &IbcChannel,
+    counterparty_version: Option<&str>,
+) -> Result<(), ContractError> {
+    if let Some(channel_id) = channel_read(storage).may_load()? {
+        return Err(ContractError::ChannelExists { channel_id });
+    }
+    let config = config_read(storage).load()?;
+    if channel.counterparty_endpoint.port_id != config.rand_port
+        || channel.connection_id != config.rand_connection
+    {
+        return Err(ContractError::InvalidCounterparty {
+            port_id: channel.counterparty_endpoint.port_id.clone(),
+            connection_id: channel.connection_id.clone(),
+        });
+    }
+    rand::ibc::validate_channel(channel, counterparty_version)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::contract::{
+        execute, instantiate, query, MAX_JOBS_PER_REQUESTER, MAX_JOBS_PER_ROUND,
+    };
+    use crate::msg::{
+        BeaconCallback, ChannelResponse, ExecuteMsg, GetResponse, InstantiateMsg, QueryMsg,
+        IBC_VERSION,
+    };
+    use cosmwasm_std::testing::{
+        mock_dependencies, mock_env, mock_ibc_channel, mock_ibc_channel_close_confirm,
+        mock_ibc_channel_connect_ack, mock_ibc_channel_open_init, mock_ibc_packet_recv, mock_info,
+        MockApi, MockQuerier, MockStorage,
+    };
+    use cosmwasm_std::{
+        from_binary, to_binary, Binary, IbcMsg, IbcOrder, IbcTimeout, OwnedDeps, Response,
+        Timestamp,
+    };
+    use rand::contract::{CALLBACK_GAS_LIMIT, CALLBACK_REPLY_ID};
+    use rand::drand::DrandBeacon;
+    use rand::errors::ContractError as RandError;
+    use rand::fixtures;
+
+    const MAINNET: &str = "8990e7a9aaed2ffed73dbd7092123d6f289930540d7651336225dc172e51b2ce";
+
+    fn beacon_72785() -> DrandBeacon {
+        fixtures::beacon(fixtures::MAINNET, 72785)
+    }
+
+    fn setup() -> OwnedDeps<MockStorage, MockApi, MockQuerier> {
+        let mut deps = mock_dependencies();
+        let msg = InstantiateMsg {
+            networks: vec![fixtures::network(fixtures::MAINNET).network_info()],
+            // The counterparty of the mock channels
+            rand_port: "their_port".into(),
+            rand_connection: "connection-2".into(),
+        };
+        instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+        deps
+    }
+
+    fn connect(mut deps: DepsMut, channel_id: &str) -> Result<(), ContractError> {
+        let msg = mock_ibc_channel_open_init(channel_id, IbcOrder::Unordered, IBC_VERSION);
+        ibc_channel_open(deps.branch(), mock_env(), msg)?;
+        let msg = mock_ibc_channel_connect_ack(channel_id, IbcOrder::Unordered, IBC_VERSION);
+        ibc_channel_connect(deps, mock_env(), msg)?;
+        Ok(())
+    }
+
+    fn request_beacon(deps: DepsMut, requester: &str, job_id: &str) -> Response {
+        let msg = ExecuteMsg::RequestBeacon {
+            network: MAINNET.into(),
+            round: 72785,
+            job_id: job_id.into(),
+        };
+        execute(deps, mock_env(), mock_info(requester, &[]), msg).unwrap()
+    }
+
+    fn delivery(signature: Binary, randomness: Binary) -> RandPacket {
+        RandPacket::DeliverBeacon {
+            network: MAINNET.into(),
+            round: 72785,
+            previous_signature: beacon_72785().previous_signature,
+            signature,
+            randomness,
+        }
+    }
+
+    fn callback(requester: &str, job_id: &str) -> SubMsg {
+        let msg = BeaconCallback {
+            job_id: job_id.into(),
+            network: MAINNET.into(),
+            round: 72785,
+            randomness: beacon_72785().randomness,
+        }
+        .into_cosmos_msg(requester)
+        .unwrap();
+        SubMsg::reply_on_error(msg, CALLBACK_REPLY_ID).with_gas_limit(CALLBACK_GAS_LIMIT)
+    }
+
+    #[test]
+    fn only_one_channel_can_be_connected() {
+        let mut deps = setup();
+
+        let msg = ExecuteMsg::RequestBeacon {
+            network: MAINNET.into(),
+            round: 72785,
+            job_id: "1".into(),
+        };
+        match execute(deps.as_mut(), mock_env(), mock_info("dapp", &[]), msg).unwrap_err() {
+            ContractError::NoChannel {} => {}
+            err => panic!("Unexpected error: {:?}", err),
+        }
+
+        connect(deps.as_mut(), "channel-1").unwrap();
+        match connect(deps.as_mut(), "channel-2").unwrap_err() {
+            ContractError::ChannelExists { channel_id } => assert_eq!(channel_id, "channel-1"),
+            err => panic!("Unexpected error: {:?}", err),
+        }
+
+        let msg = mock_ibc_channel_close_confirm("channel-1", IbcOrder::Unordered, IBC_VERSION);
+        ibc_channel_close(deps.as_mut(), mock_env(), msg).unwrap();
+        connect(deps.as_mut(), "channel-2").unwrap();
+
+        let channel: ChannelResponse =
+            from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::Channel {}).unwrap()).unwrap();
+        assert_eq!(channel.channel_id, Some("channel-2".into()));
+    }
+
+    #[test]
+    fn only_the_rand_contract_can_connect() {
+        let mut deps = setup();
+        let open = |channel: IbcChannel| IbcChannelOpenMsg::new_init(channel);
+
+        let mut other_port = mock_ibc_channel("channel-1", IbcOrder::Unordered, IBC_VERSION);
+        other_port.counterparty_endpoint.port_id = "wasm.other".into();
+        match ibc_channel_open(deps.as_mut(), mock_env(), open(other_port)).unwrap_err() {
+            ContractError::InvalidCounterparty {
+                port_id,
+                connection_id,
+            } => {
+                assert_eq!(port_id, "wasm.other");
+                assert_eq!(connection_id, "connection-2");
+            }
+            err => panic!("Unexpected error: {:?}", err),
+        }
+
+        let mut other_connection = mock_ibc_channel("channel-1", IbcOrder::Unordered, IBC_VERSION);
+        other_connection.connection_id = "connection-3".into();
+        match ibc_channel_open(deps.as_mut(), mock_env(), open(other_connection)).unwrap_err() {
+            ContractError::InvalidCounterparty { connection_id, .. } => {
+                assert_eq!(connection_id, "connection-3")
+            }
+            err => panic!("Unexpected error: {:?}", err),
+        }
+
+        // The counterparty is checked again when the channel is connected
+        let mut msg = mock_ibc_channel_connect_ack("channel-1", IbcOrder::Unordered, IBC_VERSION);
+        if let IbcChannelConnectMsg::OpenAck { channel, .. } = &mut msg {
+            channel.counterparty_endpoint.port_id = "wasm.other".into();
+        }
+        match ibc_channel_connect(deps.as_mut(), mock_env(), msg).unwrap_err() {
+            ContractError::InvalidCounterparty { port_id, .. } => {
+                assert_eq!(port_id, "wasm.other")
+            }
+            err => panic!("Unexpected error: {:?}", err),
+        }
+
+        // Order and version are checked like in the rand contract
+        let ordered = mock_ibc_channel("channel-1", IbcOrder::Ordered, IBC_VERSION);
+        match ibc_channel_open(deps.as_mut(), mock_env(), open(ordered)).unwrap_err() {
+            ContractError::Rand(RandError::InvalidIbcOrder {}) => {}
+            err => panic!("Unexpected error: {:?}", err),
+        }
+        let msg = mock_ibc_channel_open_init("channel-1", IbcOrder::Unordered, "rand-2");
+        match ibc_channel_open(deps.as_mut(), mock_env(), msg).unwrap_err() {
+            ContractError::Rand(RandError::InvalidIbcVersion { version, .. }) => {
+                assert_eq!(version, "rand-2")
+            }
+            err => panic!("Unexpected error: {:?}", err),
+        }
+
+        let channel: ChannelResponse =
+            from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::Channel {}).unwrap()).unwrap();
+        assert_eq!(
+            channel,
+            ChannelResponse {
+                channel_id: None,
+                rand_port: "their_port".into(),
+                rand_connection: "connection-2".into(),
+            }
+        );
+    }
+
+    #[test]
+    fn requests_are_limited() {
+        let mut deps = setup();
+        connect(deps.as_mut(), "channel-1").unwrap();
+
+        let request = |round: u64, job_id: usize| ExecuteMsg::RequestBeacon {
+            network: MAINNET.into(),
+            round,
+            job_id: job_id.to_string(),
+        };
+
+        let msg = request(0, 0);
+        match execute(deps.as_mut(), mock_env(), mock_info("dapp", &[]), msg).unwrap_err() {
+            ContractError::InvalidRound { round } => assert_eq!(round, 0),
+            err => panic!("Unexpected error: {:?}", err),
+        }
+
+        // Round 72785 was published in 2020
+        let mut env = mock_env();
+        env.block.time = Timestamp::from_seconds(1_700_000_000);
+        let msg = request(72785, 0);
+        match execute(deps.as_mut(), env, mock_info("dapp", &[]), msg).unwrap_err() {
+            ContractError::RoundTooOld { round, .. } => assert_eq!(round, 72785),
+            err => panic!("Unexpected error: {:?}", err),
+        }
+
+        // A single requester cannot take all job slots
+
+        for job_id in 0..MAX_JOBS_PER_REQUESTER {
+            let msg = request(72785, job_id);
+            execute(deps.as_mut(), mock_env(), mock_info("dapp", &[]), msg).unwrap();
+        }
+        let msg = request(72785, MAX_JOBS_PER_REQUESTER);
+        match execute(deps.as_mut(), mock_env(), mock_info("dapp", &[]), msg).unwrap_err() {
+            ContractError::TooManyJobsOfRequester { round, limit } => {
+                assert_eq!(round, 72785);
+                assert_eq!(limit, MAX_JOBS_PER_REQUESTER as u64);
+            }
+            err => panic!("Unexpected error: {:?}", err),
+        }
+
+        for job_id in MAX_JOBS_PER_REQUESTER..MAX_JOBS_PER_ROUND {
+            let requester = format!("dapp{}", job_id);
+            let msg = request(72785, job_id);
+            execute(deps.as_mut(), mock_env(), mock_info(&requester, &[]), msg).unwrap();
+        }
+        let msg = request(72785, MAX_JOBS_PER_ROUND);
+        match execute(deps.as_mut(), mock_env(), mock_info("other", &[]), msg).unwrap_err() {
+            ContractError::TooManyJobs { round, limit } => {
+                assert_eq!(round, 72785);
+                assert_eq!(limit, MAX_JOBS_PER_ROUND as u64);
+            }
+            err => panic!("Unexpected error: {:?}", err),
+        }
+    }
+
+    #[test]
+    fn verified_beacons_are_passed_to_requesters() {
+        let mut deps = setup();
+        let beacon = beacon_72785();
+        connect(deps.as_mut(), "channel-1").unwrap();
+
+        let response = request_beacon(deps.as_mut(), "dapp", "1");
+        assert_eq!(
+            response.messages,
+            vec![SubMsg::new(IbcMsg::SendPacket {
+                channel_id: "channel-1".into(),
+                data: to_binary(&RandPacket::RequestBeacon {
+                    network: MAINNET.into(),
+                    round: 72785,
+                })
+                .unwrap(),
+                timeout: IbcTimeout::with_timestamp(mock_env().block.time.plus_seconds(3600)),
+            })]
+        );
+        request_beacon(deps.as_mut(), "other_dapp", "2");
+
+        let msg = mock_ibc_packet_recv(
+            "channel-1",
+            &delivery(beacon.signature, beacon.randomness.clone()),
+        )
+        .unwrap();
+        let response = ibc_packet_receive(deps.as_mut(), mock_env(), msg).unwrap();
+        assert_eq!(response.acknowledgement, ack_success());
+        assert_eq!(
+            response.messages,
+            vec![callback("dapp", "1"), callback("other_dapp", "2")]
+        );
+
+        // Later requests are answered from storage
+        let response = request_beacon(deps.as_mut(), "dapp", "3");
+        assert_eq!(response.messages, vec![callback("dapp", "3")]);
+
+        let msg = QueryMsg::Get {
+            network: MAINNET.into(),
+            round: 72785,
+        };
+        let get: GetResponse =
+            from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+        assert_eq!(get.randomness, beacon.randomness);
+    }
+
+    #[test]
+    fn invalid_beacons_are_rejected() {
+        let mut deps = setup();
+        let beacon = beacon_72785();
+        connect(deps.as_mut(), "channel-1").unwrap();
+        request_beacon(deps.as_mut(), "dapp", "1");
+
+        // signature of round 72784
+        let packet = delivery(beacon.previous_signature, beacon.randomness);
+        let msg = mock_ibc_packet_recv("channel-1", &packet).unwrap();
+        let response = ibc_packet_receive(deps.as_mut(), mock_env(), msg).unwrap();
+        assert_eq!(
+            response.acknowledgement,
+            ack_error("Signature verification failed".into())
+        );
+        assert_eq!(response.messages, vec![]);
+
+        let packet = delivery(beacon.signature, vec![0; 32].into());
+        let msg = mock_ibc_packet_recv("channel-1", &packet).unwrap();
+        let response = ibc_packet_receive(deps.as_mut(), mock_env(), msg).unwrap();
+        assert_eq!(
+            response.acknowledgement,
+            ack_error("Randomness does not match the signature".into())
+        );
+        assert_eq!(response.messages, vec![]);
+
+        let msg = QueryMsg::Get {
+            network: MAINNET.into(),
+            round: 72785,
+        };
+        let get: GetResponse =
+            from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+        assert_eq!(get.randomness, Binary::default());
+    }
+}
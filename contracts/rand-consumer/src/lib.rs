@@ -0,0 +1,5 @@
+pub mod contract;
+mod errors;
+pub mod ibc;
+pub mod msg;
+pub mod state;
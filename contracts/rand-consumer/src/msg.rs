@@ -0,0 +1,55 @@
+//! Messages of the consumer contract.
+//!
+//! Networks, packets and callbacks use the types of the rand contract, such that a consumer
+//! and its requesters speak the same protocol as the rand contract itself.
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+pub use rand::msg::{
+    BeaconCallback, GetResponse, NetworkInfo, RandAck, RandPacket, ReceiverExecuteMsg, IBC_VERSION,
+};
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct InstantiateMsg {
+    /// The networks whose beacons are accepted. Delivered beacons are verified against
+    /// these pubkeys, so the relayers and the remote chain do not need to be trusted.
+    pub networks: Vec<NetworkInfo>,
+    /// The IBC port of the rand contract, `wasm.<contract address>`. Channels from other
+    /// ports are rejected.
+    pub rand_port: String,
+    /// The connection to the chain of the rand contract. Channels over other connections
+    /// are rejected.
+    pub rand_connection: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ExecuteMsg {
+    /// Requests a beacon from the connected rand contract. The sender receives a
+    /// `ReceiverExecuteMsg::ReceiveBeacon` callback once the verified beacon arrived.
+    RequestBeacon {
+        network: String,
+        round: u64,
+        job_id: String,
+    },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum QueryMsg {
+    /// Returns the channel to the rand contract
+    Channel {},
+    /// Returns the randomness of a round that was delivered already
+    Get { network: String, round: u64 },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ChannelResponse {
+    /// None if no channel is connected
+    pub channel_id: Option<String>,
+    /// The port of the rand contract that channels must be connected to
+    pub rand_port: String,
+    /// The connection that channels must use
+    pub rand_connection: String,
+}
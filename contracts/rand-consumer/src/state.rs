@@ -0,0 +1,74 @@
+use cosmwasm_std::{Binary, Storage};
+use cosmwasm_storage::{
+    bucket, bucket_read, singleton, singleton_read, Bucket, ReadonlyBucket, ReadonlySingleton,
+    Singleton,
+};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+pub use rand::state::{Job, Network};
+
+const CONFIG_KEY: &[u8] = b"config";
+const CHANNEL_KEY: &[u8] = b"channel";
+const NETWORKS_KEY: &[u8] = b"networks";
+const BEACONS_KEY: &[u8] = b"beacons";
+const JOBS_KEY: &[u8] = b"jobs";
+
+/// The counterparty a channel must be connected to
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Config {
+    /// The IBC port of the rand contract, `wasm.<contract address>`
+    pub rand_port: String,
+    /// The connection to the chain of the rand contract
+    pub rand_connection: String,
+}
+
+pub fn config(storage: &mut dyn Storage) -> Singleton<'_, Config> {
+    singleton(storage, CONFIG_KEY)
+}
+
+pub fn config_read(storage: &dyn Storage) -> ReadonlySingleton<'_, Config> {
+    singleton_read(storage, CONFIG_KEY)
+}
+
+/// The ID of the channel connected to the rand contract
+pub fn channel(storage: &mut dyn Storage) -> Singleton<'_, String> {
+    singleton(storage, CHANNEL_KEY)
+}
+
+pub fn channel_read(storage: &dyn Storage) -> ReadonlySingleton<'_, String> {
+    singleton_read(storage, CHANNEL_KEY)
+}
+
+/// Accepted networks, keyed by chain hash
+pub fn networks_storage(storage: &mut dyn Storage) -> Bucket<'_, Network> {
+    bucket(storage, NETWORKS_KEY)
+}
+
+pub fn networks_storage_read(storage: &dyn Storage) -> ReadonlyBucket<'_, Network> {
+    bucket_read(storage, NETWORKS_KEY)
+}
+
+/// Verified randomness of one network, keyed by round
+pub fn beacons_storage<'a>(storage: &'a mut dyn Storage, network: &str) -> Bucket<'a, Binary> {
+    Bucket::multilevel(storage, &[BEACONS_KEY, network.as_bytes()])
+}
+
+pub fn beacons_storage_read<'a>(
+    storage: &'a dyn Storage,
+    network: &str,
+) -> ReadonlyBucket<'a, Binary> {
+    ReadonlyBucket::multilevel(storage, &[BEACONS_KEY, network.as_bytes()])
+}
+
+/// Jobs waiting for a round of one network, keyed by round
+pub fn jobs_storage<'a>(storage: &'a mut dyn Storage, network: &str) -> Bucket<'a, Vec<Job>> {
+    Bucket::multilevel(storage, &[JOBS_KEY, network.as_bytes()])
+}
+
+pub fn jobs_storage_read<'a>(
+    storage: &'a dyn Storage,
+    network: &str,
+) -> ReadonlyBucket<'a, Vec<Job>> {
+    ReadonlyBucket::multilevel(storage, &[JOBS_KEY, network.as_bytes()])
+}
@@ -0,0 +1,174 @@
+#[cfg(not(feature = "library"))]
+use cosmwasm_std::entry_point;
+use cosmwasm_std::{
+    to_binary, BankMsg, Binary, Coin, Deps, DepsMut, Env, MessageInfo, Response, StdResult,
+    Uint128, WasmMsg,
+};
+use cw2::set_contract_version;
+use rand::msg::{ExecuteMsg as RandExecuteMsg, QueryMsg as RandQueryMsg, RoundResponse};
+use rand::randomness::{derive_sub_randomness, RandomStream};
+
+use crate::errors::ContractError;
+use crate::msg::{BeaconCallback, ExecuteMsg, InstantiateMsg, LotteryResponse, QueryMsg};
+use crate::state::{config, config_read, lottery, lottery_read, Config, Draw, Lottery};
+
+const CONTRACT_NAME: &str = "crates.io:rand-lottery";
+const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn instantiate(
+    deps: DepsMut,
+    _env: Env,
+    _info: MessageInfo,
+    msg: InstantiateMsg,
+) -> Result<Response, ContractError> {
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+    config(deps.storage).save(&Config {
+        rand: deps.api.addr_validate(&msg.rand)?,
+        network: msg.network,
+        ticket_price: msg.ticket_price,
+    })?;
+    lottery(deps.storage).save(&Lottery::default())?;
+    Ok(Response::default())
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn execute(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    msg: ExecuteMsg,
+) -> Result<Response, ContractError> {
+    match msg {
+        ExecuteMsg::BuyTicket {} => try_buy_ticket(deps, info),
+        ExecuteMsg::Draw {} => try_draw(deps, env, info),
+        ExecuteMsg::ReceiveBeacon(callback) => try_receive_beacon(deps, env, info, callback),
+    }
+}
+
+pub fn try_buy_ticket(deps: DepsMut, info: MessageInfo) -> Result<Response, ContractError> {
+    let price = config_read(deps.storage).load()?.ticket_price;
+    if info.funds != [price.clone()] {
+        return Err(ContractError::WrongTicketPrice { price });
+    }
+
+    let mut state = lottery_read(deps.storage).load()?;
+    if let Some(draw) = state.draw {
+        return Err(ContractError::DrawInProgress { round: draw.round });
+    }
+    state.players.push(info.sender.clone());
+    lottery(deps.storage).save(&state)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "buy_ticket")
+        .add_attribute("player", info.sender)
+        .add_attribute("tickets", state.players.len().to_string()))
+}
+
+pub fn try_draw(deps: DepsMut, env: Env, info: MessageInfo) -> Result<Response, ContractError> {
+    let Config { rand, network, .. } = config_read(deps.storage).load()?;
+    let mut state = lottery_read(deps.storage).load()?;
+    if let Some(draw) = state.draw {
+        return Err(ContractError::DrawInProgress { round: draw.round });
+    }
+    if state.players.is_empty() {
+        return Err(ContractError::NoPlayers {});
+    }
+
+    // Use a round nobody knows yet, such that players cannot predict the winner
+    let RoundResponse { round } = deps.querier.query_wasm_smart(
+        &rand,
+        &RandQueryMsg::NextRoundAfter {
+            network: network.clone(),
+            timestamp: env.block.time,
+        },
+    )?;
+    let job_id = format!("draw-{}", state.draws + 1);
+    state.draw = Some(Draw {
+        round,
+        job_id: job_id.clone(),
+    });
+    lottery(deps.storage).save(&state)?;
+
+    // The rand contract adds the funds to the bounty of the round, which also pays its job fee
+    Ok(Response::new()
+        .add_attribute("action", "draw")
+        .add_attribute("round", round.to_string())
+        .add_message(WasmMsg::Execute {
+            contract_addr: rand.into(),
+            msg: to_binary(&RandExecuteMsg::RequestBeacon {
+                network,
+                round,
+                job_id,
+            })?,
+            funds: info.funds,
+        }))
+}
+
+pub fn try_receive_beacon(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    callback: BeaconCallback,
+) -> Result<Response, ContractError> {
+    let config = config_read(deps.storage).load()?;
+    if info.sender != config.rand {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let mut state = lottery_read(deps.storage).load()?;
+    match &state.draw {
+        Some(draw) if draw.job_id == callback.job_id && draw.round == callback.round => {}
+        _ => {
+            return Err(ContractError::UnknownJob {
+                job_id: callback.job_id,
+            })
+        }
+    }
+
+    let randomness = derive_sub_randomness(
+        &callback.randomness,
+        env.contract.address.as_str(),
+        &callback.job_id,
+    );
+    let index = RandomStream::new(&randomness, b"").int_in_range(0, state.players.len() as u64 - 1);
+    let winner = state.players[index as usize].clone();
+    let pot = Coin {
+        denom: config.ticket_price.denom,
+        amount: config.ticket_price.amount * Uint128::from(state.players.len() as u128),
+    };
+
+    state.players.clear();
+    state.draw = None;
+    state.draws += 1;
+    state.last_winner = Some(winner.clone());
+    lottery(deps.storage).save(&state)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "pay_winner")
+        .add_attribute("winner", winner.clone())
+        .add_attribute("pot", pot.to_string())
+        .add_message(BankMsg::Send {
+            to_address: winner.into(),
+            amount: vec![pot],
+        }))
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> Result<Binary, ContractError> {
+    let response = match msg {
+        QueryMsg::Lottery {} => to_binary(&query_lottery(deps)?)?,
+    };
+    Ok(response)
+}
+
+fn query_lottery(deps: Deps) -> StdResult<LotteryResponse> {
+    let config = config_read(deps.storage).load()?;
+    let state = lottery_read(deps.storage).load()?;
+    Ok(LotteryResponse {
+        ticket_price: config.ticket_price,
+        players: state.players,
+        draw_round: state.draw.map(|draw| draw.round),
+        last_winner: state.last_winner,
+    })
+}
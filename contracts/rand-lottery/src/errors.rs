@@ -0,0 +1,18 @@
+use cosmwasm_std::{Coin, StdError};
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum ContractError {
+    #[error("StdError: {0}")]
+    StdError(#[from] StdError),
+    #[error("A ticket costs exactly {price}")]
+    WrongTicketPrice { price: Coin },
+    #[error("Ticket sales are closed until round {round} is drawn")]
+    DrawInProgress { round: u64 },
+    #[error("No tickets were bought")]
+    NoPlayers {},
+    #[error("Beacons are only accepted from the rand contract")]
+    Unauthorized {},
+    #[error("No draw is waiting for job {job_id}")]
+    UnknownJob { job_id: String },
+}
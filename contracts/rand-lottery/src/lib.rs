@@ -0,0 +1,4 @@
+pub mod contract;
+mod errors;
+pub mod msg;
+pub mod state;
@@ -0,0 +1,49 @@
+//! Messages of the lottery contract.
+//!
+//! The beacon callback uses the type of the rand contract, such that `ExecuteMsg::ReceiveBeacon`
+//! is serialized exactly like `rand::msg::ReceiverExecuteMsg::ReceiveBeacon`.
+
+use cosmwasm_std::{Addr, Coin};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+pub use rand::msg::BeaconCallback;
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct InstantiateMsg {
+    /// The address of the rand contract
+    pub rand: String,
+    /// The chain hash of the drand network used for draws
+    pub network: String,
+    pub ticket_price: Coin,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ExecuteMsg {
+    /// Buys a ticket. Exactly the ticket price must be sent.
+    BuyTicket {},
+    /// Stops ticket sales and requests the next round of the network to pick the winner.
+    /// Funds sent along pay the job fee of the rand contract and the rest is set as a bounty
+    /// on that round to get it relayed quickly.
+    Draw {},
+    /// The callback of the rand contract. Pays the pot to the winner.
+    ReceiveBeacon(BeaconCallback),
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum QueryMsg {
+    /// Returns the state of the current draw
+    Lottery {},
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct LotteryResponse {
+    pub ticket_price: Coin,
+    /// One entry per ticket, in the order they were bought
+    pub players: Vec<Addr>,
+    /// The round the winner is drawn from. None if ticket sales are open.
+    pub draw_round: Option<u64>,
+    pub last_winner: Option<Addr>,
+}
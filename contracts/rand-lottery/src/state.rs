@@ -0,0 +1,46 @@
+use cosmwasm_std::{Addr, Coin, Storage};
+use cosmwasm_storage::{singleton, singleton_read, ReadonlySingleton, Singleton};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+const CONFIG_KEY: &[u8] = b"config";
+const LOTTERY_KEY: &[u8] = b"lottery";
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Config {
+    pub rand: Addr,
+    pub network: String,
+    pub ticket_price: Coin,
+}
+
+pub fn config(storage: &mut dyn Storage) -> Singleton<'_, Config> {
+    singleton(storage, CONFIG_KEY)
+}
+
+pub fn config_read(storage: &dyn Storage) -> ReadonlySingleton<'_, Config> {
+    singleton_read(storage, CONFIG_KEY)
+}
+
+/// A draw waiting for its beacon
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Draw {
+    pub round: u64,
+    pub job_id: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, JsonSchema)]
+pub struct Lottery {
+    pub players: Vec<Addr>,
+    pub draw: Option<Draw>,
+    /// The number of completed draws
+    pub draws: u64,
+    pub last_winner: Option<Addr>,
+}
+
+pub fn lottery(storage: &mut dyn Storage) -> Singleton<'_, Lottery> {
+    singleton(storage, LOTTERY_KEY)
+}
+
+pub fn lottery_read(storage: &dyn Storage) -> ReadonlySingleton<'_, Lottery> {
+    singleton_read(storage, LOTTERY_KEY)
+}
@@ -3,7 +3,11 @@ use std::fs::create_dir_all;
 
 use cosmwasm_schema::{export_schema, remove_schemas, schema_for};
 use rand::msg::{
-    BountiesResponse, ExecuteMsg, GetResponse, InstantiateMsg, LatestResponse, QueryMsg,
+    AdminResponse, BeaconResponse, BeaconsResponse, BountiesResponse, DeriveResponse, ExecuteMsg,
+    GetResponse, InstantiateMsg, JobsResponse, LatestResponse, MigrateMsg, NetworksResponse,
+    PickResponse, QueryMsg, RandAck, RandPacket, RandomIntResponse, ReceiverExecuteMsg,
+    RelayerResponse, RelayersResponse, RoundResponse, ShuffleResponse, TimeResponse,
+    WithdrawableResponse,
 };
 use rand::state::Config;
 
@@ -14,10 +18,28 @@ fn main() {
     remove_schemas(&out_dir).unwrap();
 
     export_schema(&schema_for!(InstantiateMsg), &out_dir);
+    export_schema(&schema_for!(MigrateMsg), &out_dir);
     export_schema(&schema_for!(ExecuteMsg), &out_dir);
     export_schema(&schema_for!(QueryMsg), &out_dir);
     export_schema(&schema_for!(Config), &out_dir);
     export_schema(&schema_for!(BountiesResponse), &out_dir);
+    export_schema(&schema_for!(BeaconResponse), &out_dir);
+    export_schema(&schema_for!(BeaconsResponse), &out_dir);
     export_schema(&schema_for!(GetResponse), &out_dir);
     export_schema(&schema_for!(LatestResponse), &out_dir);
+    export_schema(&schema_for!(NetworksResponse), &out_dir);
+    export_schema(&schema_for!(AdminResponse), &out_dir);
+    export_schema(&schema_for!(JobsResponse), &out_dir);
+    export_schema(&schema_for!(ReceiverExecuteMsg), &out_dir);
+    export_schema(&schema_for!(RoundResponse), &out_dir);
+    export_schema(&schema_for!(TimeResponse), &out_dir);
+    export_schema(&schema_for!(RandomIntResponse), &out_dir);
+    export_schema(&schema_for!(ShuffleResponse), &out_dir);
+    export_schema(&schema_for!(PickResponse), &out_dir);
+    export_schema(&schema_for!(DeriveResponse), &out_dir);
+    export_schema(&schema_for!(RelayerResponse), &out_dir);
+    export_schema(&schema_for!(RelayersResponse), &out_dir);
+    export_schema(&schema_for!(WithdrawableResponse), &out_dir);
+    export_schema(&schema_for!(RandPacket), &out_dir);
+    export_schema(&schema_for!(RandAck), &out_dir);
 }
@@ -0,0 +1,133 @@
+//! Writes the synthetic networks in tests/fixtures.
+//!
+//! Only a few real beacons of each drand network are checked in, so every scheme also gets a
+//! synthetic network with many consecutive rounds. They are signed with secret keys derived
+//! from the network name, which makes them reproducible but worthless outside of tests.
+//!
+//! cargo run --example synthetic_fixtures
+
+use std::fs::write;
+use std::path::PathBuf;
+
+use bls12_381::hash_to_curve::{ExpandMsgXmd, HashToCurve};
+use bls12_381::{G1Affine, G1Projective, G2Affine, G2Projective, Scalar};
+use drand_verify::derive_randomness;
+use rand::drand::{ChainInfo, DrandBeacon};
+use serde_json::json;
+use sha2::{Digest, Sha256};
+
+const DOMAIN_HASH_TO_G2: &[u8] = b"BLS_SIG_BLS12381G2_XMD:SHA-256_SSWU_RO_NUL_";
+const DOMAIN_HASH_TO_G1: &[u8] = b"BLS_SIG_BLS12381G1_XMD:SHA-256_SSWU_RO_NUL_";
+
+/// After the default mock time and before the later mock time of the tests
+const GENESIS_TIME: u64 = 1_600_000_000;
+const PERIOD: u64 = 3;
+const ROUNDS: u64 = 40;
+
+/// (name, scheme ID)
+const NETWORKS: [(&str, &str); 4] = [
+    ("synthetic-chained", "pedersen-bls-chained"),
+    ("synthetic-unchained", "pedersen-bls-unchained"),
+    ("synthetic-g1", "bls-unchained-on-g1"),
+    ("synthetic-rfc9380", "bls-unchained-g1-rfc9380"),
+];
+
+fn secret_key(name: &str) -> Scalar {
+    let mut wide = [0u8; 64];
+    wide[..32].copy_from_slice(&Sha256::digest(name.as_bytes()));
+    let upper = Sha256::digest(&wide[..32]);
+    wide[32..].copy_from_slice(&upper);
+    Scalar::from_bytes_wide(&wide)
+}
+
+fn hash_to_g1(msg: &[u8], domain: &[u8]) -> G1Projective {
+    HashToCurve::<ExpandMsgXmd<Sha256>>::hash_to_curve(msg, domain)
+}
+
+fn hash_to_g2(msg: &[u8], domain: &[u8]) -> G2Projective {
+    HashToCurve::<ExpandMsgXmd<Sha256>>::hash_to_curve(msg, domain)
+}
+
+fn public_key(scheme_id: &str, sk: &Scalar) -> Vec<u8> {
+    match scheme_id {
+        "pedersen-bls-chained" | "pedersen-bls-unchained" => {
+            G1Affine::from(G1Projective::generator() * sk)
+                .to_compressed()
+                .to_vec()
+        }
+        _ => G2Affine::from(G2Projective::generator() * sk)
+            .to_compressed()
+            .to_vec(),
+    }
+}
+
+fn sign(scheme_id: &str, sk: &Scalar, msg: &[u8]) -> Vec<u8> {
+    match scheme_id {
+        "pedersen-bls-chained" | "pedersen-bls-unchained" => {
+            G2Affine::from(hash_to_g2(msg, DOMAIN_HASH_TO_G2) * sk)
+                .to_compressed()
+                .to_vec()
+        }
+        // drand's fastnet hashes to G1 with the G2 domain, see drand-verify
+        "bls-unchained-on-g1" => G1Affine::from(hash_to_g1(msg, DOMAIN_HASH_TO_G2) * sk)
+            .to_compressed()
+            .to_vec(),
+        _ => G1Affine::from(hash_to_g1(msg, DOMAIN_HASH_TO_G1) * sk)
+            .to_compressed()
+            .to_vec(),
+    }
+}
+
+/// The message signed by drand, sha256(previous_signature || round)
+fn message(round: u64, previous_signature: &[u8]) -> Vec<u8> {
+    let mut hasher = Sha256::new();
+    hasher.update(previous_signature);
+    hasher.update(round.to_be_bytes());
+    hasher.finalize().to_vec()
+}
+
+fn main() {
+    let mut dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    dir.push("tests/fixtures");
+
+    for (name, scheme_id) in NETWORKS {
+        let sk = secret_key(name);
+        let pubkey = public_key(scheme_id, &sk);
+        let info = ChainInfo {
+            hash: hex::encode(Sha256::digest(&pubkey)),
+            public_key: pubkey.into(),
+            period: PERIOD,
+            genesis_time: GENESIS_TIME,
+            scheme_id: Some(scheme_id.to_string()),
+        };
+
+        let chained = scheme_id == "pedersen-bls-chained";
+        // Takes the place of the genesis seed for the first round
+        let mut previous_signature = Sha256::digest(name.as_bytes()).to_vec();
+        let mut beacons = vec![];
+        for round in 1..=ROUNDS {
+            let signed_previous = if chained {
+                &previous_signature[..]
+            } else {
+                &[]
+            };
+            let signature = sign(scheme_id, &sk, &message(round, signed_previous));
+            beacons.push(DrandBeacon {
+                round,
+                randomness: derive_randomness(&signature).to_vec().into(),
+                signature: signature.clone().into(),
+                previous_signature: signed_previous.to_vec().into(),
+            });
+            previous_signature = signature;
+        }
+
+        let fixture = json!({ "info": info, "beacons": beacons });
+        let path = dir.join(format!("{}.json", name));
+        write(
+            &path,
+            serde_json::to_string_pretty(&fixture).unwrap() + "\n",
+        )
+        .unwrap();
+        println!("{} has {} beacons", path.display(), ROUNDS);
+    }
+}
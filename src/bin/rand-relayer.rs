@@ -0,0 +1,654 @@
+//! Relays drand beacons to the rand contract.
+//!
+//! Polls a drand HTTP endpoint, verifies the beacons locally with the same code as the contract
+//! and prints ready to sign `ExecuteMsg::Add` or `ExecuteMsg::AddBatch` JSON to stdout, one line
+//! per poll. If a chain REST endpoint and the contract address are given, rounds with bounties
+//! are relayed first, highest bounty first, followed by the latest round. Bounties are ranked
+//! by their amount in one denom, which defaults to the first bounty denom of the contract.
+//!
+//! Every round is emitted at most once per process, so signing and broadcasting the output is
+//! left to the caller. Only rounds from the oldest bounty on are remembered, such that the
+//! memory of a long running relayer stays bounded.
+
+use std::collections::BTreeSet;
+use std::thread::sleep;
+use std::time::Duration;
+
+use cosmwasm_std::to_binary;
+use drand_verify::derive_randomness;
+use rand::drand::{ChainInfo, DrandBeacon};
+use rand::msg::{
+    BatchMode, BeaconSubmission, BountiesResponse, Bounty, ConfigResponse, ExecuteMsg, NetworkInfo,
+    QueryMsg,
+};
+use rand::verify::verify_beacon;
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+
+const USAGE: &str = "Usage: rand-relayer --chain-hash <hash> [--drand-url <url>] \
+[--lcd-url <url> --contract <address> [--denom <denom>]] [--interval <seconds>] [--batch-size <n>] \
+[--once]";
+
+const DEFAULT_DRAND_URL: &str = "https://api.drand.sh";
+const DEFAULT_BATCH_SIZE: usize = 10;
+/// Page size of the bounties query, which is the maximum the contract supports
+const BOUNTIES_PAGE_SIZE: u32 = 100;
+
+#[derive(Debug, Clone, PartialEq)]
+struct Args {
+    chain_hash: String,
+    drand_url: String,
+    lcd_url: Option<String>,
+    contract: Option<String>,
+    /// The denom bounties are ranked by
+    denom: Option<String>,
+    /// Seconds between two polls. Defaults to the period of the network.
+    interval: Option<u64>,
+    batch_size: usize,
+    once: bool,
+}
+
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Args, String> {
+    let mut chain_hash = None;
+    let mut parsed = Args {
+        chain_hash: String::new(),
+        drand_url: DEFAULT_DRAND_URL.to_string(),
+        lcd_url: None,
+        contract: None,
+        denom: None,
+        interval: None,
+        batch_size: DEFAULT_BATCH_SIZE,
+        once: false,
+    };
+    while let Some(arg) = args.next() {
+        if arg == "--once" {
+            parsed.once = true;
+            continue;
+        }
+        let value = args
+            .next()
+            .ok_or_else(|| format!("Missing value for {}", arg))?;
+        match arg.as_str() {
+            "--chain-hash" => chain_hash = Some(value),
+            "--drand-url" => parsed.drand_url = value.trim_end_matches('/').to_string(),
+            "--lcd-url" => parsed.lcd_url = Some(value.trim_end_matches('/').to_string()),
+            "--contract" => parsed.contract = Some(value),
+            "--denom" => parsed.denom = Some(value),
+            "--interval" => {
+                parsed.interval = Some(value.parse().map_err(|_| "Invalid interval")?);
+            }
+            "--batch-size" => {
+                parsed.batch_size = value.parse().map_err(|_| "Invalid batch size")?;
+            }
+            _ => return Err(format!("Unknown argument {}", arg)),
+        }
+    }
+    parsed.chain_hash = chain_hash.ok_or("Missing --chain-hash")?;
+    if parsed.lcd_url.is_some() != parsed.contract.is_some() {
+        return Err("--lcd-url and --contract must be used together".to_string());
+    }
+    if parsed.denom.is_some() && parsed.contract.is_none() {
+        return Err("--denom requires --contract".to_string());
+    }
+    if parsed.batch_size == 0 {
+        return Err("Invalid batch size".to_string());
+    }
+    Ok(parsed)
+}
+
+/// The response of a smart query via the REST endpoint of a chain
+#[derive(Deserialize)]
+struct SmartQueryResponse<T> {
+    data: T,
+}
+
+struct Relayer {
+    agent: ureq::Agent,
+    args: Args,
+    network: NetworkInfo,
+    /// Rounds that were emitted already
+    emitted: BTreeSet<u64>,
+}
+
+impl Relayer {
+    fn new(args: Args) -> Result<Self, String> {
+        let agent = ureq::AgentBuilder::new()
+            .timeout(Duration::from_secs(10))
+            .build();
+        let url = format!("{}/{}/info", args.drand_url, args.chain_hash);
+        let info: ChainInfo = get_json(&agent, &url)?;
+        if info.hash != args.chain_hash {
+            return Err(format!("{} serves chain {}", url, info.hash));
+        }
+        let mut relayer = Relayer {
+            agent,
+            network: info.network_info()?,
+            args,
+            emitted: BTreeSet::new(),
+        };
+        if relayer.args.denom.is_none() {
+            let config: Option<ConfigResponse> = relayer.query(&QueryMsg::Config {})?;
+            relayer.args.denom = config.and_then(|config| config.bounty_denoms.into_iter().next());
+        }
+        Ok(relayer)
+    }
+
+    /// Runs a smart query against the contract, or returns None if no contract is configured
+    fn query<T: DeserializeOwned>(&self, query: &QueryMsg) -> Result<Option<T>, String> {
+        let (lcd_url, contract) = match (&self.args.lcd_url, &self.args.contract) {
+            (Some(lcd_url), Some(contract)) => (lcd_url, contract),
+            _ => return Ok(None),
+        };
+        let url = format!("{}{}", lcd_url, smart_query_path(contract, query)?);
+        let response: SmartQueryResponse<T> = get_json(&self.agent, &url)?;
+        Ok(Some(response.data))
+    }
+
+    /// Fetches a beacon and verifies it against the pubkey of the network
+    fn beacon(&self, round: Option<u64>) -> Result<DrandBeacon, String> {
+        let path = round.map_or_else(|| "latest".to_string(), |round| round.to_string());
+        let url = format!(
+            "{}/{}/public/{}",
+            self.args.drand_url, self.args.chain_hash, path
+        );
+        let beacon: DrandBeacon = get_json(&self.agent, &url)?;
+        // A valid beacon of another round would be relayed in place of the requested one
+        if let Some(round) = round {
+            if beacon.round != round {
+                return Err(format!("{} served round {}", url, beacon.round));
+            }
+        }
+        let valid = verify_beacon(
+            self.network.scheme,
+            &self.network.pubkey,
+            beacon.round,
+            &beacon.previous_signature,
+            &beacon.signature,
+        )
+        .map_err(|err| err.to_string())?;
+        if !valid || beacon.randomness.as_slice() != derive_randomness(&beacon.signature) {
+            return Err(format!(
+                "Invalid beacon for round {} from {}",
+                beacon.round, url
+            ));
+        }
+        Ok(beacon)
+    }
+
+    /// All bounties up to `max_round`, or none if no contract is configured
+    fn bounties(&self, max_round: u64) -> Result<Vec<Bounty>, String> {
+        let mut bounties: Vec<Bounty> = vec![];
+        loop {
+            let query = QueryMsg::Bounties {
+                network: self.args.chain_hash.clone(),
+                start_after: bounties.last().map(|bounty| bounty.round),
+                limit: Some(BOUNTIES_PAGE_SIZE),
+                min_round: None,
+                max_round: Some(max_round),
+            };
+            let page: BountiesResponse = match self.query(&query)? {
+                Some(page) => page,
+                None => return Ok(vec![]),
+            };
+            let done = page.bounties.len() < BOUNTIES_PAGE_SIZE as usize;
+            bounties.extend(page.bounties);
+            if done {
+                return Ok(bounties);
+            }
+        }
+    }
+
+    /// Returns the message for all rounds that are worth relaying and were not emitted yet
+    fn poll(&mut self) -> Result<Option<ExecuteMsg>, String> {
+        let latest = self.beacon(None)?;
+        let bounties = self.bounties(latest.round)?;
+
+        // Rounds below the oldest bounty are neither relayed nor fetched again
+        let oldest = bounties
+            .iter()
+            .map(|bounty| bounty.round)
+            .min()
+            .map_or(latest.round, |round| round.min(latest.round));
+        self.emitted = self.emitted.split_off(&oldest);
+
+        let denom = self.args.denom.clone().unwrap_or_default();
+        let mut rounds = prioritize(bounties, &denom);
+        rounds.push(latest.round);
+
+        let mut beacons: Vec<BeaconSubmission> = vec![];
+        for round in rounds {
+            if beacons.len() == self.args.batch_size {
+                break;
+            }
+            if self.emitted.contains(&round) {
+                continue;
+            }
+            let beacon = if round == latest.round {
+                latest.clone()
+            } else {
+                // A round that cannot be fetched must not block the others and is retried
+                // in the next poll
+                match self.beacon(Some(round)) {
+                    Ok(beacon) => beacon,
+                    Err(err) => {
+                        eprintln!("Skipping round {}: {}", round, err);
+                        continue;
+                    }
+                }
+            };
+            self.emitted.insert(round);
+            beacons.push(beacon.into());
+        }
+
+        let network = self.args.chain_hash.clone();
+        Ok(match beacons.len() {
+            0 => None,
+            1 => {
+                let beacon = beacons.remove(0);
+                Some(ExecuteMsg::Add {
+                    network,
+                    round: beacon.round,
+                    previous_signature: beacon.previous_signature,
+                    signature: beacon.signature,
+                })
+            }
+            _ => Some(ExecuteMsg::AddBatch {
+                network,
+                beacons,
+                mode: BatchMode::Lenient,
+            }),
+        })
+    }
+}
+
+/// Orders the rounds by their bounty in `denom`, highest first. Amounts in other denoms are
+/// ignored because their values cannot be compared. Equal bounties are ordered by round.
+fn prioritize(mut bounties: Vec<Bounty>, denom: &str) -> Vec<u64> {
+    let amount = |bounty: &Bounty| -> u128 {
+        bounty
+            .amount
+            .iter()
+            .find(|coin| coin.denom == denom)
+            .map_or(0, |coin| coin.amount.u128())
+    };
+    bounties.sort_by(|a, b| amount(b).cmp(&amount(a)).then(a.round.cmp(&b.round)));
+    bounties.into_iter().map(|bounty| bounty.round).collect()
+}
+
+/// The path of a smart query in the REST API of a chain
+fn smart_query_path(contract: &str, query: &QueryMsg) -> Result<String, String> {
+    let encoded = to_binary(query).map_err(|err| err.to_string())?.to_base64();
+    let escaped = encoded
+        .replace('+', "%2B")
+        .replace('/', "%2F")
+        .replace('=', "%3D");
+    Ok(format!(
+        "/cosmwasm/wasm/v1/contract/{}/smart/{}",
+        contract, escaped
+    ))
+}
+
+fn get_json<T: DeserializeOwned>(agent: &ureq::Agent, url: &str) -> Result<T, String> {
+    let body = agent
+        .get(url)
+        .call()
+        .map_err(|err| err.to_string())?
+        .into_string()
+        .map_err(|err| format!("Reading {} failed: {}", url, err))?;
+    serde_json::from_str(&body).map_err(|err| format!("Invalid response from {}: {}", url, err))
+}
+
+fn main() {
+    let args = match parse_args(std::env::args().skip(1)) {
+        Ok(args) => args,
+        Err(err) => {
+            eprintln!("{}\n{}", err, USAGE);
+            std::process::exit(2);
+        }
+    };
+    let mut relayer = match Relayer::new(args) {
+        Ok(relayer) => relayer,
+        Err(err) => {
+            eprintln!("{}", err);
+            std::process::exit(1);
+        }
+    };
+    let interval = Duration::from_secs(relayer.args.interval.unwrap_or(relayer.network.period));
+
+    loop {
+        match relayer.poll() {
+            Ok(Some(msg)) => println!("{}", serde_json::to_string(&msg).unwrap()),
+            Ok(None) => {}
+            Err(err) => eprintln!("{}", err),
+        }
+        if relayer.args.once {
+            break;
+        }
+        sleep(interval);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cosmwasm_std::coin;
+    use rand::fixtures::{self, NetworkFixture};
+    use std::collections::HashMap;
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::TcpListener;
+    use std::sync::{Arc, Mutex};
+    use std::thread;
+
+    const MAINNET: &str = "8990e7a9aaed2ffed73dbd7092123d6f289930540d7651336225dc172e51b2ce";
+    const CONTRACT: &str = "wasm14hj2tavq8fpesdwxxcu44rty3hh90vhujrvcmstl4zr3txmfvw9s4hmalr";
+    const LATEST: u64 = 72785;
+
+    fn mainnet() -> NetworkFixture {
+        fixtures::network(fixtures::MAINNET)
+    }
+
+    fn submission(round: u64) -> BeaconSubmission {
+        mainnet().beacon(round).clone().into()
+    }
+
+    type Routes = Arc<Mutex<HashMap<String, String>>>;
+
+    /// Serves the given bodies by path on a local port and returns the base URL.
+    /// Routes can be changed while serving.
+    fn serve(routes: Routes) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let mut stream = stream.unwrap();
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                let mut request_line = String::new();
+                reader.read_line(&mut request_line).unwrap();
+                let mut header = String::new();
+                while reader.read_line(&mut header).unwrap() > 2 {
+                    header.clear();
+                }
+                let path = request_line.split_whitespace().nth(1).unwrap_or_default();
+                let (status, body) = match routes.lock().unwrap().get(path) {
+                    Some(body) => ("200 OK", body.clone()),
+                    None => ("404 Not Found", String::new()),
+                };
+                write!(
+                    stream,
+                    "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    status,
+                    body.len(),
+                    body
+                )
+                .unwrap();
+            }
+        });
+        url
+    }
+
+    fn mock_drand() -> HashMap<String, String> {
+        let mainnet = mainnet();
+        let hash = mainnet.chain_hash();
+        let mut routes = HashMap::new();
+        let info = serde_json::to_string(&mainnet.info).unwrap();
+        routes.insert(format!("/{}/info", hash), info);
+        for beacon in &mainnet.beacons {
+            let body = serde_json::to_string(beacon).unwrap();
+            routes.insert(format!("/{}/public/{}", hash, beacon.round), body);
+        }
+        let latest = serde_json::to_string(mainnet.beacon(LATEST)).unwrap();
+        routes.insert(format!("/{}/public/latest", hash), latest);
+        routes
+    }
+
+    fn mock_bounties(routes: &mut HashMap<String, String>, bounties: Vec<Bounty>) {
+        let query = QueryMsg::Bounties {
+            network: MAINNET.into(),
+            start_after: None,
+            limit: Some(BOUNTIES_PAGE_SIZE),
+            min_round: None,
+            max_round: Some(LATEST),
+        };
+        let body = serde_json::json!({ "data": BountiesResponse { bounties } });
+        routes.insert(
+            smart_query_path(CONTRACT, &query).unwrap(),
+            body.to_string(),
+        );
+
+        let config = ConfigResponse {
+            bounty_denoms: vec!["ucosm".into(), "uatom".into()],
+            clock_tolerance: 0,
+            bounty_expiry: 86_400,
+            claim_delay: None,
+            reject_duplicates: false,
+            job_fee: None,
+        };
+        let body = serde_json::json!({ "data": config });
+        routes.insert(
+            smart_query_path(CONTRACT, &QueryMsg::Config {}).unwrap(),
+            body.to_string(),
+        );
+    }
+
+    fn args(url: &str) -> Args {
+        parse_args(
+            [
+                "--chain-hash",
+                MAINNET,
+                "--drand-url",
+                url,
+                "--lcd-url",
+                url,
+                "--contract",
+                CONTRACT,
+            ]
+            .iter()
+            .map(|arg| arg.to_string()),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn parse_args_works() {
+        let parsed = parse_args(
+            ["--chain-hash", MAINNET, "--once", "--batch-size", "3"]
+                .iter()
+                .map(|arg| arg.to_string()),
+        )
+        .unwrap();
+        assert_eq!(
+            parsed,
+            Args {
+                chain_hash: MAINNET.into(),
+                drand_url: DEFAULT_DRAND_URL.into(),
+                lcd_url: None,
+                contract: None,
+                denom: None,
+                interval: None,
+                batch_size: 3,
+                once: true,
+            }
+        );
+
+        let parse = |args: &[&str]| parse_args(args.iter().map(|arg| arg.to_string()));
+        assert_eq!(parse(&[]).unwrap_err(), "Missing --chain-hash");
+        assert_eq!(
+            parse(&["--chain-hash", MAINNET, "--contract", CONTRACT]).unwrap_err(),
+            "--lcd-url and --contract must be used together"
+        );
+        assert_eq!(
+            parse(&["--chain-hash", MAINNET, "--denom", "ucosm"]).unwrap_err(),
+            "--denom requires --contract"
+        );
+        assert_eq!(
+            parse(&["--chain-hash"]).unwrap_err(),
+            "Missing value for --chain-hash"
+        );
+    }
+
+    #[test]
+    fn prioritize_orders_by_bounty_in_denom() {
+        let bounties = vec![
+            Bounty {
+                round: 10,
+                amount: vec![coin(5, "ucosm")],
+            },
+            Bounty {
+                round: 11,
+                amount: vec![coin(3, "uatom"), coin(4, "ucosm")],
+            },
+            Bounty {
+                round: 12,
+                amount: vec![coin(7, "ucosm")],
+            },
+        ];
+        assert_eq!(prioritize(bounties.clone(), "ucosm"), vec![12, 10, 11]);
+        assert_eq!(prioritize(bounties, "uatom"), vec![11, 10, 12]);
+    }
+
+    #[test]
+    fn poll_relays_bounties_first() {
+        let mut routes = mock_drand();
+        mock_bounties(
+            &mut routes,
+            vec![
+                Bounty {
+                    round: 42,
+                    amount: vec![coin(100, "ucosm")],
+                },
+                Bounty {
+                    round: 45,
+                    amount: vec![coin(500, "ucosm")],
+                },
+            ],
+        );
+        let url = serve(Arc::new(Mutex::new(routes)));
+
+        let mut relayer = Relayer::new(args(&url)).unwrap();
+        assert_eq!(
+            relayer.poll().unwrap(),
+            Some(ExecuteMsg::AddBatch {
+                network: MAINNET.into(),
+                beacons: vec![submission(45), submission(42), submission(LATEST)],
+                mode: BatchMode::Lenient,
+            })
+        );
+        // Nothing new to relay
+        assert_eq!(relayer.poll().unwrap(), None);
+    }
+
+    #[test]
+    fn poll_forgets_rounds_without_bounties() {
+        let mut routes = mock_drand();
+        mock_bounties(
+            &mut routes,
+            vec![
+                Bounty {
+                    round: 42,
+                    amount: vec![coin(100, "ucosm")],
+                },
+                Bounty {
+                    round: 45,
+                    amount: vec![coin(500, "ucosm")],
+                },
+            ],
+        );
+        let routes = Arc::new(Mutex::new(routes));
+        let url = serve(routes.clone());
+
+        let mut relayer = Relayer::new(args(&url)).unwrap();
+        relayer.poll().unwrap();
+        assert_eq!(
+            relayer.emitted.iter().copied().collect::<Vec<_>>(),
+            vec![42, 45, LATEST]
+        );
+
+        // Round 42 was added and its bounty paid
+        mock_bounties(
+            &mut routes.lock().unwrap(),
+            vec![Bounty {
+                round: 45,
+                amount: vec![coin(500, "ucosm")],
+            }],
+        );
+        assert_eq!(relayer.poll().unwrap(), None);
+        assert_eq!(
+            relayer.emitted.iter().copied().collect::<Vec<_>>(),
+            vec![45, LATEST]
+        );
+
+        mock_bounties(&mut routes.lock().unwrap(), vec![]);
+        assert_eq!(relayer.poll().unwrap(), None);
+        assert_eq!(
+            relayer.emitted.iter().copied().collect::<Vec<_>>(),
+            vec![LATEST]
+        );
+    }
+
+    #[test]
+    fn beacon_rejects_other_rounds() {
+        let mut routes = mock_drand();
+        mock_bounties(&mut routes, vec![]);
+        // A valid beacon, but not the requested one
+        let other = serde_json::to_string(mainnet().beacon(45)).unwrap();
+        routes.insert(format!("/{}/public/42", MAINNET), other);
+        let url = serve(Arc::new(Mutex::new(routes)));
+
+        let relayer = Relayer::new(args(&url)).unwrap();
+        assert_eq!(
+            relayer.beacon(Some(42)).unwrap_err(),
+            format!("{}/{}/public/42 served round 45", url, MAINNET)
+        );
+        assert_eq!(relayer.beacon(Some(45)).unwrap().round, 45);
+    }
+
+    #[test]
+    fn poll_skips_invalid_beacons() {
+        let mut routes = mock_drand();
+        mock_bounties(
+            &mut routes,
+            vec![Bounty {
+                round: 42,
+                amount: vec![coin(100, "ucosm")],
+            }],
+        );
+        // Serve the beacon of round 45 for round 42
+        let mut tampered = mainnet().beacon(45).clone();
+        tampered.round = 42;
+        routes.insert(
+            format!("/{}/public/42", MAINNET),
+            serde_json::to_string(&tampered).unwrap(),
+        );
+        let routes = Arc::new(Mutex::new(routes));
+        let url = serve(routes.clone());
+
+        let mut relayer = Relayer::new(args(&url)).unwrap();
+        let latest = submission(LATEST);
+        assert_eq!(
+            relayer.poll().unwrap(),
+            Some(ExecuteMsg::Add {
+                network: MAINNET.into(),
+                round: latest.round,
+                previous_signature: latest.previous_signature,
+                signature: latest.signature,
+            })
+        );
+
+        // The skipped round is retried once it is served correctly
+        let beacon = serde_json::to_string(mainnet().beacon(42)).unwrap();
+        routes
+            .lock()
+            .unwrap()
+            .insert(format!("/{}/public/42", MAINNET), beacon);
+        let retried = submission(42);
+        assert_eq!(
+            relayer.poll().unwrap(),
+            Some(ExecuteMsg::Add {
+                network: MAINNET.into(),
+                round: retried.round,
+                previous_signature: retried.previous_signature,
+                signature: retried.signature,
+            })
+        );
+    }
+}
@@ -0,0 +1,410 @@
+//! Verifies a beacon offline and prints its randomness.
+//!
+//! Runs exactly the signature verification and randomness derivation of `ExecuteMsg::Add`,
+//! which helps to debug `InvalidSignature` errors reported by relayers. The round is not
+//! compared to the block time, so a beacon that passes here may still be rejected with
+//! `RoundInFuture` by the contract.
+//!
+//! The pubkey is given as hex or as the drand chain info JSON (`/{chain_hash}/info`). The beacon
+//! is given as hex values, as the drand beacon JSON (`/{chain_hash}/public/{round}`) or as the
+//! response of the contract's `beacon` query. The latter checks that a stored beacon still
+//! verifies and that its stored randomness was derived from the signature. JSON arguments may be
+//! passed inline or as a path to a file.
+
+use std::fs;
+use std::path::Path;
+use std::process::exit;
+
+use drand_verify::derive_randomness;
+use rand::drand::{parse_scheme_id, ChainInfo, DrandBeacon};
+use rand::msg::{BeaconResponse, Scheme};
+use rand::verify::{check_pubkey, verify_beacon};
+
+const USAGE: &str = "Usage: rand-verify (--pubkey <hex> [--scheme <scheme id>] | --info <json>) \
+(--round <round> --signature <hex> [--previous-signature <hex>] | --beacon <json> | --stored <json>)";
+
+#[derive(Debug, Clone, PartialEq)]
+struct Args {
+    scheme: Scheme,
+    pubkey: Vec<u8>,
+    round: u64,
+    previous_signature: Vec<u8>,
+    signature: Vec<u8>,
+    /// The randomness claimed by the beacon JSON or stored by the contract, if any
+    randomness: Option<Vec<u8>>,
+}
+
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Args, String> {
+    let mut scheme = None;
+    let mut info: Option<ChainInfo> = None;
+    let mut pubkey = None;
+    let mut beacon: Option<DrandBeacon> = None;
+    let mut stored: Option<BeaconResponse> = None;
+    let mut round = None;
+    let mut previous_signature = None;
+    let mut signature = None;
+
+    while let Some(arg) = args.next() {
+        let value = args
+            .next()
+            .ok_or_else(|| format!("Missing value for {}", arg))?;
+        match arg.as_str() {
+            "--scheme" => {
+                scheme = Some(
+                    parse_scheme_id(&value).ok_or_else(|| format!("Unknown scheme {}", value))?,
+                )
+            }
+            "--info" => info = Some(parse_json(&value, "chain info")?),
+            "--pubkey" => pubkey = Some(parse_hex(&value, "pubkey")?),
+            "--beacon" => beacon = Some(parse_json(&value, "beacon")?),
+            "--stored" => stored = Some(parse_json(&value, "stored beacon")?),
+            "--round" => round = Some(value.parse().map_err(|_| "Invalid round")?),
+            "--previous-signature" => {
+                previous_signature = Some(parse_hex(&value, "previous signature")?)
+            }
+            "--signature" => signature = Some(parse_hex(&value, "signature")?),
+            _ => return Err(format!("Unknown argument {}", arg)),
+        }
+    }
+
+    let (scheme, pubkey) = match (info, pubkey) {
+        (Some(info), None) => (
+            scheme.map_or_else(|| info.scheme(), Ok)?,
+            info.public_key.to_vec(),
+        ),
+        (None, Some(pubkey)) => (scheme.unwrap_or_default(), pubkey),
+        _ => return Err("Expected either --pubkey or --info".to_string()),
+    };
+
+    let has_hex = round.is_some() || previous_signature.is_some() || signature.is_some();
+    match (beacon, stored) {
+        (Some(_), Some(_)) => Err("--beacon cannot be combined with --stored".to_string()),
+        (Some(beacon), None) => {
+            if has_hex {
+                return Err("--beacon cannot be combined with hex values".to_string());
+            }
+            Ok(Args {
+                scheme,
+                pubkey,
+                round: beacon.round,
+                previous_signature: beacon.previous_signature.to_vec(),
+                signature: beacon.signature.to_vec(),
+                randomness: Some(beacon.randomness.to_vec()),
+            })
+        }
+        (None, Some(BeaconResponse { beacon })) => {
+            if has_hex {
+                return Err("--stored cannot be combined with hex values".to_string());
+            }
+            Ok(Args {
+                scheme,
+                pubkey,
+                round: beacon.round,
+                previous_signature: beacon.previous_signature.to_vec(),
+                signature: beacon.signature.to_vec(),
+                randomness: Some(beacon.randomness.to_vec()),
+            })
+        }
+        (None, None) => Ok(Args {
+            scheme,
+            pubkey,
+            round: round.ok_or("Missing --round")?,
+            previous_signature: previous_signature.unwrap_or_default(),
+            signature: signature.ok_or("Missing --signature")?,
+            randomness: None,
+        }),
+    }
+}
+
+/// Reads JSON given inline or as a path to a file
+fn parse_json<T: serde::de::DeserializeOwned>(value: &str, what: &str) -> Result<T, String> {
+    let json = if value.trim_start().starts_with('{') || !Path::new(value).exists() {
+        value.to_string()
+    } else {
+        fs::read_to_string(value).map_err(|err| format!("Reading {} failed: {}", value, err))?
+    };
+    serde_json::from_str(&json).map_err(|err| format!("Invalid {} JSON: {}", what, err))
+}
+
+fn parse_hex(value: &str, what: &str) -> Result<Vec<u8>, String> {
+    hex::decode(value).map_err(|err| format!("Invalid {} hex: {}", what, err))
+}
+
+/// Verifies the beacon like the contract does and returns the randomness
+fn run(args: &Args) -> Result<[u8; 32], String> {
+    check_pubkey(args.scheme, &args.pubkey)
+        .map_err(|err| format!("Invalid pubkey for {:?}: {}", args.scheme, err))?;
+    let valid = verify_beacon(
+        args.scheme,
+        &args.pubkey,
+        args.round,
+        &args.previous_signature,
+        &args.signature,
+    )
+    .map_err(|err| err.to_string())?;
+    if !valid {
+        return Err("Signature verification failed".to_string());
+    }
+
+    let randomness = derive_randomness(&args.signature);
+    if let Some(claimed) = &args.randomness {
+        if claimed.as_slice() != randomness {
+            return Err(format!(
+                "Randomness {} does not match the signature, expected {}",
+                hex::encode(claimed),
+                hex::encode(randomness)
+            ));
+        }
+    }
+    Ok(randomness)
+}
+
+fn main() {
+    let args = match parse_args(std::env::args().skip(1)) {
+        Ok(args) => args,
+        Err(err) => {
+            eprintln!("{}\n{}", err, USAGE);
+            exit(2);
+        }
+    };
+    match run(&args) {
+        Ok(randomness) => println!("{}", hex::encode(randomness)),
+        Err(err) => {
+            eprintln!("{}", err);
+            exit(1);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
+    use cosmwasm_std::{Binary, Timestamp};
+    use rand::contract::{execute, instantiate, query};
+    use rand::fixtures::{self, NetworkFixture};
+    use rand::msg::{ExecuteMsg, InstantiateMsg, QueryMsg};
+    use std::env::temp_dir;
+
+    fn verify<S: AsRef<str>>(args: &[S]) -> Result<String, String> {
+        let args = parse_args(args.iter().map(|arg| arg.as_ref().to_string()))?;
+        run(&args).map(hex::encode)
+    }
+
+    /// The arguments to verify the beacon with hex values
+    fn hex_args(network: &NetworkFixture, beacon: &DrandBeacon) -> Vec<String> {
+        let mut args = vec![
+            "--pubkey".into(),
+            hex::encode(network.info.public_key.as_slice()),
+        ];
+        if let Some(scheme_id) = &network.info.scheme_id {
+            args.extend(["--scheme".into(), scheme_id.clone()]);
+        }
+        args.extend(["--round".into(), beacon.round.to_string()]);
+        if !beacon.previous_signature.is_empty() {
+            args.extend([
+                "--previous-signature".into(),
+                hex::encode(beacon.previous_signature.as_slice()),
+            ]);
+        }
+        args.extend([
+            "--signature".into(),
+            hex::encode(beacon.signature.as_slice()),
+        ]);
+        args
+    }
+
+    fn to_json<T: serde::Serialize>(value: &T) -> String {
+        serde_json::to_string(value).unwrap()
+    }
+
+    #[test]
+    fn verifies_hex_arguments() {
+        for network in fixtures::networks() {
+            for beacon in &network.beacons {
+                let randomness = verify(&hex_args(&network, beacon)).unwrap();
+                assert_eq!(
+                    randomness,
+                    hex::encode(beacon.randomness.as_slice()),
+                    "round {} of {}",
+                    beacon.round,
+                    network.name
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn verifies_drand_json() {
+        for network in fixtures::networks() {
+            let info = to_json(&network.info);
+            for beacon in &network.beacons {
+                let randomness = verify(&["--info", &info, "--beacon", &to_json(beacon)]).unwrap();
+                assert_eq!(randomness, hex::encode(beacon.randomness.as_slice()));
+            }
+        }
+
+        let mainnet = fixtures::network(fixtures::MAINNET);
+        let beacon = mainnet.beacon(72785);
+        let path = temp_dir().join("rand-verify-beacon.json");
+        fs::write(&path, to_json(beacon)).unwrap();
+        let randomness = verify(&[
+            "--pubkey",
+            &hex::encode(mainnet.info.public_key.as_slice()),
+            "--beacon",
+            path.to_str().unwrap(),
+        ])
+        .unwrap();
+        assert_eq!(randomness, hex::encode(beacon.randomness.as_slice()));
+    }
+
+    /// Adds the beacon to a contract and returns the response of the beacon query
+    fn store(network: &NetworkFixture, beacon: &DrandBeacon) -> BeaconResponse {
+        let mut deps = mock_dependencies();
+        let mut env = mock_env();
+        env.block.time = Timestamp::from_seconds(1_700_000_000);
+        let msg = InstantiateMsg {
+            networks: vec![network.network_info()],
+            bounty_denoms: vec!["ucosm".into()],
+            clock_tolerance: 0,
+            bounty_expiry: 86_400,
+            claim_delay: None,
+            reject_duplicates: false,
+            admin: None,
+            job_fee: None,
+        };
+        instantiate(deps.as_mut(), env.clone(), mock_info("creator", &[]), msg).unwrap();
+        let msg = ExecuteMsg::Add {
+            network: network.chain_hash().into(),
+            round: beacon.round,
+            previous_signature: beacon.previous_signature.clone(),
+            signature: beacon.signature.clone(),
+        };
+        execute(deps.as_mut(), env.clone(), mock_info("relayer", &[]), msg).unwrap();
+        let msg = QueryMsg::Beacon {
+            network: network.chain_hash().into(),
+            round: beacon.round,
+        };
+        serde_json::from_slice(&query(deps.as_ref(), env, msg).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn verifies_stored_beacons() {
+        for network in fixtures::networks() {
+            let info = to_json(&network.info);
+            for beacon in &network.beacons {
+                let stored = to_json(&store(&network, beacon));
+                let randomness = verify(&["--info", &info, "--stored", &stored]).unwrap();
+                assert_eq!(randomness, hex::encode(beacon.randomness.as_slice()));
+            }
+        }
+
+        // Stored randomness that was not derived from the signature
+        let mainnet = fixtures::network(fixtures::MAINNET);
+        let mut stored = store(&mainnet, mainnet.beacon(72785));
+        stored.beacon.randomness = vec![0; 32].into();
+        let path = temp_dir().join("rand-verify-stored.json");
+        fs::write(&path, to_json(&stored)).unwrap();
+        let err = verify(&[
+            "--info",
+            &to_json(&mainnet.info),
+            "--stored",
+            path.to_str().unwrap(),
+        ])
+        .unwrap_err();
+        assert!(err.starts_with("Randomness 0000"), "{}", err);
+    }
+
+    #[test]
+    fn rejects_invalid_beacons() {
+        let mainnet = fixtures::network(fixtures::MAINNET);
+        let beacon = mainnet.beacon(72785);
+
+        // Wrong round
+        let mut args = hex_args(&mainnet, beacon);
+        let round = args.iter().position(|arg| arg == "--round").unwrap() + 1;
+        args[round] = "72786".into();
+        let err = verify(&args).unwrap_err();
+        assert_eq!(err, "Signature verification failed");
+
+        // Chained beacon without previous signature
+        let unchained = DrandBeacon {
+            previous_signature: Binary::default(),
+            ..beacon.clone()
+        };
+        let err = verify(&hex_args(&mainnet, &unchained)).unwrap_err();
+        assert_eq!(err, "Signature verification failed");
+
+        // Randomness not derived from the signature
+        let tampered = DrandBeacon {
+            randomness: vec![0; 32].into(),
+            ..beacon.clone()
+        };
+        let err = verify(&[
+            "--info",
+            &to_json(&mainnet.info),
+            "--beacon",
+            &to_json(&tampered),
+        ])
+        .unwrap_err();
+        assert_eq!(
+            err,
+            format!(
+                "Randomness {} does not match the signature, expected {}",
+                "00".repeat(32),
+                hex::encode(beacon.randomness.as_slice())
+            )
+        );
+
+        // Mainnet pubkey with a scheme that expects the pubkey on G2
+        let err = verify(&[
+            "--pubkey",
+            &hex::encode(mainnet.info.public_key.as_slice()),
+            "--scheme",
+            "bls-unchained-g1-rfc9380",
+            "--round",
+            "123",
+            "--signature",
+            &hex::encode(beacon.signature.as_slice()),
+        ])
+        .unwrap_err();
+        assert!(
+            err.starts_with("Invalid pubkey for BlsUnchainedG1Rfc9380"),
+            "{}",
+            err
+        );
+    }
+
+    #[test]
+    fn parse_args_rejects_incomplete_input() {
+        let mainnet = fixtures::network(fixtures::MAINNET);
+        let pubkey = hex::encode(mainnet.info.public_key.as_slice());
+        let beacon = to_json(mainnet.beacon(72785));
+        let parse = |args: &[&str]| parse_args(args.iter().map(|arg| arg.to_string()));
+        assert_eq!(
+            parse(&["--round", "1"]).unwrap_err(),
+            "Expected either --pubkey or --info"
+        );
+        assert_eq!(
+            parse(&["--pubkey", &pubkey, "--round", "1"]).unwrap_err(),
+            "Missing --signature"
+        );
+        assert_eq!(
+            parse(&["--pubkey", &pubkey, "--beacon", &beacon, "--round", "1"]).unwrap_err(),
+            "--beacon cannot be combined with hex values"
+        );
+        let stored = to_json(&store(&mainnet, mainnet.beacon(72785)));
+        assert_eq!(
+            parse(&["--pubkey", &pubkey, "--stored", &stored, "--round", "1"]).unwrap_err(),
+            "--stored cannot be combined with hex values"
+        );
+        assert_eq!(
+            parse(&["--pubkey", &pubkey, "--stored", &stored, "--beacon", &beacon]).unwrap_err(),
+            "--beacon cannot be combined with --stored"
+        );
+        assert_eq!(
+            parse(&["--scheme", "foo"]).unwrap_err(),
+            "Unknown scheme foo"
+        );
+    }
+}
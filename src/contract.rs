@@ -1,26 +1,62 @@
+#[cfg(not(feature = "library"))]
 use cosmwasm_std::entry_point;
 use cosmwasm_std::{
-    coins, to_binary, BankMsg, Binary, Deps, DepsMut, Env, MessageInfo, Order, Response, StdResult,
-    Storage, SubMsg,
+    to_binary, Addr, BankMsg, Binary, Coin, Deps, DepsMut, Env, Event, MessageInfo, Order, Reply,
+    Response, StdError, StdResult, Storage, SubMsg, SubMsgResult, Timestamp, Uint128,
 };
-use drand_verify::{derive_randomness, g1_from_variable, verify};
+use drand_verify::derive_randomness;
 
 use crate::errors::ContractError;
+use crate::events::{bounty_paid_events, bounty_set_events, drand_beacon_event};
+use crate::ibc::take_deliveries;
+use crate::migrations::MIGRATIONS;
 use crate::msg::{
-    BountiesResponse, Bounty, ConfigResponse, ExecuteMsg, GetResponse, InstantiateMsg,
-    LatestResponse, QueryMsg,
+    bounty_commitment, AdminResponse, BatchMode, BeaconCallback, BeaconResponse, BeaconSubmission,
+    BeaconsResponse, BountiesResponse, Bounty, ConfigResponse, DeriveResponse, ExecuteMsg,
+    GetResponse, InstantiateMsg, JobsResponse, LatestResponse, MigrateMsg, NetworkInfo,
+    NetworksResponse, PickResponse, QueryMsg, RandomIntResponse, RelayerResponse, RelayersResponse,
+    RoundRandomness, RoundResponse, ShuffleResponse, SortOrder, TimeResponse, WithdrawableResponse,
 };
+use crate::randomness::{derive_sub_randomness, RandomStream, DERIVATION_VERSION};
 use crate::state::{
-    beacons_storage, beacons_storage_read, bounties_storage, bounties_storage_read, config,
-    config_read, Config,
+    beacons_storage, beacons_storage_read, bounties_storage, bounties_storage_read,
+    commitments_storage, commitments_storage_read, config, config_read, contributions_storage,
+    contributions_storage_read, jobs_storage, jobs_storage_read, networks_storage,
+    networks_storage_read, relayers_storage, relayers_storage_read, Beacon, Commitment, Config,
+    Job, Network,
 };
+use crate::verify::{check_pubkey, verify_beacon};
 
-use cw2::set_contract_version;
+use cw2::{get_contract_version, set_contract_version};
+use semver::Version;
 
 const CONTRACT_NAME: &str = "crates.io:rand";
 const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
 
-#[entry_point]
+/// Upper bound for the number of elements in `Shuffle` and `Pick` queries to keep their gas usage bounded
+const MAX_RANDOM_ELEMENTS: u64 = 1000;
+
+/// Seconds ahead of the block time in which a round must be published to accept a bounty,
+/// such that a mistyped round cannot lock funds for years
+const MAX_BOUNTY_LEAD: u64 = 365 * 24 * 60 * 60;
+
+/// Default and maximum page sizes of paginated queries
+const DEFAULT_LIMIT: u32 = 30;
+const MAX_LIMIT: u32 = 100;
+
+/// Reply ID of job callbacks. Replies are only received for failed callbacks.
+pub const CALLBACK_REPLY_ID: u64 = 1;
+
+/// Gas available to a single job callback. Running out of gas is reported like any other
+/// failed callback instead of aborting the beacon submission.
+pub const CALLBACK_GAS_LIMIT: u64 = 500_000;
+
+/// Upper bound for the number of pending jobs of a round and for the callbacks of a single
+/// `Add` or `AddBatch`. Together with `CALLBACK_GAS_LIMIT` this caps the callback gas of a
+/// message at 5M, such that consumers cannot make a round too expensive to add.
+const MAX_JOBS_PER_ROUND: usize = 10;
+
+#[cfg_attr(not(feature = "library"), entry_point)]
 pub fn instantiate(
     deps: DepsMut,
     _env: Env,
@@ -28,14 +64,169 @@ pub fn instantiate(
     msg: InstantiateMsg,
 ) -> Result<Response, ContractError> {
     set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
-    config(deps.storage).save(&Config {
-        pubkey: msg.pubkey,
-        bounty_denom: msg.bounty_denom,
-    })?;
+    let admin = msg
+        .admin
+        .map(|admin| deps.api.addr_validate(&admin))
+        .transpose()?;
+    let config_data = Config {
+        bounty_denoms: msg.bounty_denoms,
+        clock_tolerance: msg.clock_tolerance,
+        bounty_expiry: msg.bounty_expiry,
+        claim_delay: msg.claim_delay,
+        reject_duplicates: msg.reject_duplicates,
+        admin,
+        job_fee: msg.job_fee.filter(|fee| !fee.amount.is_zero()),
+    };
+    check_job_fee(&config_data)?;
+    config(deps.storage).save(&config_data)?;
+    for network in msg.networks {
+        add_network(deps.storage, network)?;
+    }
     Ok(Response::default())
 }
 
-#[entry_point]
+pub(crate) fn add_network(
+    storage: &mut dyn Storage,
+    info: NetworkInfo,
+) -> Result<(), ContractError> {
+    validate_network(&info)?;
+    if networks_storage_read(storage)
+        .may_load(info.chain_hash.as_bytes())?
+        .is_some()
+    {
+        return Err(ContractError::DuplicateNetwork {
+            chain_hash: info.chain_hash,
+        });
+    }
+    save_network(storage, info)
+}
+
+/// Checks the chain hash, period, pubkey and sample beacon of a network. Public such that
+/// contracts receiving beacons over IBC accept exactly the same networks.
+pub fn validate_network(info: &NetworkInfo) -> Result<(), ContractError> {
+    if !is_valid_chain_hash(&info.chain_hash) {
+        return Err(ContractError::InvalidChainHash {
+            chain_hash: info.chain_hash.clone(),
+        });
+    }
+    if info.period == 0 {
+        return Err(ContractError::InvalidPeriod {});
+    }
+    check_pubkey(info.scheme, &info.pubkey).map_err(|reason| ContractError::MalformedPubkey {
+        chain_hash: info.chain_hash.clone(),
+        reason,
+    })?;
+    if let Some(sample) = &info.sample_beacon {
+        let valid = verify_beacon(
+            info.scheme,
+            &info.pubkey,
+            sample.round,
+            &sample.previous_signature,
+            &sample.signature,
+        )
+        .map_err(|_| ContractError::InvalidPubkey {})?;
+        if !valid {
+            return Err(ContractError::InvalidSampleBeacon {
+                chain_hash: info.chain_hash.clone(),
+                round: sample.round,
+            });
+        }
+    }
+    Ok(())
+}
+
+/// The job fee is added to the bounty of the requested round, so it must be in a bounty denom
+fn check_job_fee(config: &Config) -> Result<(), ContractError> {
+    match &config.job_fee {
+        Some(fee) if !config.bounty_denoms.contains(&fee.denom) => {
+            Err(ContractError::UnacceptedDenom {
+                denom: fee.denom.clone(),
+            })
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Stores a network, replacing an existing network with the same chain hash
+fn save_network(storage: &mut dyn Storage, info: NetworkInfo) -> Result<(), ContractError> {
+    let NetworkInfo {
+        chain_hash,
+        pubkey,
+        scheme,
+        genesis_time,
+        period,
+        ..
+    } = info;
+    networks_storage(storage).save(
+        chain_hash.as_bytes(),
+        &Network {
+            pubkey,
+            scheme,
+            genesis_time,
+            period,
+        },
+    )?;
+    Ok(())
+}
+
+/// Chain hashes are 32 bytes sha256 digests in lowercase hex
+fn is_valid_chain_hash(chain_hash: &str) -> bool {
+    chain_hash.len() == 64
+        && chain_hash
+            .chars()
+            .all(|c| c.is_ascii_digit() || ('a'..='f').contains(&c))
+}
+
+pub(crate) fn load_network(storage: &dyn Storage, network: &str) -> Result<Network, ContractError> {
+    networks_storage_read(storage)
+        .may_load(network.as_bytes())?
+        .ok_or_else(|| ContractError::UnknownNetwork {
+            network: network.to_string(),
+        })
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn migrate(deps: DepsMut, env: Env, msg: MigrateMsg) -> Result<Response, ContractError> {
+    let stored = get_contract_version(deps.storage)?;
+    if stored.contract != CONTRACT_NAME {
+        return Err(ContractError::ForeignContract {
+            contract: stored.contract,
+        });
+    }
+    let from = parse_version(&stored.version)?;
+    if from > parse_version(CONTRACT_VERSION)? {
+        return Err(ContractError::CannotDowngrade {
+            from: stored.version,
+            to: CONTRACT_VERSION.to_string(),
+        });
+    }
+
+    for (version, migration) in MIGRATIONS {
+        if from < parse_version(version)? {
+            if !migration(deps.storage, &env, &msg)? {
+                // The version is only updated once the migration completed, such that
+                // migrating again resumes it
+                return Ok(Response::new()
+                    .add_attribute("from_version", stored.version)
+                    .add_attribute("pending_migration", *version));
+            }
+            set_contract_version(deps.storage, CONTRACT_NAME, *version)?;
+        }
+    }
+
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+    Ok(Response::new()
+        .add_attribute("from_version", stored.version)
+        .add_attribute("to_version", CONTRACT_VERSION))
+}
+
+fn parse_version(version: &str) -> Result<Version, ContractError> {
+    Version::parse(version).map_err(|_| ContractError::InvalidVersion {
+        version: version.to_string(),
+    })
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
 pub fn execute(
     deps: DepsMut,
     env: Env,
@@ -43,468 +234,4586 @@ pub fn execute(
     msg: ExecuteMsg,
 ) -> Result<Response, ContractError> {
     match msg {
-        ExecuteMsg::SetBounty { round } => try_set_bounty(deps, info, round),
+        ExecuteMsg::SetBounty { network, round } => try_set_bounty(deps, env, info, network, round),
         ExecuteMsg::Add {
+            network,
             round,
             previous_signature,
             signature,
-        } => try_add(deps, env, info, round, previous_signature, signature),
+        } => try_add(
+            deps,
+            env,
+            info,
+            network,
+            round,
+            previous_signature,
+            signature,
+        ),
+        ExecuteMsg::AddBatch {
+            network,
+            beacons,
+            mode,
+        } => try_add_batch(deps, env, info, network, beacons, mode),
+        ExecuteMsg::RequestBeacon {
+            network,
+            round,
+            job_id,
+        } => try_request_beacon(deps, env, info, network, round, job_id),
+        ExecuteMsg::RequestBeaconAfter {
+            network,
+            after,
+            job_id,
+        } => {
+            let round = load_network(deps.storage, &network)?.next_round_after(after);
+            try_request_beacon(deps, env, info, network, round, job_id)
+        }
+        ExecuteMsg::WithdrawBounty { network, round } => {
+            try_withdraw_bounty(deps, env, info, network, round)
+        }
+        ExecuteMsg::UpdateConfig {
+            bounty_denoms,
+            clock_tolerance,
+            bounty_expiry,
+            job_fee,
+            claim_delay,
+            reject_duplicates,
+            networks,
+        } => try_update_config(
+            deps,
+            info,
+            bounty_denoms,
+            clock_tolerance,
+            bounty_expiry,
+            job_fee,
+            claim_delay,
+            reject_duplicates,
+            networks,
+        ),
+        ExecuteMsg::TransferAdmin { admin } => {
+            let admin = deps.api.addr_validate(&admin)?;
+            try_set_admin(deps, info, Some(admin))
+        }
+        ExecuteMsg::RenounceAdmin {} => try_set_admin(deps, info, None),
+        ExecuteMsg::CommitBounty {
+            network,
+            round,
+            commitment,
+        } => try_commit_bounty(deps, env, info, network, round, commitment),
+        ExecuteMsg::ClaimBounty { network, round } => {
+            try_claim_bounty(deps, env, info, network, round)
+        }
+    }
+}
+
+/// Loads the config and ensures the sender is its admin
+fn load_config_as_admin(storage: &dyn Storage, sender: &Addr) -> Result<Config, ContractError> {
+    let config = config_read(storage).load()?;
+    match &config.admin {
+        Some(admin) if admin == sender => Ok(config),
+        _ => Err(ContractError::Unauthorized {}),
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn try_update_config(
+    deps: DepsMut,
+    info: MessageInfo,
+    bounty_denoms: Option<Vec<String>>,
+    clock_tolerance: Option<u64>,
+    bounty_expiry: Option<u64>,
+    job_fee: Option<Coin>,
+    claim_delay: Option<u64>,
+    reject_duplicates: Option<bool>,
+    networks: Vec<NetworkInfo>,
+) -> Result<Response, ContractError> {
+    let mut updated = load_config_as_admin(deps.storage, &info.sender)?;
+
+    let mut event = Event::new("update_config");
+    if let Some(bounty_denoms) = bounty_denoms {
+        event = event.add_attribute("bounty_denoms", bounty_denoms.join(","));
+        updated.bounty_denoms = bounty_denoms;
+    }
+    if let Some(clock_tolerance) = clock_tolerance {
+        event = event.add_attribute("clock_tolerance", clock_tolerance.to_string());
+        updated.clock_tolerance = clock_tolerance;
+    }
+    if let Some(bounty_expiry) = bounty_expiry {
+        event = event.add_attribute("bounty_expiry", bounty_expiry.to_string());
+        updated.bounty_expiry = bounty_expiry;
+    }
+    if let Some(job_fee) = job_fee {
+        event = event.add_attribute("job_fee", job_fee.to_string());
+        updated.job_fee = Some(job_fee).filter(|fee| !fee.amount.is_zero());
+    }
+    if let Some(claim_delay) = claim_delay {
+        event = event.add_attribute("claim_delay", claim_delay.to_string());
+        updated.claim_delay = Some(claim_delay).filter(|delay| *delay != 0);
+    }
+    if let Some(reject_duplicates) = reject_duplicates {
+        event = event.add_attribute("reject_duplicates", reject_duplicates.to_string());
+        updated.reject_duplicates = reject_duplicates;
     }
+    check_job_fee(&updated)?;
+    for network in networks {
+        validate_network(&network)?;
+        event = event.add_attribute("network", network.chain_hash.clone());
+        save_network(deps.storage, network)?;
+    }
+    config(deps.storage).save(&updated)?;
+
+    Ok(Response::new().add_event(event))
+}
+
+/// Transfers the admin role to `admin` or renounces it if `admin` is None
+pub fn try_set_admin(
+    deps: DepsMut,
+    info: MessageInfo,
+    admin: Option<Addr>,
+) -> Result<Response, ContractError> {
+    let mut updated = load_config_as_admin(deps.storage, &info.sender)?;
+    updated.admin = admin;
+    config(deps.storage).save(&updated)?;
+
+    let event = Event::new("update_admin")
+        .add_attribute("previous_admin", info.sender)
+        .add_attribute(
+            "admin",
+            updated
+                .admin
+                .map(|admin| admin.into_string())
+                .unwrap_or_default(),
+        );
+    Ok(Response::new().add_event(event))
 }
 
 pub fn try_set_bounty(
     deps: DepsMut,
+    env: Env,
     info: MessageInfo,
+    network: String,
     round: u64,
 ) -> Result<Response, ContractError> {
-    let denom = config_read(deps.storage).load()?.bounty_denom;
+    let network_info = load_network(deps.storage, &network)?;
+    // A bounty on a published round would not incentivize anything
+    if round <= network_info.round_at(env.block.time) {
+        return Err(ContractError::RoundAlreadyPublished { round });
+    }
+    let max_round = network_info.round_at(env.block.time.plus_seconds(MAX_BOUNTY_LEAD));
+    if round > max_round {
+        return Err(ContractError::RoundTooFarAhead { round, max_round });
+    }
+    let denoms = config_read(deps.storage).load()?.bounty_denoms;
 
-    let matching_coin = info.funds.iter().find(|fund| fund.denom == denom);
-    let sent_amount: u128 = match matching_coin {
-        Some(coin) => coin.amount.into(),
-        None => {
-            return Err(ContractError::NoFundsSent {
-                expected_denom: denom,
-            });
-        }
-    };
+    let (bounty, sent) = contribute_bounty(
+        deps.storage,
+        &denoms,
+        &network,
+        round,
+        &info.sender,
+        info.funds,
+    )?;
+    if sent.is_empty() {
+        return Err(ContractError::NoFundsSent {
+            expected_denoms: denoms.join(","),
+        });
+    }
+
+    Ok(Response::new()
+        .add_attribute("bounty", coins_to_string(&bounty))
+        .add_events(bounty_set_events(&network, round, &info.sender, &sent)))
+}
+
+/// Adds the funds to the bounty of a round and records them as contribution of the sender.
+/// Returns the new bounty and the added funds, which exclude zero amounts.
+fn contribute_bounty(
+    storage: &mut dyn Storage,
+    denoms: &[String],
+    network: &str,
+    round: u64,
+    sender: &Addr,
+    funds: Vec<Coin>,
+) -> Result<(Vec<Coin>, Vec<Coin>), ContractError> {
+    if let Some(coin) = funds.iter().find(|fund| !denoms.contains(&fund.denom)) {
+        return Err(ContractError::UnacceptedDenom {
+            denom: coin.denom.clone(),
+        });
+    }
+    let sent: Vec<Coin> = funds
+        .into_iter()
+        .filter(|fund| !fund.amount.is_zero())
+        .collect();
+    let mut bounty = get_bounty(storage, network, round)?;
+    if sent.is_empty() {
+        return Ok((bounty, sent));
+    }
 
-    let current = get_bounty(deps.storage, round)?;
-    let new_value = current + sent_amount;
-    set_bounty(deps.storage, round, new_value);
+    add_coins(&mut bounty, &sent);
+    set_bounty(storage, network, round, &bounty)?;
 
-    Ok(Response::new().add_attribute("bounty", new_value.to_string()))
+    let mut contributed = get_contribution(storage, network, round, sender)?;
+    add_coins(&mut contributed, &sent);
+    set_contribution(storage, network, round, sender, &contributed)?;
+    Ok((bounty, sent))
 }
 
-pub fn try_add(
+pub fn try_withdraw_bounty(
     deps: DepsMut,
-    _env: Env,
+    env: Env,
     info: MessageInfo,
+    network: String,
     round: u64,
-    previous_signature: Binary,
-    signature: Binary,
 ) -> Result<Response, ContractError> {
-    let Config {
-        pubkey,
-        bounty_denom,
-        ..
-    } = config_read(deps.storage).load()?;
-    let pk = g1_from_variable(&pubkey).map_err(|_| ContractError::InvalidPubkey {})?;
-    let valid = verify(
-        &pk,
-        round,
-        previous_signature.as_slice(),
-        signature.as_slice(),
-    )
-    .unwrap_or(false);
+    let network_info = load_network(deps.storage, &network)?;
+    let bounty_expiry = config_read(deps.storage).load()?.bounty_expiry;
 
-    if !valid {
-        return Err(ContractError::InvalidSignature {});
+    let expires_at = bounty_expires_at(&network_info, round, bounty_expiry)
+        .ok_or(ContractError::InvalidRound { round })?;
+    if env.block.time.seconds() < expires_at {
+        return Err(ContractError::BountyNotExpired { round, expires_at });
+    }
+
+    // Contributions are removed when the round is added, so this also
+    // covers bounties that were already paid out.
+    let amount = get_contribution(deps.storage, &network, round, &info.sender)?;
+    if amount.is_empty() {
+        return Err(ContractError::NoContribution { round });
+    }
+    set_contribution(deps.storage, &network, round, &info.sender, &[])?;
+
+    let mut remaining = get_bounty(deps.storage, &network, round)?;
+    subtract_coins(&mut remaining, &amount)?;
+    set_bounty(deps.storage, &network, round, &remaining)?;
+    // Commitments are worthless once nothing is left to claim
+    if remaining.is_empty() {
+        clear_commitments(deps.storage, &network, round)?;
     }
 
-    let randomness = derive_randomness(&signature);
-    beacons_storage(deps.storage).set(&round.to_be_bytes(), &randomness);
+    Ok(Response::new()
+        .add_attribute("round", round.to_string())
+        .add_attribute("withdrawn", coins_to_string(&amount))
+        .add_message(BankMsg::Send {
+            to_address: info.sender.to_string(),
+            amount,
+        }))
+}
+
+/// The time in seconds since epoch from which on the bounty of `round` can be withdrawn
+fn bounty_expires_at(network_info: &Network, round: u64, bounty_expiry: u64) -> Option<u64> {
+    network_info
+        .time_of_round(round)?
+        .seconds()
+        .checked_add(bounty_expiry)
+}
+
+pub fn try_add(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    network: String,
+    round: u64,
+    previous_signature: Binary,
+    signature: Binary,
+) -> Result<Response, ContractError> {
+    let network_info = load_network(deps.storage, &network)?;
+    let config = config_read(deps.storage).load()?;
 
-    let bounty = get_bounty(deps.storage, round)?;
+    let result = add_beacon(
+        deps.storage,
+        &env,
+        &info.sender,
+        &network,
+        &network_info,
+        &config,
+        round,
+        &previous_signature,
+        &signature,
+    );
+    let added = match result {
+        Err(ContractError::BeaconExists { round }) if !config.reject_duplicates => {
+            return Ok(Response::new().add_attribute("already_known", round.to_string()));
+        }
+        result => result?,
+    };
 
     let mut messages: Vec<SubMsg> = vec![];
-    if bounty != 0 {
+    if !added.bounty.is_empty() {
         let msg = BankMsg::Send {
             to_address: info.sender.to_string(),
-            amount: coins(bounty, bounty_denom),
+            amount: added.bounty,
         };
         messages.push(SubMsg::new(msg));
-        clear_bounty(deps.storage, round);
     }
+    messages.extend(added.callbacks);
+    messages.extend(added.deliveries);
 
     Ok(Response::new()
-        .add_attribute("randomness", Binary::from(randomness).to_base64())
+        .add_attribute("randomness", Binary::from(added.randomness).to_base64())
+        .add_events(added.events)
         .add_submessages(messages))
 }
 
-#[entry_point]
-pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> Result<Binary, ContractError> {
-    let response = match msg {
-        QueryMsg::Config {} => to_binary(&query_config(deps)?)?,
-        QueryMsg::Get { round } => to_binary(&query_get(deps, round)?)?,
-        QueryMsg::Latest {} => to_binary(&query_latest(deps)?)?,
-        QueryMsg::Bounties {} => to_binary(&query_bounties(deps)?)?,
-    };
-    Ok(response)
+pub fn try_add_batch(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    network: String,
+    beacons: Vec<BeaconSubmission>,
+    mode: BatchMode,
+) -> Result<Response, ContractError> {
+    let network_info = load_network(deps.storage, &network)?;
+    let config = config_read(deps.storage).load()?;
+
+    let mut response = Response::new();
+    let mut total_bounty: Vec<Coin> = vec![];
+    let mut callbacks: Vec<SubMsg> = vec![];
+    let mut callback_count = 0;
+    for BeaconSubmission {
+        round,
+        previous_signature,
+        signature,
+    } in beacons
+    {
+        // The jobs of all rounds share the callback budget of a single message
+        let result =
+            if callback_count + count_jobs(deps.storage, &network, round)? > MAX_JOBS_PER_ROUND {
+                Err(ContractError::TooManyCallbacks {
+                    round,
+                    limit: MAX_JOBS_PER_ROUND as u64,
+                })
+            } else {
+                add_beacon(
+                    deps.storage,
+                    &env,
+                    &info.sender,
+                    &network,
+                    &network_info,
+                    &config,
+                    round,
+                    &previous_signature,
+                    &signature,
+                )
+            };
+        match (result, mode) {
+            (Ok(added), _) => {
+                callback_count += added.callbacks.len();
+                add_coins(&mut total_bounty, &added.bounty);
+                callbacks.extend(added.callbacks);
+                callbacks.extend(added.deliveries);
+                response = response
+                    .add_attribute("added", round.to_string())
+                    .add_events(added.events);
+            }
+            (Err(ContractError::BeaconExists { round }), _) if !config.reject_duplicates => {
+                response = response.add_attribute("already_known", round.to_string());
+            }
+            (Err(err), BatchMode::Strict) => return Err(err),
+            (Err(err), BatchMode::Lenient) => {
+                response = response.add_attribute("failed", format!("{}: {}", round, err));
+            }
+        }
+    }
+
+    // All bounties are paid out in a single transfer
+    if !total_bounty.is_empty() {
+        response = response.add_message(BankMsg::Send {
+            to_address: info.sender.to_string(),
+            amount: total_bounty,
+        });
+    }
+    Ok(response.add_submessages(callbacks))
 }
 
-fn query_config(deps: Deps) -> Result<ConfigResponse, ContractError> {
-    let config = config_read(deps.storage).load()?;
-    Ok(ConfigResponse {
-        pubkey: config.pubkey,
-        bounty_denom: config.bounty_denom,
-    })
+/// The number of jobs waiting for a round
+fn count_jobs(storage: &dyn Storage, network: &str, round: u64) -> StdResult<usize> {
+    Ok(jobs_storage_read(storage, network)
+        .may_load(&round.to_be_bytes())?
+        .map_or(0, |jobs| jobs.len()))
 }
 
-fn query_get(deps: Deps, round: u64) -> Result<GetResponse, ContractError> {
-    let beacons = beacons_storage_read(deps.storage);
-    let randomness = beacons.get(&round.to_be_bytes()).unwrap_or_default();
-    Ok(GetResponse {
-        randomness: randomness.into(),
-    })
+/// The outcome of storing a single beacon
+struct Added {
+    randomness: [u8; 32],
+    /// The bounty claimed for this round, which the caller must pay out
+    bounty: Vec<Coin>,
+    /// Callbacks of the jobs waiting for this round
+    callbacks: Vec<SubMsg>,
+    /// Packets to the IBC channels waiting for this round
+    deliveries: Vec<SubMsg>,
+    /// The `drand_beacon` event followed by the `bounty_paid` events
+    events: Vec<Event>,
 }
 
-fn query_latest(deps: Deps) -> Result<LatestResponse, ContractError> {
-    let store = beacons_storage_read(deps.storage);
-    let mut iter = store.range(None, None, Order::Descending);
-    let (key, value) = iter.next().ok_or(ContractError::NoBeacon {})?;
+/// Verifies and stores a beacon, claims its bounty and collects the job callbacks.
+///
+/// All checks happen before storage is written, such that an error leaves the state untouched.
+/// Rounds that are already stored fail with `BeaconExists` before the expensive verification.
+#[allow(clippy::too_many_arguments)]
+fn add_beacon(
+    storage: &mut dyn Storage,
+    env: &Env,
+    submitter: &Addr,
+    network: &str,
+    network_info: &Network,
+    config: &Config,
+    round: u64,
+    previous_signature: &[u8],
+    signature: &[u8],
+) -> Result<Added, ContractError> {
+    if beacons_storage_read(storage, network)
+        .may_load(&round.to_be_bytes())?
+        .is_some()
+    {
+        return Err(ContractError::BeaconExists { round });
+    }
 
-    Ok(LatestResponse {
-        round: u64::from_be_bytes(Binary(key).to_array()?),
-        randomness: value.into(),
-    })
-}
+    // Saturating such that a huge tolerance accepts all rounds instead of panicking
+    let tolerance = config.clock_tolerance.saturating_mul(1_000_000_000);
+    let latest_time = Timestamp::from_nanos(env.block.time.nanos().saturating_add(tolerance));
+    let latest_round = network_info.round_at(latest_time);
+    if round > latest_round {
+        return Err(ContractError::RoundInFuture {
+            round,
+            latest_round,
+        });
+    }
 
-fn query_bounties(deps: Deps) -> Result<BountiesResponse, ContractError> {
-    let Config { bounty_denom, .. } = config_read(deps.storage).load()?;
+    let valid = verify_beacon(
+        network_info.scheme,
+        &network_info.pubkey,
+        round,
+        previous_signature,
+        signature,
+    )
+    .map_err(|_| ContractError::InvalidPubkey {})?;
 
-    let store = bounties_storage_read(deps.storage);
-    let iter = store.range(None, None, Order::Ascending);
+    if !valid {
+        return Err(ContractError::InvalidSignature {});
+    }
 
-    let bounties: Result<Vec<Bounty>, _> = iter
-        .map(|(key, value)| -> StdResult<Bounty> {
-            let round = u64::from_be_bytes(Binary(key).to_array()?);
-            let amount = coins(
-                u128::from_be_bytes(Binary(value).to_array()?),
-                &bounty_denom,
-            );
-            Ok(Bounty { round, amount })
+    let randomness = derive_randomness(signature);
+    let previous_signature = if network_info.scheme.is_chained() {
+        previous_signature
+    } else {
+        b""
+    };
+    let beacon = Beacon {
+        round,
+        signature: signature.into(),
+        previous_signature: previous_signature.into(),
+        randomness: randomness.into(),
+        submitter: submitter.clone(),
+        block_height: env.block.height,
+        block_time: env.block.time,
+    };
+    beacons_storage(storage, network).save(&round.to_be_bytes(), &beacon)?;
+
+    // With commit-reveal enabled, a submitter without a matching commitment stores the
+    // beacon but leaves the bounty for the relayer that committed to it
+    let claimable = match config.claim_delay {
+        Some(delay) => {
+            check_commitment(storage, env, network, round, submitter, signature, delay).is_ok()
+        }
+        None => true,
+    };
+    let bounty = if claimable {
+        claim_bounty(storage, network, round)?
+    } else {
+        vec![]
+    };
+
+    relayers_storage(storage).update(submitter.as_bytes(), |stats| -> StdResult<_> {
+        let mut stats = stats.unwrap_or_default();
+        stats.rounds_submitted += 1;
+        add_coins(&mut stats.bounties_earned, &bounty);
+        stats
+            .first_submission_height
+            .get_or_insert(env.block.height);
+        stats.last_submission_height = Some(env.block.height);
+        Ok(stats)
+    })?;
+
+    let jobs = jobs_storage_read(storage, network)
+        .may_load(&round.to_be_bytes())?
+        .unwrap_or_default();
+    let callbacks = jobs
+        .into_iter()
+        .map(|Job { requester, job_id }| {
+            callback_msg(
+                requester,
+                job_id,
+                network.to_string(),
+                round,
+                randomness.into(),
+            )
         })
-        .collect();
+        .collect::<StdResult<_>>()?;
+    jobs_storage(storage, network).remove(&round.to_be_bytes());
+    let deliveries = take_deliveries(storage, env, network, &beacon)?;
 
-    Ok(BountiesResponse {
-        bounties: bounties?,
+    let mut events = vec![drand_beacon_event(network, round, &randomness, submitter)];
+    events.extend(bounty_paid_events(network, round, submitter, &bounty));
+
+    Ok(Added {
+        randomness,
+        bounty,
+        callbacks,
+        deliveries,
+        events,
     })
 }
 
-fn get_bounty(storage: &dyn Storage, round: u64) -> StdResult<u128> {
-    let key = round.to_be_bytes();
-    let bounties = bounties_storage_read(storage);
-    let value = match bounties.get(&key) {
-        Some(data) => u128::from_be_bytes(Binary(data).to_array()?),
-        None => 0u128,
-    };
-    Ok(value)
-}
+pub fn try_commit_bounty(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    network: String,
+    round: u64,
+    commitment: Binary,
+) -> Result<Response, ContractError> {
+    load_network(deps.storage, &network)?;
+    if config_read(deps.storage).load()?.claim_delay.is_none() {
+        return Err(ContractError::CommitRevealDisabled {});
+    }
+    if get_bounty(deps.storage, &network, round)?.is_empty() {
+        return Err(ContractError::NoBounty { round });
+    }
 
-fn set_bounty(storage: &mut dyn Storage, round: u64, amount: u128) {
-    let key = round.to_be_bytes();
-    let mut bounties = bounties_storage(storage);
-    bounties.set(&key, &amount.to_be_bytes());
-}
+    let mut commitments = commitments_storage(deps.storage, &network, round);
+    if commitments.may_load(info.sender.as_bytes())?.is_some() {
+        return Err(ContractError::AlreadyCommitted { round });
+    }
+    commitments.save(
+        info.sender.as_bytes(),
+        &Commitment {
+            commitment,
+            height: env.block.height,
+        },
+    )?;
 
-fn clear_bounty(storage: &mut dyn Storage, round: u64) {
-    let key = round.to_be_bytes();
-    let mut bounties = bounties_storage(storage);
-    bounties.remove(&key);
+    Ok(Response::new().add_attribute("round", round.to_string()))
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
-    use cosmwasm_std::{from_binary, Addr, Coin, Uint128};
+pub fn try_claim_bounty(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    network: String,
+    round: u64,
+) -> Result<Response, ContractError> {
+    load_network(deps.storage, &network)?;
+    let delay = config_read(deps.storage)
+        .load()?
+        .claim_delay
+        .ok_or(ContractError::CommitRevealDisabled {})?;
+    let beacon = beacons_storage_read(deps.storage, &network)
+        .may_load(&round.to_be_bytes())?
+        .ok_or(ContractError::NoBeacon {})?;
+    check_commitment(
+        deps.storage,
+        &env,
+        &network,
+        round,
+        &info.sender,
+        &beacon.signature,
+        delay,
+    )?;
 
-    // $ node
-    // > Uint8Array.from(Buffer.from("868f005eb8e6e4ca0a47c8a77ceaa5309a47978a7c71bc5cce96366b5d7a569937c529eeda66c7293784a9402801af31", "hex"))
-    fn pubkey_loe_mainnet() -> Binary {
-        vec![
-            134, 143, 0, 94, 184, 230, 228, 202, 10, 71, 200, 167, 124, 234, 165, 48, 154, 71, 151,
-            138, 124, 113, 188, 92, 206, 150, 54, 107, 93, 122, 86, 153, 55, 197, 41, 238, 218,
-            102, 199, 41, 55, 132, 169, 64, 40, 1, 175, 49,
-        ]
-        .into()
+    let bounty = claim_bounty(deps.storage, &network, round)?;
+    if bounty.is_empty() {
+        return Err(ContractError::NoBounty { round });
     }
+    relayers_storage(deps.storage).update(info.sender.as_bytes(), |stats| -> StdResult<_> {
+        let mut stats = stats.unwrap_or_default();
+        add_coins(&mut stats.bounties_earned, &bounty);
+        Ok(stats)
+    })?;
 
-    const BOUNTY_DENOM: &str = "ucosm";
+    Ok(Response::new()
+        .add_attribute("round", round.to_string())
+        .add_attribute("claimed", coins_to_string(&bounty))
+        .add_events(bounty_paid_events(&network, round, &info.sender, &bounty))
+        .add_message(BankMsg::Send {
+            to_address: info.sender.to_string(),
+            amount: bounty,
+        }))
+}
 
-    #[test]
-    fn proper_initialization() {
-        let mut deps = mock_dependencies();
+/// Ensures the sender committed to the signature of the round at least `delay` blocks ago.
+///
+/// Once the signature is public, anybody can compute a valid commitment, e.g. after seeing
+/// somebody else's `CommitBounty` in the mempool. The earliest valid commitment therefore has
+/// the exclusive right to claim for `delay` blocks after it can be revealed.
+fn check_commitment(
+    storage: &dyn Storage,
+    env: &Env,
+    network: &str,
+    round: u64,
+    sender: &Addr,
+    signature: &[u8],
+    delay: u64,
+) -> Result<(), ContractError> {
+    let Commitment { commitment, height } = commitments_storage_read(storage, network, round)
+        .may_load(sender.as_bytes())?
+        .ok_or(ContractError::NoCommitment { round })?;
+    if commitment.as_slice() != bounty_commitment(sender.as_str(), round, signature) {
+        return Err(ContractError::CommitmentMismatch { round });
+    }
+    let reveal_height = height.saturating_add(delay);
+    if env.block.height < reveal_height {
+        return Err(ContractError::CommitmentTooRecent { reveal_height });
+    }
 
-        let info = mock_info("creator", &coins(1000, "earth"));
-        let msg = InstantiateMsg {
-            pubkey: pubkey_loe_mainnet(),
-            bounty_denom: BOUNTY_DENOM.into(),
-        };
+    let earliest = earliest_commitment_height(storage, network, round, signature)?;
+    let until_height = earliest.saturating_add(delay).saturating_add(delay);
+    if height > earliest && env.block.height < until_height {
+        return Err(ContractError::ClaimReserved {
+            round,
+            until_height,
+        });
+    }
+    Ok(())
+}
 
-        let res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
-        assert_eq!(res.messages.len(), 0);
+/// The height of the earliest commitment matching the signature of the round, or `u64::MAX`
+/// if there is none
+fn earliest_commitment_height(
+    storage: &dyn Storage,
+    network: &str,
+    round: u64,
+    signature: &[u8],
+) -> StdResult<u64> {
+    let mut earliest = u64::MAX;
+    for item in
+        commitments_storage_read(storage, network, round).range(None, None, Order::Ascending)
+    {
+        let (committer, Commitment { commitment, height }) = item?;
+        let committer = String::from_utf8(committer)?;
+        if height < earliest
+            && commitment.as_slice() == bounty_commitment(&committer, round, signature)
+        {
+            earliest = height;
+        }
+    }
+    Ok(earliest)
+}
 
-        let response: ConfigResponse =
-            from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::Config {}).unwrap()).unwrap();
-        assert_eq!(
-            response,
-            ConfigResponse {
-                pubkey: pubkey_loe_mainnet(),
-                bounty_denom: BOUNTY_DENOM.into(),
-            }
-        );
+/// Removes the bounty of a round along with its contributions and commitments and returns it
+fn claim_bounty(storage: &mut dyn Storage, network: &str, round: u64) -> StdResult<Vec<Coin>> {
+    let bounty = get_bounty(storage, network, round)?;
+    if !bounty.is_empty() {
+        set_bounty(storage, network, round, &[])?;
+        clear_contributions(storage, network, round)?;
+        clear_commitments(storage, network, round)?;
     }
+    Ok(bounty)
+}
 
-    #[test]
-    fn set_bounty_works() {
-        let mut deps = mock_dependencies();
+pub fn try_request_beacon(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    network: String,
+    round: u64,
+    job_id: String,
+) -> Result<Response, ContractError> {
+    let network_info = load_network(deps.storage, &network)?;
+    if round == 0 {
+        return Err(ContractError::InvalidRound { round });
+    }
 
-        let info = mock_info("creator", &[]);
-        let msg = InstantiateMsg {
-            pubkey: pubkey_loe_mainnet(),
-            bounty_denom: BOUNTY_DENOM.into(),
-        };
-        instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+    let response = Response::new()
+        .add_attribute("round", round.to_string())
+        .add_attribute("job_id", job_id.clone());
 
-        // First bounty
+    // Beacon is already known, so we can answer right away and return the funds
+    if let Some(beacon) =
+        beacons_storage_read(deps.storage, &network).may_load(&round.to_be_bytes())?
+    {
+        let funds: Vec<Coin> = info
+            .funds
+            .into_iter()
+            .filter(|fund| !fund.amount.is_zero())
+            .collect();
+        let msg = callback_msg(
+            info.sender.clone(),
+            job_id,
+            network,
+            round,
+            beacon.randomness,
+        )?;
+        let response = response.add_submessage(msg);
+        if funds.is_empty() {
+            return Ok(response);
+        }
+        return Ok(response.add_message(BankMsg::Send {
+            to_address: info.sender.to_string(),
+            amount: funds,
+        }));
+    }
 
-        let msg = ExecuteMsg::SetBounty { round: 7000 };
-        let info = mock_info(
-            "anyone",
-            &[Coin {
-                denom: BOUNTY_DENOM.into(),
-                amount: Uint128::new(5000),
-            }],
+    // A round whose bounty would already be expired is unlikely to ever be added, so
+    // its job would never be answered
+    let config = config_read(deps.storage).load()?;
+    let expired_before = env
+        .block
+        .time
+        .minus_seconds(config.bounty_expiry.min(env.block.time.seconds()));
+    let min_round = network_info.round_at(expired_before) + 1;
+    if round < min_round {
+        return Err(ContractError::RoundTooOld { round, min_round });
+    }
+
+    if let Some(fee) = &config.job_fee {
+        let paid = info
+            .funds
+            .iter()
+            .filter(|fund| fund.denom == fee.denom)
+            .map(|fund| fund.amount)
+            .sum::<Uint128>();
+        if paid < fee.amount {
+            return Err(ContractError::InsufficientJobFee { fee: fee.clone() });
+        }
+    }
+
+    let mut jobs = jobs_storage_read(deps.storage, &network)
+        .may_load(&round.to_be_bytes())?
+        .unwrap_or_default();
+    if jobs.len() >= MAX_JOBS_PER_ROUND {
+        return Err(ContractError::TooManyJobs {
+            round,
+            limit: MAX_JOBS_PER_ROUND as u64,
+        });
+    }
+    jobs.push(Job {
+        requester: info.sender.clone(),
+        job_id,
+    });
+    jobs_storage(deps.storage, &network).save(&round.to_be_bytes(), &jobs)?;
+
+    // The fee and any additional funds pay the relayer of this round
+    let (bounty, sent) = contribute_bounty(
+        deps.storage,
+        &config.bounty_denoms,
+        &network,
+        round,
+        &info.sender,
+        info.funds,
+    )?;
+    if sent.is_empty() {
+        return Ok(response);
+    }
+    Ok(response
+        .add_attribute("bounty", coins_to_string(&bounty))
+        .add_events(bounty_set_events(&network, round, &info.sender, &sent)))
+}
+
+/// Creates the callback for a job. Failures, including running out of gas, are caught in
+/// `reply` such that a broken consumer cannot block beacon submission.
+pub fn callback_msg(
+    requester: Addr,
+    job_id: String,
+    network: String,
+    round: u64,
+    randomness: Binary,
+) -> StdResult<SubMsg> {
+    let msg = BeaconCallback {
+        job_id,
+        network,
+        round,
+        randomness,
+    }
+    .into_cosmos_msg(requester)?;
+    Ok(SubMsg::reply_on_error(msg, CALLBACK_REPLY_ID).with_gas_limit(CALLBACK_GAS_LIMIT))
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn reply(_deps: DepsMut, _env: Env, msg: Reply) -> Result<Response, ContractError> {
+    match (msg.id, msg.result) {
+        (CALLBACK_REPLY_ID, SubMsgResult::Err(err)) => {
+            Ok(Response::new().add_attribute("callback_error", err))
+        }
+        (CALLBACK_REPLY_ID, SubMsgResult::Ok(_)) => Ok(Response::new()),
+        (id, _) => Err(ContractError::UnknownReplyId { id }),
+    }
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> Result<Binary, ContractError> {
+    let response = match msg {
+        QueryMsg::Config {} => to_binary(&query_config(deps)?)?,
+        QueryMsg::Admin {} => to_binary(&query_admin(deps)?)?,
+        QueryMsg::Networks {} => to_binary(&query_networks(deps)?)?,
+        QueryMsg::Get { network, round } => to_binary(&query_get(deps, network, round)?)?,
+        QueryMsg::Beacon { network, round } => to_binary(&query_beacon(deps, network, round)?)?,
+        QueryMsg::Latest { network } => to_binary(&query_latest(deps, network)?)?,
+        QueryMsg::Bounties {
+            network,
+            start_after,
+            limit,
+            min_round,
+            max_round,
+        } => to_binary(&query_bounties(
+            deps,
+            network,
+            start_after,
+            limit,
+            min_round,
+            max_round,
+        )?)?,
+        QueryMsg::Beacons {
+            network,
+            start_after,
+            limit,
+            order,
+        } => to_binary(&query_beacons(deps, network, start_after, limit, order)?)?,
+        QueryMsg::Jobs { network, round } => to_binary(&query_jobs(deps, network, round)?)?,
+        QueryMsg::RoundAt { network, timestamp } => {
+            to_binary(&query_round_at(deps, network, timestamp)?)?
+        }
+        QueryMsg::TimeOfRound { network, round } => {
+            to_binary(&query_time_of_round(deps, network, round)?)?
+        }
+        QueryMsg::NextRoundAfter { network, timestamp } => {
+            to_binary(&query_next_round_after(deps, network, timestamp)?)?
+        }
+        QueryMsg::RandomInt {
+            network,
+            round,
+            min,
+            max,
+            salt,
+        } => to_binary(&query_random_int(deps, network, round, min, max, salt)?)?,
+        QueryMsg::Shuffle {
+            network,
+            round,
+            n,
+            salt,
+        } => to_binary(&query_shuffle(deps, network, round, n, salt)?)?,
+        QueryMsg::Derive {
+            network,
+            round,
+            namespace,
+            job_id,
+        } => to_binary(&query_derive(deps, network, round, namespace, job_id)?)?,
+        QueryMsg::Pick {
+            network,
+            round,
+            items,
+            k,
+            salt,
+        } => to_binary(&query_pick(deps, network, round, items, k, salt)?)?,
+        QueryMsg::Relayer { address } => to_binary(&query_relayer(deps, address)?)?,
+        QueryMsg::Relayers { start_after, limit } => {
+            to_binary(&query_relayers(deps, start_after, limit)?)?
+        }
+        QueryMsg::Withdrawable {
+            network,
+            address,
+            start_after,
+            limit,
+        } => to_binary(&query_withdrawable(
+            deps,
+            env,
+            network,
+            address,
+            start_after,
+            limit,
+        )?)?,
+    };
+    Ok(response)
+}
+
+fn query_config(deps: Deps) -> Result<ConfigResponse, ContractError> {
+    let config = config_read(deps.storage).load()?;
+    Ok(ConfigResponse {
+        bounty_denoms: config.bounty_denoms,
+        clock_tolerance: config.clock_tolerance,
+        bounty_expiry: config.bounty_expiry,
+        claim_delay: config.claim_delay,
+        reject_duplicates: config.reject_duplicates,
+        job_fee: config.job_fee,
+    })
+}
+
+fn query_admin(deps: Deps) -> Result<AdminResponse, ContractError> {
+    let admin = config_read(deps.storage).load()?.admin;
+    Ok(AdminResponse {
+        admin: admin.map(|admin| admin.into_string()),
+    })
+}
+
+fn query_networks(deps: Deps) -> Result<NetworksResponse, ContractError> {
+    let networks: StdResult<Vec<NetworkInfo>> = networks_storage_read(deps.storage)
+        .range(None, None, Order::Ascending)
+        .map(|item| {
+            let (key, network) = item?;
+            Ok(NetworkInfo {
+                chain_hash: String::from_utf8(key)?,
+                pubkey: network.pubkey,
+                scheme: network.scheme,
+                genesis_time: network.genesis_time,
+                period: network.period,
+                sample_beacon: None,
+            })
+        })
+        .collect();
+    Ok(NetworksResponse {
+        networks: networks?,
+    })
+}
+
+fn query_get(deps: Deps, network: String, round: u64) -> Result<GetResponse, ContractError> {
+    load_network(deps.storage, &network)?;
+    let beacons = beacons_storage_read(deps.storage, &network);
+    let randomness = beacons
+        .may_load(&round.to_be_bytes())?
+        .map(|beacon| beacon.randomness)
+        .unwrap_or_default();
+    Ok(GetResponse { randomness })
+}
+
+fn query_beacon(deps: Deps, network: String, round: u64) -> Result<BeaconResponse, ContractError> {
+    load_network(deps.storage, &network)?;
+    let beacon = beacons_storage_read(deps.storage, &network)
+        .may_load(&round.to_be_bytes())?
+        .ok_or(ContractError::NoBeacon {})?;
+    Ok(BeaconResponse { beacon })
+}
+
+fn query_latest(deps: Deps, network: String) -> Result<LatestResponse, ContractError> {
+    load_network(deps.storage, &network)?;
+    let store = beacons_storage_read(deps.storage, &network);
+    let mut iter = store.range(None, None, Order::Descending);
+    let (_, beacon) = iter.next().ok_or(ContractError::NoBeacon {})??;
+
+    Ok(LatestResponse {
+        round: beacon.round,
+        randomness: beacon.randomness,
+    })
+}
+
+fn query_beacons(
+    deps: Deps,
+    network: String,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+    order: SortOrder,
+) -> Result<BeaconsResponse, ContractError> {
+    load_network(deps.storage, &network)?;
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+
+    let (min, max, order) = match order {
+        SortOrder::Ascending => (
+            start_after.map(|round| round.checked_add(1)),
+            None,
+            Order::Ascending,
+        ),
+        SortOrder::Descending => (
+            None,
+            start_after.map(|round| round.checked_sub(1)),
+            Order::Descending,
+        ),
+    };
+    let (start, end) = match round_range(min, max) {
+        Some(range) => range,
+        None => return Ok(BeaconsResponse { beacons: vec![] }),
+    };
+
+    let store = beacons_storage_read(deps.storage, &network);
+    let beacons: StdResult<Vec<RoundRandomness>> = store
+        .range(start.as_deref(), end.as_deref(), order)
+        .take(limit)
+        .map(|item| {
+            let (_, beacon) = item?;
+            Ok(RoundRandomness {
+                round: beacon.round,
+                randomness: beacon.randomness,
+            })
+        })
+        .collect();
+
+    Ok(BeaconsResponse { beacons: beacons? })
+}
+
+/// Converts inclusive round bounds into storage range bounds. An outer `None` means unbounded,
+/// an inner `None` a bound that no round can satisfy. Returns `None` if the range is empty.
+#[allow(clippy::type_complexity)]
+fn round_range(
+    min: Option<Option<u64>>,
+    max: Option<Option<u64>>,
+) -> Option<(Option<Vec<u8>>, Option<Vec<u8>>)> {
+    let start = match min {
+        Some(min) => Some(min?.to_be_bytes().to_vec()),
+        None => None,
+    };
+    let end = match max {
+        // The end of a range is exclusive
+        Some(max) => max?.checked_add(1).map(|end| end.to_be_bytes().to_vec()),
+        None => None,
+    };
+    Some((start, end))
+}
+
+fn query_bounties(
+    deps: Deps,
+    network: String,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+    min_round: Option<u64>,
+    max_round: Option<u64>,
+) -> Result<BountiesResponse, ContractError> {
+    load_network(deps.storage, &network)?;
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+
+    let min = match (start_after, min_round) {
+        (Some(start_after), min_round) => Some(
+            start_after
+                .checked_add(1)
+                .map(|next| next.max(min_round.unwrap_or_default())),
+        ),
+        (None, min_round) => min_round.map(Some),
+    };
+    let (start, end) = match round_range(min, max_round.map(Some)) {
+        Some(range) => range,
+        None => return Ok(BountiesResponse { bounties: vec![] }),
+    };
+
+    let store = bounties_storage_read(deps.storage, &network);
+    let iter = store
+        .range(start.as_deref(), end.as_deref(), Order::Ascending)
+        .take(limit);
+
+    let bounties: Result<Vec<Bounty>, _> = iter
+        .map(|item| -> StdResult<Bounty> {
+            let (key, amount) = item?;
+            let round = u64::from_be_bytes(Binary(key).to_array()?);
+            Ok(Bounty { round, amount })
+        })
+        .collect();
+
+    Ok(BountiesResponse {
+        bounties: bounties?,
+    })
+}
+
+fn query_relayer(deps: Deps, address: String) -> Result<RelayerResponse, ContractError> {
+    let stats = relayers_storage_read(deps.storage)
+        .may_load(address.as_bytes())?
+        .ok_or_else(|| ContractError::UnknownRelayer {
+            address: address.clone(),
+        })?;
+    Ok(RelayerResponse { address, stats })
+}
+
+fn query_relayers(
+    deps: Deps,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> Result<RelayersResponse, ContractError> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    // Appending a zero byte gives the smallest key after start_after
+    let start = start_after.map(|address| {
+        let mut key = address.into_bytes();
+        key.push(0);
+        key
+    });
+
+    let relayers: StdResult<Vec<RelayerResponse>> = relayers_storage_read(deps.storage)
+        .range(start.as_deref(), None, Order::Ascending)
+        .take(limit)
+        .map(|item| {
+            let (key, stats) = item?;
+            Ok(RelayerResponse {
+                address: String::from_utf8(key)?,
+                stats,
+            })
+        })
+        .collect();
+    Ok(RelayersResponse {
+        relayers: relayers?,
+    })
+}
+
+fn query_withdrawable(
+    deps: Deps,
+    env: Env,
+    network: String,
+    address: String,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> Result<WithdrawableResponse, ContractError> {
+    let network_info = load_network(deps.storage, &network)?;
+    let address = deps.api.addr_validate(&address)?;
+    let bounty_expiry = config_read(deps.storage).load()?.bounty_expiry;
+    // At least one round is checked per page to make progress
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT) as usize;
+
+    let (start, _) = match round_range(start_after.map(|round| round.checked_add(1)), None) {
+        Some(range) => range,
+        None => {
+            return Ok(WithdrawableResponse {
+                withdrawable: vec![],
+                last_round: None,
+            })
+        }
+    };
+
+    let mut withdrawable = vec![];
+    let mut last_checked = None;
+    let mut last_round = None;
+    let store = bounties_storage_read(deps.storage, &network);
+    let iter = store.range(start.as_deref(), None, Order::Ascending);
+    for (checked, item) in iter.enumerate() {
+        let (key, _) = item?;
+        let round = u64::from_be_bytes(Binary(key).to_array()?);
+        // Rounds are sorted by publish time, so all later rounds expire later
+        match bounty_expires_at(&network_info, round, bounty_expiry) {
+            Some(expires_at) if expires_at <= env.block.time.seconds() => {}
+            _ => break,
+        }
+        // Only report a page end if another expired bounty follows
+        if checked == limit {
+            last_round = last_checked;
+            break;
+        }
+        last_checked = Some(round);
+        let amount = get_contribution(deps.storage, &network, round, &address)?;
+        if !amount.is_empty() {
+            withdrawable.push(Bounty { round, amount });
+        }
+    }
+
+    Ok(WithdrawableResponse {
+        withdrawable,
+        last_round,
+    })
+}
+
+fn query_jobs(deps: Deps, network: String, round: u64) -> Result<JobsResponse, ContractError> {
+    load_network(deps.storage, &network)?;
+    let jobs = jobs_storage_read(deps.storage, &network)
+        .may_load(&round.to_be_bytes())?
+        .unwrap_or_default();
+    Ok(JobsResponse { jobs })
+}
+
+fn query_round_at(
+    deps: Deps,
+    network: String,
+    timestamp: Timestamp,
+) -> Result<RoundResponse, ContractError> {
+    let network = load_network(deps.storage, &network)?;
+    Ok(RoundResponse {
+        round: network.round_at(timestamp),
+    })
+}
+
+fn query_time_of_round(
+    deps: Deps,
+    network: String,
+    round: u64,
+) -> Result<TimeResponse, ContractError> {
+    let network = load_network(deps.storage, &network)?;
+    let time = network
+        .time_of_round(round)
+        .ok_or(ContractError::InvalidRound { round })?;
+    Ok(TimeResponse { time })
+}
+
+fn query_next_round_after(
+    deps: Deps,
+    network: String,
+    timestamp: Timestamp,
+) -> Result<RoundResponse, ContractError> {
+    let network = load_network(deps.storage, &network)?;
+    Ok(RoundResponse {
+        round: network.next_round_after(timestamp),
+    })
+}
+
+fn load_randomness(deps: Deps, network: &str, round: u64) -> Result<Vec<u8>, ContractError> {
+    load_network(deps.storage, network)?;
+    let beacon = beacons_storage_read(deps.storage, network)
+        .may_load(&round.to_be_bytes())?
+        .ok_or(ContractError::NoBeacon {})?;
+    Ok(beacon.randomness.into())
+}
+
+/// Creates a random stream from a stored beacon
+fn random_stream(
+    deps: Deps,
+    network: &str,
+    round: u64,
+    salt: &[u8],
+) -> Result<RandomStream, ContractError> {
+    let randomness = load_randomness(deps, network, round)?;
+    Ok(RandomStream::new(&randomness, salt))
+}
+
+fn query_derive(
+    deps: Deps,
+    network: String,
+    round: u64,
+    namespace: String,
+    job_id: String,
+) -> Result<DeriveResponse, ContractError> {
+    let randomness = load_randomness(deps, &network, round)?;
+    Ok(DeriveResponse {
+        randomness: derive_sub_randomness(&randomness, &namespace, &job_id).into(),
+        derivation: DERIVATION_VERSION.to_string(),
+    })
+}
+
+fn check_element_count(count: u64) -> Result<(), ContractError> {
+    if count > MAX_RANDOM_ELEMENTS {
+        return Err(ContractError::TooManyElements {
+            count,
+            limit: MAX_RANDOM_ELEMENTS,
+        });
+    }
+    Ok(())
+}
+
+fn query_random_int(
+    deps: Deps,
+    network: String,
+    round: u64,
+    min: u64,
+    max: u64,
+    salt: Binary,
+) -> Result<RandomIntResponse, ContractError> {
+    if min > max {
+        return Err(ContractError::InvalidRange { min, max });
+    }
+    let mut stream = random_stream(deps, &network, round, &salt)?;
+    Ok(RandomIntResponse {
+        value: stream.int_in_range(min, max),
+    })
+}
+
+fn query_shuffle(
+    deps: Deps,
+    network: String,
+    round: u64,
+    n: u32,
+    salt: Binary,
+) -> Result<ShuffleResponse, ContractError> {
+    check_element_count(n.into())?;
+    let mut stream = random_stream(deps, &network, round, &salt)?;
+    let mut permutation: Vec<u32> = (0..n).collect();
+    stream.shuffle(&mut permutation);
+    Ok(ShuffleResponse { permutation })
+}
+
+fn query_pick(
+    deps: Deps,
+    network: String,
+    round: u64,
+    items: Vec<String>,
+    k: u32,
+    salt: Binary,
+) -> Result<PickResponse, ContractError> {
+    check_element_count(items.len() as u64)?;
+    let mut stream = random_stream(deps, &network, round, &salt)?;
+    Ok(PickResponse {
+        picked: stream.pick(items, k as usize),
+    })
+}
+
+fn get_bounty(storage: &dyn Storage, network: &str, round: u64) -> StdResult<Vec<Coin>> {
+    let bounties = bounties_storage_read(storage, network);
+    Ok(bounties.may_load(&round.to_be_bytes())?.unwrap_or_default())
+}
+
+/// Stores the bounty of a round. An empty bounty is removed.
+fn set_bounty(
+    storage: &mut dyn Storage,
+    network: &str,
+    round: u64,
+    amount: &[Coin],
+) -> StdResult<()> {
+    let key = round.to_be_bytes();
+    let mut bounties = bounties_storage(storage, network);
+    if amount.is_empty() {
+        bounties.remove(&key);
+        Ok(())
+    } else {
+        bounties.save(&key, &amount.to_vec())
+    }
+}
+
+fn get_contribution(
+    storage: &dyn Storage,
+    network: &str,
+    round: u64,
+    contributor: &Addr,
+) -> StdResult<Vec<Coin>> {
+    let contributions = contributions_storage_read(storage, network, round);
+    Ok(contributions
+        .may_load(contributor.as_bytes())?
+        .unwrap_or_default())
+}
+
+/// Stores the contribution of an address to the bounty of a round. An empty contribution is removed.
+fn set_contribution(
+    storage: &mut dyn Storage,
+    network: &str,
+    round: u64,
+    contributor: &Addr,
+    amount: &[Coin],
+) -> StdResult<()> {
+    let mut contributions = contributions_storage(storage, network, round);
+    if amount.is_empty() {
+        contributions.remove(contributor.as_bytes());
+        Ok(())
+    } else {
+        contributions.save(contributor.as_bytes(), &amount.to_vec())
+    }
+}
+
+fn clear_contributions(storage: &mut dyn Storage, network: &str, round: u64) -> StdResult<()> {
+    let contributors = contributions_storage_read(storage, network, round)
+        .range(None, None, Order::Ascending)
+        .map(|item| item.map(|(key, _)| key))
+        .collect::<StdResult<Vec<Vec<u8>>>>()?;
+    let mut contributions = contributions_storage(storage, network, round);
+    for contributor in contributors {
+        contributions.remove(&contributor);
+    }
+    Ok(())
+}
+
+fn clear_commitments(storage: &mut dyn Storage, network: &str, round: u64) -> StdResult<()> {
+    let committers = commitments_storage_read(storage, network, round)
+        .range(None, None, Order::Ascending)
+        .map(|item| item.map(|(key, _)| key))
+        .collect::<StdResult<Vec<Vec<u8>>>>()?;
+    let mut commitments = commitments_storage(storage, network, round);
+    for committer in committers {
+        commitments.remove(&committer);
+    }
+    Ok(())
+}
+
+/// Adds `coins` to `total`, which is kept sorted by denom
+fn add_coins(total: &mut Vec<Coin>, coins: &[Coin]) {
+    for coin in coins {
+        match total.binary_search_by(|existing| existing.denom.cmp(&coin.denom)) {
+            Ok(index) => total[index].amount += coin.amount,
+            Err(index) => total.insert(index, coin.clone()),
+        }
+    }
+}
+
+/// Subtracts `coins` from `total` and removes denoms that drop to zero
+fn subtract_coins(total: &mut Vec<Coin>, coins: &[Coin]) -> StdResult<()> {
+    for coin in coins {
+        let existing = total
+            .iter_mut()
+            .find(|existing| existing.denom == coin.denom)
+            .ok_or_else(|| StdError::generic_err(format!("No {} to subtract from", coin.denom)))?;
+        existing.amount = existing.amount.checked_sub(coin.amount)?;
+    }
+    total.retain(|coin| !coin.amount.is_zero());
+    Ok(())
+}
+
+fn coins_to_string(coins: &[Coin]) -> String {
+    coins
+        .iter()
+        .map(|coin| coin.to_string())
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::{self, Rejection};
+    use crate::msg::Scheme;
+    use crate::state::RelayerStats;
+    use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
+    use cosmwasm_std::{coin, coins, from_binary, Addr, SubMsgExecutionResponse, Uint128};
+
+    const MAINNET: &str = "8990e7a9aaed2ffed73dbd7092123d6f289930540d7651336225dc172e51b2ce";
+    const UNCHAINED_TESTNET: &str =
+        "7672797f548f3f4748ac4bf3352fc6c6b6468c9ad40ad456a397545c6e2df5bf";
+    const FASTNET: &str = "dbd506d6ef76e5f386f41c651dcb808c5bcbd75471cc4eafa3f4df7ad4e4c493";
+    const QUICKNET: &str = "52db9ba70e0cc0f6eaf7803dd07447a1f5477735fd3f661792ba94600c84e971";
+
+    const BOUNTY_DENOM: &str = "ucosm";
+
+    /// A block time after all rounds used in the tests were published.
+    /// The default mock time is before the genesis of all networks.
+    fn mock_env_later() -> Env {
+        let mut env = mock_env();
+        env.block.time = Timestamp::from_seconds(1_700_000_000);
+        env
+    }
+
+    #[test]
+    fn proper_initialization() {
+        let mut deps = mock_dependencies();
+
+        let info = mock_info("creator", &coins(1000, "earth"));
+        let msg = InstantiateMsg {
+            networks: vec![fixtures::network(fixtures::MAINNET).network_info()],
+            bounty_denoms: vec![BOUNTY_DENOM.into()],
+            clock_tolerance: 0,
+            bounty_expiry: 86_400,
+            claim_delay: None,
+            reject_duplicates: false,
+            admin: None,
+            job_fee: None,
+        };
+
+        let res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+        assert_eq!(res.messages.len(), 0);
+
+        let response: ConfigResponse =
+            from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::Config {}).unwrap()).unwrap();
+        assert_eq!(
+            response,
+            ConfigResponse {
+                bounty_denoms: vec![BOUNTY_DENOM.into()],
+                clock_tolerance: 0,
+                bounty_expiry: 86_400,
+                claim_delay: None,
+                reject_duplicates: false,
+                job_fee: None,
+            }
         );
-        execute(deps.as_mut(), mock_env(), info, msg).unwrap();
 
-        let response: BountiesResponse =
-            from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::Bounties {}).unwrap()).unwrap();
+        let response: NetworksResponse =
+            from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::Networks {}).unwrap()).unwrap();
         assert_eq!(
             response,
-            BountiesResponse {
-                bounties: vec![Bounty {
-                    round: 7000,
-                    amount: coins(5000, BOUNTY_DENOM),
-                }]
+            NetworksResponse {
+                networks: vec![fixtures::network(fixtures::MAINNET).network_info()],
             }
         );
+    }
 
-        // Increase bounty
+    #[test]
+    fn update_config_works() {
+        let mut deps = mock_dependencies();
 
-        let msg = ExecuteMsg::SetBounty { round: 7000 };
-        let info = mock_info(
-            "anyone",
-            &[Coin {
-                denom: BOUNTY_DENOM.into(),
-                amount: Uint128::new(24),
-            }],
+        let info = mock_info("creator", &[]);
+        let msg = InstantiateMsg {
+            networks: vec![fixtures::network(fixtures::MAINNET).network_info()],
+            bounty_denoms: vec![BOUNTY_DENOM.into()],
+            clock_tolerance: 0,
+            bounty_expiry: 86_400,
+            claim_delay: None,
+            reject_duplicates: false,
+            admin: Some("admin".into()),
+            job_fee: None,
+        };
+        instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let msg = ExecuteMsg::UpdateConfig {
+            bounty_denoms: Some(vec![BOUNTY_DENOM.into(), "uatom".into()]),
+            clock_tolerance: None,
+            bounty_expiry: Some(3600),
+            job_fee: Some(coin(100, "uatom")),
+            claim_delay: None,
+            reject_duplicates: None,
+            networks: vec![fixtures::network(fixtures::QUICKNET).network_info()],
+        };
+
+        // Only the admin can update the config
+
+        let result = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("anyone", &[]),
+            msg.clone(),
+        );
+        match result.unwrap_err() {
+            ContractError::Unauthorized {} => {}
+            err => panic!("Unexpected error: {:?}", err),
+        }
+
+        let response = execute(deps.as_mut(), mock_env(), mock_info("admin", &[]), msg).unwrap();
+        assert_eq!(
+            response.events,
+            vec![Event::new("update_config")
+                .add_attribute("bounty_denoms", "ucosm,uatom")
+                .add_attribute("bounty_expiry", "3600")
+                .add_attribute("job_fee", "100uatom")
+                .add_attribute("network", QUICKNET)]
         );
-        execute(deps.as_mut(), mock_env(), info, msg).unwrap();
-        let response: BountiesResponse =
-            from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::Bounties {}).unwrap()).unwrap();
 
+        let response: ConfigResponse =
+            from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::Config {}).unwrap()).unwrap();
         assert_eq!(
             response,
-            BountiesResponse {
-                bounties: vec![Bounty {
-                    round: 7000,
-                    amount: coins(5024, BOUNTY_DENOM),
-                }]
+            ConfigResponse {
+                bounty_denoms: vec![BOUNTY_DENOM.into(), "uatom".into()],
+                clock_tolerance: 0,
+                bounty_expiry: 3600,
+                claim_delay: None,
+                reject_duplicates: false,
+                job_fee: Some(coin(100, "uatom")),
             }
         );
-    }
+        let response: NetworksResponse =
+            from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::Networks {}).unwrap()).unwrap();
+        assert_eq!(response.networks.len(), 2);
+
+        // Networks are replaced by chain hash, e.g. to fix a wrong pubkey
+
+        let msg = ExecuteMsg::UpdateConfig {
+            bounty_denoms: None,
+            clock_tolerance: None,
+            bounty_expiry: None,
+            job_fee: None,
+            claim_delay: None,
+            reject_duplicates: None,
+            networks: vec![NetworkInfo {
+                period: 60,
+                ..fixtures::network(fixtures::MAINNET).network_info()
+            }],
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("admin", &[]), msg).unwrap();
+        assert_eq!(load_network(&deps.storage, MAINNET).unwrap().period, 60);
+    }
+
+    #[test]
+    fn admin_can_be_transferred_and_renounced() {
+        let mut deps = mock_dependencies();
+
+        let info = mock_info("creator", &[]);
+        let msg = InstantiateMsg {
+            networks: vec![],
+            bounty_denoms: vec![BOUNTY_DENOM.into()],
+            clock_tolerance: 0,
+            bounty_expiry: 86_400,
+            claim_delay: None,
+            reject_duplicates: false,
+            admin: Some("admin".into()),
+            job_fee: None,
+        };
+        instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let query_admin = |deps: Deps| -> Option<String> {
+            let response: AdminResponse =
+                from_binary(&query(deps, mock_env(), QueryMsg::Admin {}).unwrap()).unwrap();
+            response.admin
+        };
+        assert_eq!(query_admin(deps.as_ref()), Some("admin".to_string()));
+
+        // Transfer
+
+        let msg = ExecuteMsg::TransferAdmin {
+            admin: "successor".into(),
+        };
+        let response = execute(deps.as_mut(), mock_env(), mock_info("admin", &[]), msg).unwrap();
+        assert_eq!(
+            response.events,
+            vec![Event::new("update_admin")
+                .add_attribute("previous_admin", "admin")
+                .add_attribute("admin", "successor")]
+        );
+        assert_eq!(query_admin(deps.as_ref()), Some("successor".to_string()));
+
+        // The previous admin lost its rights
+
+        let result = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("admin", &[]),
+            ExecuteMsg::RenounceAdmin {},
+        );
+        match result.unwrap_err() {
+            ContractError::Unauthorized {} => {}
+            err => panic!("Unexpected error: {:?}", err),
+        }
+
+        // Renounce
+
+        let response = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("successor", &[]),
+            ExecuteMsg::RenounceAdmin {},
+        )
+        .unwrap();
+        assert_eq!(
+            response.events,
+            vec![Event::new("update_admin")
+                .add_attribute("previous_admin", "successor")
+                .add_attribute("admin", "")]
+        );
+        assert_eq!(query_admin(deps.as_ref()), None);
+
+        // Nobody can update the config anymore
+
+        let msg = ExecuteMsg::UpdateConfig {
+            bounty_denoms: None,
+            clock_tolerance: Some(5),
+            bounty_expiry: None,
+            job_fee: None,
+            claim_delay: None,
+            reject_duplicates: None,
+            networks: vec![],
+        };
+        let result = execute(deps.as_mut(), mock_env(), mock_info("successor", &[]), msg);
+        match result.unwrap_err() {
+            ContractError::Unauthorized {} => {}
+            err => panic!("Unexpected error: {:?}", err),
+        }
+    }
+
+    #[test]
+    fn set_bounty_works() {
+        let mut deps = mock_dependencies();
+
+        let info = mock_info("creator", &[]);
+        let msg = InstantiateMsg {
+            networks: vec![fixtures::network(fixtures::MAINNET).network_info()],
+            bounty_denoms: vec![BOUNTY_DENOM.into()],
+            clock_tolerance: 0,
+            bounty_expiry: 86_400,
+            claim_delay: None,
+            reject_duplicates: false,
+            admin: None,
+            job_fee: None,
+        };
+        instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        // First bounty
+
+        let msg = ExecuteMsg::SetBounty {
+            network: MAINNET.into(),
+            round: 7000,
+        };
+        let info = mock_info(
+            "anyone",
+            &[Coin {
+                denom: BOUNTY_DENOM.into(),
+                amount: Uint128::new(5000),
+            }],
+        );
+        execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let response: BountiesResponse = from_binary(
+            &query(
+                deps.as_ref(),
+                mock_env(),
+                QueryMsg::Bounties {
+                    network: MAINNET.into(),
+                    start_after: None,
+                    limit: None,
+                    min_round: None,
+                    max_round: None,
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(
+            response,
+            BountiesResponse {
+                bounties: vec![Bounty {
+                    round: 7000,
+                    amount: coins(5000, BOUNTY_DENOM),
+                }]
+            }
+        );
+
+        // Increase bounty
+
+        let msg = ExecuteMsg::SetBounty {
+            network: MAINNET.into(),
+            round: 7000,
+        };
+        let info = mock_info(
+            "anyone",
+            &[Coin {
+                denom: BOUNTY_DENOM.into(),
+                amount: Uint128::new(24),
+            }],
+        );
+        execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        let response: BountiesResponse = from_binary(
+            &query(
+                deps.as_ref(),
+                mock_env(),
+                QueryMsg::Bounties {
+                    network: MAINNET.into(),
+                    start_after: None,
+                    limit: None,
+                    min_round: None,
+                    max_round: None,
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+
+        assert_eq!(
+            response,
+            BountiesResponse {
+                bounties: vec![Bounty {
+                    round: 7000,
+                    amount: coins(5024, BOUNTY_DENOM),
+                }]
+            }
+        );
+    }
+
+    #[test]
+    fn bounties_support_multiple_denoms() {
+        const IBC_DENOM: &str =
+            "ibc/27394FB092D2ECCD56123C74F36E4C1F926001CEADA9CA97EA622B25F41E5EB2";
+
+        let mut deps = mock_dependencies();
+
+        let info = mock_info("creator", &[]);
+        let msg = InstantiateMsg {
+            networks: vec![fixtures::network(fixtures::MAINNET).network_info()],
+            bounty_denoms: vec![BOUNTY_DENOM.into(), IBC_DENOM.into()],
+            clock_tolerance: 0,
+            bounty_expiry: 86_400,
+            claim_delay: None,
+            reject_duplicates: false,
+            admin: None,
+            job_fee: None,
+        };
+        instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let msg = ExecuteMsg::SetBounty {
+            network: MAINNET.into(),
+            round: 72785,
+        };
+
+        // Funds in several accepted denoms
+
+        let funds = [coin(100, BOUNTY_DENOM), coin(7, IBC_DENOM)];
+        let response = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("alice", &funds),
+            msg.clone(),
+        )
+        .unwrap();
+        assert_eq!(
+            response.attributes[0].value,
+            format!("7{},100ucosm", IBC_DENOM)
+        );
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("bob", &[coin(3, IBC_DENOM)]),
+            msg.clone(),
+        )
+        .unwrap();
+
+        // Unaccepted denoms are rejected as a whole
+
+        let funds = [coin(1, BOUNTY_DENOM), coin(5, "uatom")];
+        let result = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("carol", &funds),
+            msg.clone(),
+        );
+        match result.unwrap_err() {
+            ContractError::UnacceptedDenom { denom } => assert_eq!(denom, "uatom"),
+            err => panic!("Unexpected error: {:?}", err),
+        }
+        let result = execute(deps.as_mut(), mock_env(), mock_info("dave", &[]), msg);
+        match result.unwrap_err() {
+            ContractError::NoFundsSent { .. } => {}
+            err => panic!("Unexpected error: {:?}", err),
+        }
+
+        let response: BountiesResponse = from_binary(
+            &query(
+                deps.as_ref(),
+                mock_env(),
+                QueryMsg::Bounties {
+                    network: MAINNET.into(),
+                    start_after: None,
+                    limit: None,
+                    min_round: None,
+                    max_round: None,
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        let expected = vec![coin(10, IBC_DENOM), coin(100, BOUNTY_DENOM)];
+        assert_eq!(
+            response.bounties,
+            vec![Bounty {
+                round: 72785,
+                amount: expected.clone(),
+            }]
+        );
+
+        // All denoms are paid out together
+
+        let msg = ExecuteMsg::Add {
+            network: MAINNET.into(),
+            round: 72785,
+            previous_signature: fixtures::beacon(fixtures::MAINNET, 72785).previous_signature,
+            signature: fixtures::beacon(fixtures::MAINNET, 72785).signature,
+        };
+        let response = execute(
+            deps.as_mut(),
+            mock_env_later(),
+            mock_info("claimer", &[]),
+            msg,
+        )
+        .unwrap();
+        assert_eq!(
+            response.messages,
+            vec![SubMsg::new(BankMsg::Send {
+                to_address: "claimer".into(),
+                amount: expected,
+            })]
+        );
+    }
+
+    #[test]
+    fn add_verifies_and_stores_randomness() {
+        let mut deps = mock_dependencies();
+
+        let info = mock_info("creator", &[]);
+        let msg = InstantiateMsg {
+            networks: vec![fixtures::network(fixtures::MAINNET).network_info()],
+            bounty_denoms: vec![BOUNTY_DENOM.into()],
+            clock_tolerance: 0,
+            bounty_expiry: 86_400,
+            claim_delay: None,
+            reject_duplicates: false,
+            admin: None,
+            job_fee: None,
+        };
+        instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let info = mock_info("anyone", &[]);
+        let msg = ExecuteMsg::Add {
+            network: MAINNET.into(),
+            round: 72785,
+            previous_signature: fixtures::beacon(fixtures::MAINNET, 72785).previous_signature,
+            signature: fixtures::beacon(fixtures::MAINNET, 72785).signature,
+        };
+        execute(deps.as_mut(), mock_env_later(), info, msg).unwrap();
+
+        let response: GetResponse = from_binary(
+            &query(
+                deps.as_ref(),
+                mock_env(),
+                QueryMsg::Get {
+                    network: MAINNET.into(),
+                    round: 72785,
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(
+            response.randomness,
+            fixtures::beacon(fixtures::MAINNET, 72785)
+                .randomness
+                .to_vec()
+        );
+    }
+
+    #[test]
+    fn add_works_for_multiple_networks() {
+        let mut deps = mock_dependencies();
+
+        let info = mock_info("creator", &[]);
+        let msg = InstantiateMsg {
+            networks: vec![
+                fixtures::network(fixtures::MAINNET).network_info(),
+                fixtures::network(fixtures::TESTNET_UNCHAINED).network_info(),
+                fixtures::network(fixtures::FASTNET).network_info(),
+                fixtures::network(fixtures::QUICKNET).network_info(),
+            ],
+            bounty_denoms: vec![BOUNTY_DENOM.into()],
+            clock_tolerance: 0,
+            bounty_expiry: 86_400,
+            claim_delay: None,
+            reject_duplicates: false,
+            admin: None,
+            job_fee: None,
+        };
+        instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        // (network, fixture, round)
+        let cases = [
+            (UNCHAINED_TESTNET, fixtures::TESTNET_UNCHAINED, 223344),
+            (FASTNET, fixtures::FASTNET, 23456),
+            (QUICKNET, fixtures::QUICKNET, 123),
+        ];
+
+        for (network, fixture, round) in cases {
+            let beacon = fixtures::beacon(fixture, round);
+            let msg = ExecuteMsg::Add {
+                network: network.into(),
+                round,
+                previous_signature: Binary::default(),
+                signature: beacon.signature.clone(),
+            };
+            execute(
+                deps.as_mut(),
+                mock_env_later(),
+                mock_info("anyone", &[]),
+                msg,
+            )
+            .unwrap();
+
+            let response: GetResponse = from_binary(
+                &query(
+                    deps.as_ref(),
+                    mock_env(),
+                    QueryMsg::Get {
+                        network: network.into(),
+                        round,
+                    },
+                )
+                .unwrap(),
+            )
+            .unwrap();
+            assert_eq!(response.randomness, beacon.randomness);
+
+            // Wrong round
+            let msg = ExecuteMsg::Add {
+                network: network.into(),
+                round: round + 1,
+                previous_signature: Binary::default(),
+                signature: beacon.signature,
+            };
+            let result = execute(
+                deps.as_mut(),
+                mock_env_later(),
+                mock_info("anyone", &[]),
+                msg,
+            );
+            match result.unwrap_err() {
+                ContractError::InvalidSignature {} => {}
+                err => panic!("Unexpected error: {:?}", err),
+            }
+
+            // Beacon is not visible in other networks
+            let response: GetResponse = from_binary(
+                &query(
+                    deps.as_ref(),
+                    mock_env(),
+                    QueryMsg::Get {
+                        network: MAINNET.into(),
+                        round,
+                    },
+                )
+                .unwrap(),
+            )
+            .unwrap();
+            assert_eq!(response.randomness, Binary::default());
+        }
+
+        // Each network has its own latest beacon
+        let latest: LatestResponse = from_binary(
+            &query(
+                deps.as_ref(),
+                mock_env(),
+                QueryMsg::Latest {
+                    network: QUICKNET.into(),
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(latest.round, 123);
+        let result = query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::Latest {
+                network: MAINNET.into(),
+            },
+        );
+        match result.unwrap_err() {
+            ContractError::NoBeacon {} => {}
+            err => panic!("Unexpected error: {:?}", err),
+        }
+    }
+
+    #[test]
+    fn add_works_for_fixtures() {
+        let mut deps = mock_dependencies();
+        let networks = fixtures::networks();
+
+        let msg = InstantiateMsg {
+            networks: networks.iter().map(|n| n.network_info()).collect(),
+            bounty_denoms: vec![BOUNTY_DENOM.into()],
+            clock_tolerance: 0,
+            bounty_expiry: 86_400,
+            claim_delay: None,
+            reject_duplicates: false,
+            admin: None,
+            job_fee: None,
+        };
+        instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+
+        // Malformed beacons first since known rounds are not verified again
+        for case in fixtures::malformed() {
+            let beacon = match (case.error, case.parse()) {
+                (Rejection::InvalidSignature, Ok(beacon)) => beacon,
+                _ => continue,
+            };
+            let msg = ExecuteMsg::Add {
+                network: fixtures::network(&case.network).chain_hash().into(),
+                round: beacon.round,
+                previous_signature: beacon.previous_signature,
+                signature: beacon.signature,
+            };
+            let result = execute(
+                deps.as_mut(),
+                mock_env_later(),
+                mock_info("anyone", &[]),
+                msg,
+            );
+            match result.unwrap_err() {
+                ContractError::InvalidSignature {} => {}
+                err => panic!("Unexpected error for {}: {:?}", case.description, err),
+            }
+        }
+
+        for network in networks {
+            for beacon in &network.beacons {
+                let msg = ExecuteMsg::Add {
+                    network: network.chain_hash().into(),
+                    round: beacon.round,
+                    previous_signature: beacon.previous_signature.clone(),
+                    signature: beacon.signature.clone(),
+                };
+                execute(
+                    deps.as_mut(),
+                    mock_env_later(),
+                    mock_info("anyone", &[]),
+                    msg,
+                )
+                .unwrap();
+
+                let response: GetResponse = from_binary(
+                    &query(
+                        deps.as_ref(),
+                        mock_env(),
+                        QueryMsg::Get {
+                            network: network.chain_hash().into(),
+                            round: beacon.round,
+                        },
+                    )
+                    .unwrap(),
+                )
+                .unwrap();
+                assert_eq!(response.randomness, beacon.randomness);
+            }
+        }
+    }
+
+    #[test]
+    fn instantiate_fails_for_invalid_networks() {
+        let mut deps = mock_dependencies();
+
+        let msg = InstantiateMsg {
+            networks: vec![NetworkInfo {
+                chain_hash: MAINNET.to_uppercase(),
+                ..fixtures::network(fixtures::MAINNET).network_info()
+            }],
+            bounty_denoms: vec![BOUNTY_DENOM.into()],
+            clock_tolerance: 0,
+            bounty_expiry: 86_400,
+            claim_delay: None,
+            reject_duplicates: false,
+            admin: None,
+            job_fee: None,
+        };
+        let result = instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg);
+        match result.unwrap_err() {
+            ContractError::InvalidChainHash { .. } => {}
+            err => panic!("Unexpected error: {:?}", err),
+        }
+
+        let msg = InstantiateMsg {
+            networks: vec![
+                fixtures::network(fixtures::MAINNET).network_info(),
+                NetworkInfo {
+                    pubkey: fixtures::network(fixtures::QUICKNET).network_info().pubkey,
+                    scheme: Scheme::BlsUnchainedG1Rfc9380,
+                    ..fixtures::network(fixtures::MAINNET).network_info()
+                },
+            ],
+            bounty_denoms: vec![BOUNTY_DENOM.into()],
+            clock_tolerance: 0,
+            bounty_expiry: 86_400,
+            claim_delay: None,
+            reject_duplicates: false,
+            admin: None,
+            job_fee: None,
+        };
+        let result = instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg);
+        match result.unwrap_err() {
+            ContractError::DuplicateNetwork { chain_hash } => assert_eq!(chain_hash, MAINNET),
+            err => panic!("Unexpected error: {:?}", err),
+        }
+
+        let msg = InstantiateMsg {
+            networks: vec![NetworkInfo {
+                period: 0,
+                ..fixtures::network(fixtures::MAINNET).network_info()
+            }],
+            bounty_denoms: vec![BOUNTY_DENOM.into()],
+            clock_tolerance: 0,
+            bounty_expiry: 86_400,
+            claim_delay: None,
+            reject_duplicates: false,
+            admin: None,
+            job_fee: None,
+        };
+        let result = instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg);
+        match result.unwrap_err() {
+            ContractError::InvalidPeriod {} => {}
+            err => panic!("Unexpected error: {:?}", err),
+        }
+    }
+
+    #[test]
+    fn unknown_network_is_rejected() {
+        let mut deps = mock_dependencies();
+
+        let msg = InstantiateMsg {
+            networks: vec![fixtures::network(fixtures::MAINNET).network_info()],
+            bounty_denoms: vec![BOUNTY_DENOM.into()],
+            clock_tolerance: 0,
+            bounty_expiry: 86_400,
+            claim_delay: None,
+            reject_duplicates: false,
+            admin: None,
+            job_fee: None,
+        };
+        instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+
+        let msg = ExecuteMsg::SetBounty {
+            network: QUICKNET.into(),
+            round: 123,
+        };
+        let result = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("anyone", &coins(10, BOUNTY_DENOM)),
+            msg,
+        );
+        match result.unwrap_err() {
+            ContractError::UnknownNetwork { network } => assert_eq!(network, QUICKNET),
+            err => panic!("Unexpected error: {:?}", err),
+        }
+
+        let result = query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::Bounties {
+                network: QUICKNET.into(),
+                start_after: None,
+                limit: None,
+                min_round: None,
+                max_round: None,
+            },
+        );
+        match result.unwrap_err() {
+            ContractError::UnknownNetwork { network } => assert_eq!(network, QUICKNET),
+            err => panic!("Unexpected error: {:?}", err),
+        }
+    }
+
+    #[test]
+    fn add_fails_for_scheme_mismatch() {
+        let mut deps = mock_dependencies();
+
+        // Quicknet pubkey configured with the fastnet scheme
+        let info = mock_info("creator", &[]);
+        let msg = InstantiateMsg {
+            networks: vec![NetworkInfo {
+                scheme: Scheme::BlsUnchainedOnG1,
+                ..fixtures::network(fixtures::QUICKNET).network_info()
+            }],
+            bounty_denoms: vec![BOUNTY_DENOM.into()],
+            clock_tolerance: 0,
+            bounty_expiry: 86_400,
+            claim_delay: None,
+            reject_duplicates: false,
+            admin: None,
+            job_fee: None,
+        };
+        instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let msg = ExecuteMsg::Add {
+            network: QUICKNET.into(),
+            round: 123,
+            previous_signature: Binary::default(),
+            signature: fixtures::beacon(fixtures::QUICKNET, 123).signature,
+        };
+        let result = execute(
+            deps.as_mut(),
+            mock_env_later(),
+            mock_info("anyone", &[]),
+            msg,
+        );
+        match result.unwrap_err() {
+            ContractError::InvalidSignature {} => {}
+            err => panic!("Unexpected error: {:?}", err),
+        }
+
+        // Mainnet pubkey is not a valid G2 point, which is caught at instantiation
+        let mut deps = mock_dependencies();
+        let msg = InstantiateMsg {
+            networks: vec![NetworkInfo {
+                pubkey: fixtures::network(fixtures::MAINNET).network_info().pubkey,
+                ..fixtures::network(fixtures::QUICKNET).network_info()
+            }],
+            bounty_denoms: vec![BOUNTY_DENOM.into()],
+            clock_tolerance: 0,
+            bounty_expiry: 86_400,
+            claim_delay: None,
+            reject_duplicates: false,
+            admin: None,
+            job_fee: None,
+        };
+        let result = instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg);
+        match result.unwrap_err() {
+            ContractError::MalformedPubkey { chain_hash, reason } => {
+                assert_eq!(chain_hash, QUICKNET);
+                assert_eq!(reason, "expected a G2 point: Invalid input length for point (must be in compressed format): Expected 96, actual: 48");
+            }
+            err => panic!("Unexpected error: {:?}", err),
+        }
+    }
+
+    #[test]
+    fn add_fails_for_rounds_in_the_future() {
+        // published at 1597614570
+        let msg = ExecuteMsg::Add {
+            network: MAINNET.into(),
+            round: 72785,
+            previous_signature: fixtures::beacon(fixtures::MAINNET, 72785).previous_signature,
+            signature: fixtures::beacon(fixtures::MAINNET, 72785).signature,
+        };
+        let mut env = mock_env();
+        env.block.time = Timestamp::from_seconds(1597614560);
+
+        // Without tolerance
+        let mut deps = mock_dependencies();
+        let instantiate_msg = InstantiateMsg {
+            networks: vec![fixtures::network(fixtures::MAINNET).network_info()],
+            bounty_denoms: vec![BOUNTY_DENOM.into()],
+            clock_tolerance: 0,
+            bounty_expiry: 86_400,
+            claim_delay: None,
+            reject_duplicates: false,
+            admin: None,
+            job_fee: None,
+        };
+        instantiate(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("creator", &[]),
+            instantiate_msg.clone(),
+        )
+        .unwrap();
+        let result = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("anyone", &[]),
+            msg.clone(),
+        );
+        match result.unwrap_err() {
+            ContractError::RoundInFuture {
+                round: 72785,
+                latest_round: 72784,
+            } => {}
+            err => panic!("Unexpected error: {:?}", err),
+        }
+
+        // With tolerance
+        let mut deps = mock_dependencies();
+        let instantiate_msg = InstantiateMsg {
+            clock_tolerance: 10,
+            ..instantiate_msg
+        };
+        instantiate(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("creator", &[]),
+            instantiate_msg.clone(),
+        )
+        .unwrap();
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("anyone", &[]),
+            msg.clone(),
+        )
+        .unwrap();
+
+        // Tolerance too large to be added to the block time
+        let mut deps = mock_dependencies();
+        let instantiate_msg = InstantiateMsg {
+            clock_tolerance: u64::MAX,
+            ..instantiate_msg
+        };
+        instantiate(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("creator", &[]),
+            instantiate_msg,
+        )
+        .unwrap();
+        execute(deps.as_mut(), env, mock_info("anyone", &[]), msg).unwrap();
+    }
+
+    #[test]
+    fn set_bounty_fails_for_published_round() {
+        let mut deps = mock_dependencies();
+
+        let msg = InstantiateMsg {
+            networks: vec![fixtures::network(fixtures::MAINNET).network_info()],
+            bounty_denoms: vec![BOUNTY_DENOM.into()],
+            clock_tolerance: 0,
+            bounty_expiry: 86_400,
+            claim_delay: None,
+            reject_duplicates: false,
+            admin: None,
+            job_fee: None,
+        };
+        instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+
+        // Round 72785 is published at 1597614570
+        let msg = ExecuteMsg::SetBounty {
+            network: MAINNET.into(),
+            round: 72785,
+        };
+        let info = mock_info("anyone", &coins(4500, BOUNTY_DENOM));
+
+        let mut env = mock_env();
+        env.block.time = Timestamp::from_seconds(1597614570);
+        let result = execute(deps.as_mut(), env, info.clone(), msg.clone());
+        match result.unwrap_err() {
+            ContractError::RoundAlreadyPublished { round: 72785 } => {}
+            err => panic!("Unexpected error: {:?}", err),
+        }
+
+        let mut env = mock_env();
+        env.block.time = Timestamp::from_seconds(1597614569);
+        execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+        // Rounds published more than a year later are rejected
+        let msg = ExecuteMsg::SetBounty {
+            network: MAINNET.into(),
+            round: 1123985,
+        };
+        let result = execute(deps.as_mut(), env.clone(), info.clone(), msg);
+        match result.unwrap_err() {
+            ContractError::RoundTooFarAhead {
+                round: 1123985,
+                max_round: 1123984,
+            } => {}
+            err => panic!("Unexpected error: {:?}", err),
+        }
+        let msg = ExecuteMsg::SetBounty {
+            network: MAINNET.into(),
+            round: 1123984,
+        };
+        execute(deps.as_mut(), env, info, msg).unwrap();
+    }
+
+    #[test]
+    fn instantiate_fails_when_pubkey_is_invalid() {
+        let mut deps = mock_dependencies();
+
+        let info = mock_info("creator", &[]);
+        let mut broken: Vec<u8> = fixtures::network(fixtures::MAINNET)
+            .network_info()
+            .pubkey
+            .into();
+        broken.push(0xF9);
+        let msg = InstantiateMsg {
+            networks: vec![NetworkInfo {
+                pubkey: broken.into(),
+                ..fixtures::network(fixtures::MAINNET).network_info()
+            }],
+            bounty_denoms: vec![BOUNTY_DENOM.into()],
+            clock_tolerance: 0,
+            bounty_expiry: 86_400,
+            claim_delay: None,
+            reject_duplicates: false,
+            admin: None,
+            job_fee: None,
+        };
+        let result = instantiate(deps.as_mut(), mock_env(), info, msg);
+        match result.unwrap_err() {
+            ContractError::MalformedPubkey { chain_hash, reason } => {
+                assert_eq!(chain_hash, MAINNET);
+                assert_eq!(reason, "expected a G1 point: Invalid input length for point (must be in compressed format): Expected 48, actual: 49");
+            }
+            err => panic!("Unexpected error: {:?}", err),
+        }
+    }
+
+    #[test]
+    fn instantiate_verifies_sample_beacon() {
+        let sample = BeaconSubmission {
+            round: 72785,
+            previous_signature: fixtures::beacon(fixtures::MAINNET, 72785).previous_signature,
+            signature: fixtures::beacon(fixtures::MAINNET, 72785).signature,
+        };
+        let instantiate_msg = |pubkey: Binary, sample_beacon: BeaconSubmission| InstantiateMsg {
+            networks: vec![NetworkInfo {
+                pubkey,
+                sample_beacon: Some(sample_beacon),
+                ..fixtures::network(fixtures::MAINNET).network_info()
+            }],
+            bounty_denoms: vec![BOUNTY_DENOM.into()],
+            clock_tolerance: 0,
+            bounty_expiry: 86_400,
+            claim_delay: None,
+            reject_duplicates: false,
+            admin: Some("admin".into()),
+            job_fee: None,
+        };
+
+        let mut deps = mock_dependencies();
+        let msg = instantiate_msg(
+            fixtures::network(fixtures::MAINNET).network_info().pubkey,
+            sample.clone(),
+        );
+        instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+
+        // A valid point that is not the network's key
+        let mut deps = mock_dependencies();
+        let msg = instantiate_msg(
+            fixtures::network(fixtures::TESTNET_UNCHAINED)
+                .network_info()
+                .pubkey,
+            sample.clone(),
+        );
+        let result = instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg);
+        match result.unwrap_err() {
+            ContractError::InvalidSampleBeacon { chain_hash, round } => {
+                assert_eq!(chain_hash, MAINNET);
+                assert_eq!(round, 72785);
+            }
+            err => panic!("Unexpected error: {:?}", err),
+        }
+
+        // The same checks apply to config updates
+        let mut deps = mock_dependencies();
+        let msg = instantiate_msg(
+            fixtures::network(fixtures::MAINNET).network_info().pubkey,
+            sample.clone(),
+        );
+        instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+        let msg = ExecuteMsg::UpdateConfig {
+            bounty_denoms: None,
+            clock_tolerance: None,
+            bounty_expiry: None,
+            job_fee: None,
+            claim_delay: None,
+            reject_duplicates: None,
+            networks: instantiate_msg(
+                fixtures::network(fixtures::TESTNET_UNCHAINED)
+                    .network_info()
+                    .pubkey,
+                sample,
+            )
+            .networks,
+        };
+        let result = execute(deps.as_mut(), mock_env(), mock_info("admin", &[]), msg);
+        match result.unwrap_err() {
+            ContractError::InvalidSampleBeacon { .. } => {}
+            err => panic!("Unexpected error: {:?}", err),
+        }
+        assert_eq!(
+            load_network(&deps.storage, MAINNET).unwrap().pubkey,
+            fixtures::network(fixtures::MAINNET).network_info().pubkey
+        );
+    }
+
+    #[test]
+    fn add_fails_for_broken_signature() {
+        let mut deps = mock_dependencies();
+
+        let info = mock_info("creator", &[]);
+        let msg = InstantiateMsg {
+            networks: vec![fixtures::network(fixtures::MAINNET).network_info()],
+            bounty_denoms: vec![BOUNTY_DENOM.into()],
+            clock_tolerance: 0,
+            bounty_expiry: 86_400,
+            claim_delay: None,
+            reject_duplicates: false,
+            admin: None,
+            job_fee: None,
+        };
+        instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let info = mock_info("anyone", &[]);
+        let msg = ExecuteMsg::Add {
+            network: MAINNET.into(),
+            round: 72785,
+            previous_signature: fixtures::beacon(fixtures::MAINNET, 72785).previous_signature,
+            signature: hex::decode("3cc6f6cdf59e95526d5a5d82aaa84fa6f181e4")
+                .unwrap()
+                .into(), // broken signature
+        };
+        let result = execute(deps.as_mut(), mock_env_later(), info, msg);
+        match result.unwrap_err() {
+            ContractError::InvalidSignature {} => {}
+            err => panic!("Unexpected error: {:?}", err),
+        }
+    }
+
+    #[test]
+    fn add_fails_for_invalid_signature() {
+        let mut deps = mock_dependencies();
+
+        let info = mock_info("creator", &[]);
+        let msg = InstantiateMsg {
+            networks: vec![fixtures::network(fixtures::MAINNET).network_info()],
+            bounty_denoms: vec![BOUNTY_DENOM.into()],
+            clock_tolerance: 0,
+            bounty_expiry: 86_400,
+            claim_delay: None,
+            reject_duplicates: false,
+            admin: None,
+            job_fee: None,
+        };
+        instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let info = mock_info("anyone", &[]);
+        let msg = ExecuteMsg::Add {
+            network: MAINNET.into(),
+            round: 1111, // wrong round
+            previous_signature: fixtures::beacon(fixtures::MAINNET, 72785).previous_signature,
+            signature: fixtures::beacon(fixtures::MAINNET, 72785).signature,
+        };
+        let result = execute(deps.as_mut(), mock_env_later(), info, msg);
+        match result.unwrap_err() {
+            ContractError::InvalidSignature {} => {}
+            err => panic!("Unexpected error: {:?}", err),
+        }
+    }
+
+    #[test]
+    fn add_handles_known_rounds_according_to_config() {
+        let setup = |reject_duplicates| {
+            let mut deps = mock_dependencies();
+
+            let msg = InstantiateMsg {
+                networks: vec![fixtures::network(fixtures::MAINNET).network_info()],
+                bounty_denoms: vec![BOUNTY_DENOM.into()],
+                clock_tolerance: 0,
+                bounty_expiry: 86_400,
+                claim_delay: None,
+                reject_duplicates,
+                admin: None,
+                job_fee: None,
+            };
+            instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+
+            let msg = ExecuteMsg::AddBatch {
+                network: MAINNET.into(),
+                beacons: batch_42_45(false),
+                mode: BatchMode::Strict,
+            };
+            execute(
+                deps.as_mut(),
+                mock_env_later(),
+                mock_info("anyone", &[]),
+                msg,
+            )
+            .unwrap();
+            deps
+        };
+        // The known round is detected before verification, so a broken signature does not matter
+        let add_45_again = || ExecuteMsg::Add {
+            network: MAINNET.into(),
+            round: 45,
+            previous_signature: batch_42_45(false)[1].previous_signature.clone(),
+            signature: batch_42_45(true)[1].signature.clone(),
+        };
+
+        // No-op by default
+
+        let mut deps = setup(false);
+        let info = mock_info("relayer", &[]);
+        let response = execute(deps.as_mut(), mock_env_later(), info, add_45_again()).unwrap();
+        assert_eq!(response.messages.len(), 0);
+        assert_eq!(response.attributes.len(), 1);
+        assert_eq!(response.attributes[0].key, "already_known");
+        assert_eq!(response.attributes[0].value, "45");
+
+        let msg = ExecuteMsg::AddBatch {
+            network: MAINNET.into(),
+            beacons: batch_42_45(false),
+            mode: BatchMode::Strict,
+        };
+        let info = mock_info("relayer", &[]);
+        let response = execute(deps.as_mut(), mock_env_later(), info, msg).unwrap();
+        assert_eq!(response.attributes.len(), 2);
+        assert_eq!(response.attributes[0].key, "already_known");
+        assert_eq!(response.attributes[0].value, "42");
+        assert_eq!(response.attributes[1].key, "already_known");
+        assert_eq!(response.attributes[1].value, "45");
+
+        // Error if configured
+
+        let mut deps = setup(true);
+        let info = mock_info("relayer", &[]);
+        let result = execute(deps.as_mut(), mock_env_later(), info, add_45_again());
+        match result.unwrap_err() {
+            ContractError::BeaconExists { round } => assert_eq!(round, 45),
+            err => panic!("Unexpected error: {:?}", err),
+        }
+
+        // The stored beacon is untouched
+        let beacon = beacons_storage_read(&deps.storage, MAINNET)
+            .load(&45u64.to_be_bytes())
+            .unwrap();
+        assert_eq!(beacon.signature, batch_42_45(false)[1].signature);
+        assert_eq!(beacon.submitter, "anyone");
+    }
+
+    #[test]
+    fn update_config_switches_reject_duplicates() {
+        let mut deps = mock_dependencies();
+
+        let msg = InstantiateMsg {
+            networks: vec![fixtures::network(fixtures::MAINNET).network_info()],
+            bounty_denoms: vec![BOUNTY_DENOM.into()],
+            clock_tolerance: 0,
+            bounty_expiry: 86_400,
+            claim_delay: None,
+            reject_duplicates: false,
+            admin: Some("admin".into()),
+            job_fee: None,
+        };
+        instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+
+        let add_42 = || {
+            let beacon = fixtures::beacon(fixtures::MAINNET, 42);
+            ExecuteMsg::Add {
+                network: MAINNET.into(),
+                round: 42,
+                previous_signature: beacon.previous_signature,
+                signature: beacon.signature,
+            }
+        };
+        let info = mock_info("relayer", &[]);
+        execute(deps.as_mut(), mock_env_later(), info.clone(), add_42()).unwrap();
+        let response = execute(deps.as_mut(), mock_env_later(), info.clone(), add_42()).unwrap();
+        assert_eq!(response.attributes[0].key, "already_known");
+
+        let update = |reject_duplicates| ExecuteMsg::UpdateConfig {
+            bounty_denoms: None,
+            clock_tolerance: None,
+            bounty_expiry: None,
+            job_fee: None,
+            claim_delay: None,
+            reject_duplicates: Some(reject_duplicates),
+            networks: vec![],
+        };
+        let response = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("admin", &[]),
+            update(true),
+        )
+        .unwrap();
+        assert_eq!(
+            response.events,
+            vec![Event::new("update_config").add_attribute("reject_duplicates", "true")]
+        );
+        let config: ConfigResponse =
+            from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::Config {}).unwrap()).unwrap();
+        assert!(config.reject_duplicates);
+
+        let result = execute(deps.as_mut(), mock_env_later(), info.clone(), add_42());
+        match result.unwrap_err() {
+            ContractError::BeaconExists { round } => assert_eq!(round, 42),
+            err => panic!("Unexpected error: {:?}", err),
+        }
+
+        // And back
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("admin", &[]),
+            update(false),
+        )
+        .unwrap();
+        let response = execute(deps.as_mut(), mock_env_later(), info, add_42()).unwrap();
+        assert_eq!(response.attributes[0].key, "already_known");
+    }
+
+    #[test]
+    fn add_receives_bounty() {
+        let mut deps = mock_dependencies();
+
+        let info = mock_info("creator", &[]);
+        let msg = InstantiateMsg {
+            networks: vec![fixtures::network(fixtures::MAINNET).network_info()],
+            bounty_denoms: vec![BOUNTY_DENOM.into()],
+            clock_tolerance: 0,
+            bounty_expiry: 86_400,
+            claim_delay: None,
+            reject_duplicates: false,
+            admin: None,
+            job_fee: None,
+        };
+        instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        // Set bounty
+
+        let msg = ExecuteMsg::SetBounty {
+            network: MAINNET.into(),
+            round: 72785,
+        };
+        let info = mock_info(
+            "anyone",
+            &[Coin {
+                denom: BOUNTY_DENOM.into(),
+                amount: Uint128::new(4500),
+            }],
+        );
+        execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        // Claim bounty
+
+        let info = mock_info("claimer", &[]);
+        let msg = ExecuteMsg::Add {
+            network: MAINNET.into(),
+            round: 72785,
+            previous_signature: fixtures::beacon(fixtures::MAINNET, 72785).previous_signature,
+            signature: fixtures::beacon(fixtures::MAINNET, 72785).signature,
+        };
+        let response = execute(deps.as_mut(), mock_env_later(), info, msg).unwrap();
+        assert_eq!(response.messages.len(), 1);
+        assert_eq!(
+            response.messages[0],
+            SubMsg::new(BankMsg::Send {
+                to_address: Addr::unchecked("claimer").to_string(),
+                amount: coins(4500, BOUNTY_DENOM),
+            })
+        );
+
+        // Cannot be claimed again
+
+        let info = mock_info("claimer2", &[]);
+        let msg = ExecuteMsg::Add {
+            network: MAINNET.into(),
+            round: 72785,
+            previous_signature: fixtures::beacon(fixtures::MAINNET, 72785).previous_signature,
+            signature: fixtures::beacon(fixtures::MAINNET, 72785).signature,
+        };
+        let response = execute(deps.as_mut(), mock_env_later(), info, msg).unwrap();
+        assert_eq!(response.messages.len(), 0);
+    }
+
+    #[test]
+    fn typed_events_are_emitted() {
+        let mut deps = mock_dependencies();
+
+        let info = mock_info("creator", &[]);
+        let msg = InstantiateMsg {
+            networks: vec![fixtures::network(fixtures::MAINNET).network_info()],
+            bounty_denoms: vec![BOUNTY_DENOM.into(), "uatom".into()],
+            clock_tolerance: 0,
+            bounty_expiry: 86_400,
+            claim_delay: None,
+            reject_duplicates: false,
+            admin: None,
+            job_fee: None,
+        };
+        instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let msg = ExecuteMsg::SetBounty {
+            network: MAINNET.into(),
+            round: 72785,
+        };
+        let info = mock_info("funder", &[coin(4500, BOUNTY_DENOM), coin(7, "uatom")]);
+        let response = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        assert_eq!(
+            response.events,
+            vec![
+                Event::new("bounty_set")
+                    .add_attribute("network", MAINNET)
+                    .add_attribute("round", "72785")
+                    .add_attribute("funder", "funder")
+                    .add_attribute("amount", "4500")
+                    .add_attribute("denom", BOUNTY_DENOM),
+                Event::new("bounty_set")
+                    .add_attribute("network", MAINNET)
+                    .add_attribute("round", "72785")
+                    .add_attribute("funder", "funder")
+                    .add_attribute("amount", "7")
+                    .add_attribute("denom", "uatom"),
+            ]
+        );
+
+        let msg = ExecuteMsg::Add {
+            network: MAINNET.into(),
+            round: 72785,
+            previous_signature: fixtures::beacon(fixtures::MAINNET, 72785).previous_signature,
+            signature: fixtures::beacon(fixtures::MAINNET, 72785).signature,
+        };
+        let info = mock_info("relayer", &[]);
+        let response = execute(deps.as_mut(), mock_env_later(), info, msg).unwrap();
+        assert_eq!(
+            response.events,
+            vec![
+                Event::new("drand_beacon")
+                    .add_attribute("network", MAINNET)
+                    .add_attribute("round", "72785")
+                    .add_attribute(
+                        "randomness",
+                        hex::encode(
+                            fixtures::beacon(fixtures::MAINNET, 72785)
+                                .randomness
+                                .as_slice()
+                        )
+                    )
+                    .add_attribute("submitter", "relayer"),
+                Event::new("bounty_paid")
+                    .add_attribute("network", MAINNET)
+                    .add_attribute("round", "72785")
+                    .add_attribute("recipient", "relayer")
+                    .add_attribute("amount", "7")
+                    .add_attribute("denom", "uatom"),
+                Event::new("bounty_paid")
+                    .add_attribute("network", MAINNET)
+                    .add_attribute("round", "72785")
+                    .add_attribute("recipient", "relayer")
+                    .add_attribute("amount", "4500")
+                    .add_attribute("denom", BOUNTY_DENOM),
+            ]
+        );
+    }
+
+    #[test]
+    fn withdraw_bounty_works() {
+        let mut deps = mock_dependencies();
+
+        let info = mock_info("creator", &[]);
+        let msg = InstantiateMsg {
+            networks: vec![fixtures::network(fixtures::MAINNET).network_info()],
+            bounty_denoms: vec![BOUNTY_DENOM.into()],
+            clock_tolerance: 0,
+            bounty_expiry: 3600,
+            claim_delay: None,
+            reject_duplicates: false,
+            admin: None,
+            job_fee: None,
+        };
+        instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        // Set bounties
+
+        for (funder, round, amount) in [
+            ("alice", 72785, 1000),
+            ("bob", 72785, 500),
+            ("alice", 72786, 200),
+        ] {
+            let msg = ExecuteMsg::SetBounty {
+                network: MAINNET.into(),
+                round,
+            };
+            let info = mock_info(funder, &coins(amount, BOUNTY_DENOM));
+            execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        }
+
+        // Round 72785 is published at 1597614570
+        let mut env = mock_env();
+        let withdrawable = |deps: Deps, env: Env, address: &str| -> Vec<Bounty> {
+            let response: WithdrawableResponse = from_binary(
+                &query(
+                    deps,
+                    env,
+                    QueryMsg::Withdrawable {
+                        network: MAINNET.into(),
+                        address: address.into(),
+                        start_after: None,
+                        limit: None,
+                    },
+                )
+                .unwrap(),
+            )
+            .unwrap();
+            response.withdrawable
+        };
+        let withdraw_msg = ExecuteMsg::WithdrawBounty {
+            network: MAINNET.into(),
+            round: 72785,
+        };
+
+        // Cannot be withdrawn before expiry
+
+        env.block.time = Timestamp::from_seconds(1597614570 + 3599);
+        assert_eq!(withdrawable(deps.as_ref(), env.clone(), "alice"), vec![]);
+        let result = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("alice", &[]),
+            withdraw_msg.clone(),
+        );
+        match result.unwrap_err() {
+            ContractError::BountyNotExpired { round, expires_at } => {
+                assert_eq!(round, 72785);
+                assert_eq!(expires_at, 1597618170);
+            }
+            err => panic!("Unexpected error: {:?}", err),
+        }
+
+        // Withdraw own share after expiry
+
+        env.block.time = Timestamp::from_seconds(1597614570 + 3600);
+        assert_eq!(
+            withdrawable(deps.as_ref(), env.clone(), "alice"),
+            vec![Bounty {
+                round: 72785,
+                amount: coins(1000, BOUNTY_DENOM),
+            }]
+        );
+        let response = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("alice", &[]),
+            withdraw_msg.clone(),
+        )
+        .unwrap();
+        assert_eq!(
+            response.messages,
+            vec![SubMsg::new(BankMsg::Send {
+                to_address: "alice".into(),
+                amount: coins(1000, BOUNTY_DENOM),
+            })]
+        );
+        assert_eq!(withdrawable(deps.as_ref(), env.clone(), "alice"), vec![]);
+        assert_eq!(
+            get_bounty(&deps.storage, MAINNET, 72785).unwrap(),
+            coins(500, BOUNTY_DENOM)
+        );
+
+        // No second withdrawal and nothing for non-contributors
+
+        for sender in ["alice", "carol"] {
+            let result = execute(
+                deps.as_mut(),
+                env.clone(),
+                mock_info(sender, &[]),
+                withdraw_msg.clone(),
+            );
+            match result.unwrap_err() {
+                ContractError::NoContribution { round } => assert_eq!(round, 72785),
+                err => panic!("Unexpected error: {:?}", err),
+            }
+        }
+
+        // Adding the round pays out the remaining bounty, after which it cannot be withdrawn
+
+        let msg = ExecuteMsg::Add {
+            network: MAINNET.into(),
+            round: 72785,
+            previous_signature: fixtures::beacon(fixtures::MAINNET, 72785).previous_signature,
+            signature: fixtures::beacon(fixtures::MAINNET, 72785).signature,
+        };
+        let response = execute(deps.as_mut(), env.clone(), mock_info("claimer", &[]), msg).unwrap();
+        assert_eq!(
+            response.messages[0],
+            SubMsg::new(BankMsg::Send {
+                to_address: "claimer".into(),
+                amount: coins(500, BOUNTY_DENOM),
+            })
+        );
+        assert_eq!(withdrawable(deps.as_ref(), env.clone(), "bob"), vec![]);
+        let result = execute(deps.as_mut(), env, mock_info("bob", &[]), withdraw_msg);
+        match result.unwrap_err() {
+            ContractError::NoContribution { round } => assert_eq!(round, 72785),
+            err => panic!("Unexpected error: {:?}", err),
+        }
+    }
+
+    fn batch_42_45(broken_signature_for_45: bool) -> Vec<BeaconSubmission> {
+        let mut beacons: Vec<BeaconSubmission> = [42, 45]
+            .iter()
+            .map(|&round| fixtures::beacon(fixtures::MAINNET, round).into())
+            .collect();
+        if broken_signature_for_45 {
+            beacons[1].signature = hex::decode("3cc6f6cdf59e95526d5a5d82aaa84fa6f181e4")
+                .unwrap()
+                .into();
+        }
+        beacons
+    }
+
+    #[test]
+    fn withdraw_bounty_clears_commitments() {
+        let mut deps = mock_dependencies();
+
+        let msg = InstantiateMsg {
+            networks: vec![fixtures::network(fixtures::MAINNET).network_info()],
+            bounty_denoms: vec![BOUNTY_DENOM.into()],
+            clock_tolerance: 0,
+            bounty_expiry: 3600,
+            claim_delay: Some(10),
+            reject_duplicates: false,
+            admin: None,
+            job_fee: None,
+        };
+        instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+
+        for funder in ["alice", "bob"] {
+            let msg = ExecuteMsg::SetBounty {
+                network: MAINNET.into(),
+                round: 72785,
+            };
+            let info = mock_info(funder, &coins(100, BOUNTY_DENOM));
+            execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        }
+        let msg = ExecuteMsg::CommitBounty {
+            network: MAINNET.into(),
+            round: 72785,
+            commitment: bounty_commitment("carol", 72785, b"signature")
+                .to_vec()
+                .into(),
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("carol", &[]), msg).unwrap();
+        let commitments = |deps: Deps| {
+            commitments_storage_read(deps.storage, MAINNET, 72785)
+                .range(None, None, Order::Ascending)
+                .count()
+        };
+
+        // Round 72785 is published at 1597614570
+        let mut env = mock_env();
+        env.block.time = Timestamp::from_seconds(1597614570 + 3600);
+        let msg = ExecuteMsg::WithdrawBounty {
+            network: MAINNET.into(),
+            round: 72785,
+        };
+
+        // Commitments are kept while a share of the bounty is left
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("alice", &[]),
+            msg.clone(),
+        )
+        .unwrap();
+        assert_eq!(commitments(deps.as_ref()), 1);
+
+        execute(deps.as_mut(), env, mock_info("bob", &[]), msg).unwrap();
+        assert_eq!(commitments(deps.as_ref()), 0);
+    }
+
+    #[test]
+    fn query_withdrawable_is_paginated() {
+        let mut deps = mock_dependencies();
+
+        let msg = InstantiateMsg {
+            networks: vec![fixtures::network(fixtures::MAINNET).network_info()],
+            bounty_denoms: vec![BOUNTY_DENOM.into()],
+            clock_tolerance: 0,
+            bounty_expiry: 3600,
+            claim_delay: None,
+            reject_duplicates: false,
+            admin: None,
+            job_fee: None,
+        };
+        instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+
+        for (funder, round) in [
+            ("alice", 7001),
+            ("bob", 7002),
+            ("alice", 7003),
+            ("alice", 7004),
+            ("bob", 7005),
+        ] {
+            let msg = ExecuteMsg::SetBounty {
+                network: MAINNET.into(),
+                round,
+            };
+            let info = mock_info(funder, &coins(round as u128, BOUNTY_DENOM));
+            execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        }
+
+        // All rounds expired
+        let mut env = mock_env();
+        env.block.time = Timestamp::from_seconds(1595700000);
+        let withdrawable = |start_after: Option<u64>| -> WithdrawableResponse {
+            let msg = QueryMsg::Withdrawable {
+                network: MAINNET.into(),
+                address: "alice".into(),
+                start_after,
+                limit: Some(2),
+            };
+            from_binary(&query(deps.as_ref(), env.clone(), msg).unwrap()).unwrap()
+        };
+        let bounty = |round: u64| Bounty {
+            round,
+            amount: coins(round as u128, BOUNTY_DENOM),
+        };
+
+        assert_eq!(
+            withdrawable(None),
+            WithdrawableResponse {
+                withdrawable: vec![bounty(7001)],
+                last_round: Some(7002),
+            }
+        );
+        assert_eq!(
+            withdrawable(Some(7002)),
+            WithdrawableResponse {
+                withdrawable: vec![bounty(7003), bounty(7004)],
+                last_round: Some(7004),
+            }
+        );
+        assert_eq!(
+            withdrawable(Some(7004)),
+            WithdrawableResponse {
+                withdrawable: vec![],
+                last_round: None,
+            }
+        );
+        assert_eq!(
+            withdrawable(Some(u64::MAX)),
+            WithdrawableResponse {
+                withdrawable: vec![],
+                last_round: None,
+            }
+        );
+    }
+
+    #[test]
+    fn add_batch_pays_all_bounties_at_once() {
+        let mut deps = mock_dependencies();
+
+        let msg = InstantiateMsg {
+            networks: vec![fixtures::network(fixtures::MAINNET).network_info()],
+            bounty_denoms: vec![BOUNTY_DENOM.into()],
+            clock_tolerance: 0,
+            bounty_expiry: 86_400,
+            claim_delay: None,
+            reject_duplicates: false,
+            admin: None,
+            job_fee: None,
+        };
+        instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+
+        for (round, amount) in [(42, 100), (45, 23)] {
+            let msg = ExecuteMsg::SetBounty {
+                network: MAINNET.into(),
+                round,
+            };
+            let info = mock_info("anyone", &coins(amount, BOUNTY_DENOM));
+            execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        }
+
+        let msg = ExecuteMsg::AddBatch {
+            network: MAINNET.into(),
+            beacons: batch_42_45(false),
+            mode: BatchMode::Strict,
+        };
+        let response = execute(
+            deps.as_mut(),
+            mock_env_later(),
+            mock_info("relayer", &[]),
+            msg,
+        )
+        .unwrap();
+        assert_eq!(
+            response.messages,
+            vec![SubMsg::new(BankMsg::Send {
+                to_address: "relayer".into(),
+                amount: coins(123, BOUNTY_DENOM),
+            })]
+        );
+        assert_eq!(response.attributes.len(), 2);
+        assert_eq!(response.attributes[0].key, "added");
+        assert_eq!(response.attributes[0].value, "42");
+        assert_eq!(response.attributes[1].key, "added");
+        assert_eq!(response.attributes[1].value, "45");
+
+        let latest: LatestResponse = from_binary(
+            &query(
+                deps.as_ref(),
+                mock_env(),
+                QueryMsg::Latest {
+                    network: MAINNET.into(),
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(latest.round, 45);
+    }
+
+    #[test]
+    fn commit_reveal_protects_bounties() {
+        let mut deps = mock_dependencies();
+
+        let info = mock_info("creator", &[]);
+        let msg = InstantiateMsg {
+            networks: vec![fixtures::network(fixtures::MAINNET).network_info()],
+            bounty_denoms: vec![BOUNTY_DENOM.into()],
+            clock_tolerance: 0,
+            bounty_expiry: 86_400,
+            claim_delay: Some(10),
+            reject_duplicates: false,
+            admin: None,
+            job_fee: None,
+        };
+        instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        for (round, amount) in [(45, 23), (72785, 4500)] {
+            let msg = ExecuteMsg::SetBounty {
+                network: MAINNET.into(),
+                round,
+            };
+            let info = mock_info("anyone", &coins(amount, BOUNTY_DENOM));
+            execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        }
+
+        let signature = fixtures::beacon(fixtures::MAINNET, 72785)
+            .signature
+            .to_vec();
+        let commit = |deps: DepsMut, height: u64, sender: &str, commitment: [u8; 32]| {
+            let mut env = mock_env_later();
+            env.block.height = height;
+            let msg = ExecuteMsg::CommitBounty {
+                network: MAINNET.into(),
+                round: 72785,
+                commitment: commitment.to_vec().into(),
+            };
+            execute(deps, env, mock_info(sender, &[]), msg)
+        };
+        let claim = |deps: DepsMut, height: u64, sender: &str| {
+            let mut env = mock_env_later();
+            env.block.height = height;
+            let msg = ExecuteMsg::ClaimBounty {
+                network: MAINNET.into(),
+                round: 72785,
+            };
+            execute(deps, env, mock_info(sender, &[]), msg)
+        };
+
+        commit(
+            deps.as_mut(),
+            100,
+            "alice",
+            bounty_commitment("alice", 72785, &signature),
+        )
+        .unwrap();
+        // bob copies alice's commitment, which is bound to her address
+        commit(
+            deps.as_mut(),
+            100,
+            "bob",
+            bounty_commitment("alice", 72785, &signature),
+        )
+        .unwrap();
+        match commit(deps.as_mut(), 101, "alice", [0; 32]).unwrap_err() {
+            ContractError::AlreadyCommitted { round } => assert_eq!(round, 72785),
+            err => panic!("Unexpected error: {:?}", err),
+        }
+
+        // mallory front-runs the beacon without a commitment and gets nothing
+        let mut env = mock_env_later();
+        env.block.height = 101;
+        let msg = ExecuteMsg::Add {
+            network: MAINNET.into(),
+            round: 72785,
+            previous_signature: fixtures::beacon(fixtures::MAINNET, 72785).previous_signature,
+            signature: signature.clone().into(),
+        };
+        let response = execute(deps.as_mut(), env, mock_info("mallory", &[]), msg).unwrap();
+        assert_eq!(response.messages.len(), 0);
+        assert_eq!(
+            get_bounty(&deps.storage, MAINNET, 72785).unwrap(),
+            coins(4500, BOUNTY_DENOM)
+        );
+
+        match claim(deps.as_mut(), 105, "alice").unwrap_err() {
+            ContractError::CommitmentTooRecent { reveal_height } => {
+                assert_eq!(reveal_height, 110)
+            }
+            err => panic!("Unexpected error: {:?}", err),
+        }
+        match claim(deps.as_mut(), 110, "bob").unwrap_err() {
+            ContractError::CommitmentMismatch { round } => assert_eq!(round, 72785),
+            err => panic!("Unexpected error: {:?}", err),
+        }
+        match claim(deps.as_mut(), 110, "mallory").unwrap_err() {
+            ContractError::NoCommitment { round } => assert_eq!(round, 72785),
+            err => panic!("Unexpected error: {:?}", err),
+        }
+
+        let response = claim(deps.as_mut(), 110, "alice").unwrap();
+        assert_eq!(
+            response.messages,
+            vec![SubMsg::new(BankMsg::Send {
+                to_address: "alice".into(),
+                amount: coins(4500, BOUNTY_DENOM),
+            })]
+        );
+        assert_eq!(get_bounty(&deps.storage, MAINNET, 72785).unwrap(), vec![]);
+        assert_eq!(
+            relayers_storage_read(&deps.storage).load(b"alice").unwrap(),
+            RelayerStats {
+                rounds_submitted: 0,
+                bounties_earned: coins(4500, BOUNTY_DENOM),
+                first_submission_height: None,
+                last_submission_height: None,
+            }
+        );
+        match claim(deps.as_mut(), 111, "alice").unwrap_err() {
+            ContractError::NoCommitment { round } => assert_eq!(round, 72785),
+            err => panic!("Unexpected error: {:?}", err),
+        }
+
+        // a committed relayer adding the beacon after the delay is paid directly
+        let beacons = batch_42_45(false);
+        let commitment = bounty_commitment("alice", 45, &beacons[1].signature);
+        let mut env = mock_env_later();
+        env.block.height = 200;
+        let msg = ExecuteMsg::CommitBounty {
+            network: MAINNET.into(),
+            round: 45,
+            commitment: commitment.to_vec().into(),
+        };
+        execute(deps.as_mut(), env.clone(), mock_info("alice", &[]), msg).unwrap();
+        env.block.height = 210;
+        let msg = ExecuteMsg::AddBatch {
+            network: MAINNET.into(),
+            beacons,
+            mode: BatchMode::Strict,
+        };
+        let response = execute(deps.as_mut(), env, mock_info("alice", &[]), msg).unwrap();
+        assert_eq!(
+            response.messages,
+            vec![SubMsg::new(BankMsg::Send {
+                to_address: "alice".into(),
+                amount: coins(23, BOUNTY_DENOM),
+            })]
+        );
+    }
+
+    #[test]
+    fn earliest_commitment_reserves_the_claim() {
+        let mut deps = mock_dependencies();
+
+        let msg = InstantiateMsg {
+            networks: vec![fixtures::network(fixtures::MAINNET).network_info()],
+            bounty_denoms: vec![BOUNTY_DENOM.into()],
+            clock_tolerance: 0,
+            bounty_expiry: 86_400,
+            claim_delay: Some(10),
+            reject_duplicates: false,
+            admin: None,
+            job_fee: None,
+        };
+        instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+
+        let msg = ExecuteMsg::SetBounty {
+            network: MAINNET.into(),
+            round: 72785,
+        };
+        let info = mock_info("anyone", &coins(4500, BOUNTY_DENOM));
+        execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let beacon = fixtures::beacon(fixtures::MAINNET, 72785);
+        let at_height = |height: u64| {
+            let mut env = mock_env_later();
+            env.block.height = height;
+            env
+        };
+        let commit = |deps: DepsMut, height: u64, sender: &str| {
+            let msg = ExecuteMsg::CommitBounty {
+                network: MAINNET.into(),
+                round: 72785,
+                commitment: bounty_commitment(sender, 72785, &beacon.signature)
+                    .to_vec()
+                    .into(),
+            };
+            execute(deps, at_height(height), mock_info(sender, &[]), msg)
+        };
+
+        // bob sees alice's commitment and computes his own, which is just as valid
+        commit(deps.as_mut(), 100, "alice").unwrap();
+        commit(deps.as_mut(), 101, "bob").unwrap();
+
+        // bob adds the beacon as soon as his commitment can be revealed
+        let msg = ExecuteMsg::Add {
+            network: MAINNET.into(),
+            round: 72785,
+            previous_signature: beacon.previous_signature.clone(),
+            signature: beacon.signature.clone(),
+        };
+        let response = execute(deps.as_mut(), at_height(111), mock_info("bob", &[]), msg).unwrap();
+        assert_eq!(response.messages.len(), 0);
+
+        let claim = |deps: DepsMut, height: u64, sender: &str| {
+            let msg = ExecuteMsg::ClaimBounty {
+                network: MAINNET.into(),
+                round: 72785,
+            };
+            execute(deps, at_height(height), mock_info(sender, &[]), msg)
+        };
+        match claim(deps.as_mut(), 119, "bob").unwrap_err() {
+            ContractError::ClaimReserved {
+                round,
+                until_height,
+            } => {
+                assert_eq!(round, 72785);
+                assert_eq!(until_height, 120);
+            }
+            err => panic!("Unexpected error: {:?}", err),
+        }
+
+        // Once the reservation ends, any matured commitment can claim
+        let response = claim(deps.as_mut(), 120, "bob").unwrap();
+        assert_eq!(
+            response.messages,
+            vec![SubMsg::new(BankMsg::Send {
+                to_address: "bob".into(),
+                amount: coins(4500, BOUNTY_DENOM),
+            })]
+        );
+    }
+
+    #[test]
+    fn update_config_switches_claim_delay() {
+        let mut deps = mock_dependencies();
+
+        let msg = InstantiateMsg {
+            networks: vec![fixtures::network(fixtures::MAINNET).network_info()],
+            bounty_denoms: vec![BOUNTY_DENOM.into()],
+            clock_tolerance: 0,
+            bounty_expiry: 86_400,
+            claim_delay: None,
+            reject_duplicates: false,
+            admin: Some("admin".into()),
+            job_fee: None,
+        };
+        instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+
+        let msg = ExecuteMsg::SetBounty {
+            network: MAINNET.into(),
+            round: 72785,
+        };
+        let info = mock_info("anyone", &coins(4500, BOUNTY_DENOM));
+        execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let update = |claim_delay| ExecuteMsg::UpdateConfig {
+            bounty_denoms: None,
+            clock_tolerance: None,
+            bounty_expiry: None,
+            job_fee: None,
+            claim_delay: Some(claim_delay),
+            reject_duplicates: None,
+            networks: vec![],
+        };
+        let commit = || ExecuteMsg::CommitBounty {
+            network: MAINNET.into(),
+            round: 72785,
+            commitment: vec![0; 32].into(),
+        };
+        let claim_delay = |deps: Deps| {
+            let response: ConfigResponse =
+                from_binary(&query(deps, mock_env(), QueryMsg::Config {}).unwrap()).unwrap();
+            response.claim_delay
+        };
+
+        let response = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("admin", &[]),
+            update(10),
+        )
+        .unwrap();
+        assert_eq!(
+            response.events,
+            vec![Event::new("update_config").add_attribute("claim_delay", "10")]
+        );
+        assert_eq!(claim_delay(deps.as_ref()), Some(10));
+        execute(deps.as_mut(), mock_env(), mock_info("alice", &[]), commit()).unwrap();
+
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("admin", &[]),
+            update(20),
+        )
+        .unwrap();
+        assert_eq!(claim_delay(deps.as_ref()), Some(20));
+
+        // Zero disables commit-reveal
+        let response = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("admin", &[]),
+            update(0),
+        )
+        .unwrap();
+        assert_eq!(
+            response.events,
+            vec![Event::new("update_config").add_attribute("claim_delay", "0")]
+        );
+        assert_eq!(claim_delay(deps.as_ref()), None);
+        let result = execute(deps.as_mut(), mock_env(), mock_info("bob", &[]), commit());
+        match result.unwrap_err() {
+            ContractError::CommitRevealDisabled {} => {}
+            err => panic!("Unexpected error: {:?}", err),
+        }
+    }
+
+    #[test]
+    fn relayer_stats_are_recorded() {
+        let mut deps = mock_dependencies();
+
+        let info = mock_info("creator", &[]);
+        let msg = InstantiateMsg {
+            networks: vec![fixtures::network(fixtures::MAINNET).network_info()],
+            bounty_denoms: vec![BOUNTY_DENOM.into()],
+            clock_tolerance: 0,
+            bounty_expiry: 86_400,
+            claim_delay: None,
+            reject_duplicates: false,
+            admin: None,
+            job_fee: None,
+        };
+        instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        for (round, amount) in [(45, 23), (72785, 4500)] {
+            let msg = ExecuteMsg::SetBounty {
+                network: MAINNET.into(),
+                round,
+            };
+            let info = mock_info("anyone", &coins(amount, BOUNTY_DENOM));
+            execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        }
+
+        let query_relayer = |deps: Deps, address: &str| {
+            query(
+                deps,
+                mock_env(),
+                QueryMsg::Relayer {
+                    address: address.into(),
+                },
+            )
+            .map(|data| from_binary::<RelayerResponse>(&data).unwrap().stats)
+        };
+        match query_relayer(deps.as_ref(), "alice").unwrap_err() {
+            ContractError::UnknownRelayer { address } => assert_eq!(address, "alice"),
+            err => panic!("Unexpected error: {:?}", err),
+        }
+
+        // alice adds two rounds in one batch and one more later
+
+        let mut env = mock_env_later();
+        env.block.height = 100;
+        let msg = ExecuteMsg::AddBatch {
+            network: MAINNET.into(),
+            beacons: batch_42_45(false),
+            mode: BatchMode::Strict,
+        };
+        execute(deps.as_mut(), env.clone(), mock_info("alice", &[]), msg).unwrap();
+        assert_eq!(
+            query_relayer(deps.as_ref(), "alice").unwrap(),
+            RelayerStats {
+                rounds_submitted: 2,
+                bounties_earned: coins(23, BOUNTY_DENOM),
+                first_submission_height: Some(100),
+                last_submission_height: Some(100),
+            }
+        );
+
+        env.block.height = 250;
+        let msg = ExecuteMsg::Add {
+            network: MAINNET.into(),
+            round: 72785,
+            previous_signature: fixtures::beacon(fixtures::MAINNET, 72785).previous_signature,
+            signature: fixtures::beacon(fixtures::MAINNET, 72785).signature,
+        };
+        execute(deps.as_mut(), env.clone(), mock_info("alice", &[]), msg).unwrap();
+        assert_eq!(
+            query_relayer(deps.as_ref(), "alice").unwrap(),
+            RelayerStats {
+                rounds_submitted: 3,
+                bounties_earned: coins(4523, BOUNTY_DENOM),
+                first_submission_height: Some(100),
+                last_submission_height: Some(250),
+            }
+        );
+
+        // Store another relayer directly to test pagination
+        relayers_storage(&mut deps.storage)
+            .save(b"bob", &RelayerStats::default())
+            .unwrap();
+
+        let list = |start_after: Option<&str>, limit| -> Vec<String> {
+            let response: RelayersResponse = from_binary(
+                &query(
+                    deps.as_ref(),
+                    mock_env(),
+                    QueryMsg::Relayers {
+                        start_after: start_after.map(Into::into),
+                        limit,
+                    },
+                )
+                .unwrap(),
+            )
+            .unwrap();
+            response.relayers.into_iter().map(|r| r.address).collect()
+        };
+        assert_eq!(list(None, None), vec!["alice", "bob"]);
+        assert_eq!(list(None, Some(1)), vec!["alice"]);
+        assert_eq!(list(Some("alice"), None), vec!["bob"]);
+        assert_eq!(list(Some("bob"), None), Vec::<String>::new());
+    }
+
+    #[test]
+    fn add_batch_handles_invalid_beacons_according_to_mode() {
+        // Failed transactions are reverted by the chain but not by the mock storage,
+        // so every mode gets a fresh instance.
+        let setup = || {
+            let mut deps = mock_dependencies();
+
+            let msg = InstantiateMsg {
+                networks: vec![fixtures::network(fixtures::MAINNET).network_info()],
+                bounty_denoms: vec![BOUNTY_DENOM.into()],
+                clock_tolerance: 0,
+                bounty_expiry: 86_400,
+                claim_delay: None,
+                reject_duplicates: false,
+                admin: None,
+                job_fee: None,
+            };
+            instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+
+            let msg = ExecuteMsg::SetBounty {
+                network: MAINNET.into(),
+                round: 42,
+            };
+            let info = mock_info("anyone", &coins(100, BOUNTY_DENOM));
+            execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+            deps
+        };
+
+        // Strict mode fails as a whole
+
+        let mut deps = setup();
+
+        let msg = ExecuteMsg::AddBatch {
+            network: MAINNET.into(),
+            beacons: batch_42_45(true),
+            mode: BatchMode::Strict,
+        };
+        let result = execute(
+            deps.as_mut(),
+            mock_env_later(),
+            mock_info("relayer", &[]),
+            msg,
+        );
+        match result.unwrap_err() {
+            ContractError::InvalidSignature {} => {}
+            err => panic!("Unexpected error: {:?}", err),
+        }
+
+        // Lenient mode adds the valid beacons
+
+        let mut deps = setup();
+
+        let msg = ExecuteMsg::AddBatch {
+            network: MAINNET.into(),
+            beacons: batch_42_45(true),
+            mode: BatchMode::Lenient,
+        };
+        let response = execute(
+            deps.as_mut(),
+            mock_env_later(),
+            mock_info("relayer", &[]),
+            msg,
+        )
+        .unwrap();
+        assert_eq!(
+            response.messages,
+            vec![SubMsg::new(BankMsg::Send {
+                to_address: "relayer".into(),
+                amount: coins(100, BOUNTY_DENOM),
+            })]
+        );
+        assert_eq!(response.attributes[0].key, "added");
+        assert_eq!(response.attributes[0].value, "42");
+        assert_eq!(response.attributes[1].key, "failed");
+        assert_eq!(
+            response.attributes[1].value,
+            "45: Signature verification failed"
+        );
+
+        let latest: LatestResponse = from_binary(
+            &query(
+                deps.as_ref(),
+                mock_env(),
+                QueryMsg::Latest {
+                    network: MAINNET.into(),
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(latest.round, 42);
+    }
+
+    #[test]
+    fn request_beacon_sends_callback_when_round_is_added() {
+        let mut deps = mock_dependencies();
+
+        let msg = InstantiateMsg {
+            networks: vec![fixtures::network(fixtures::MAINNET).network_info()],
+            bounty_denoms: vec![BOUNTY_DENOM.into()],
+            clock_tolerance: 0,
+            bounty_expiry: 86_400,
+            claim_delay: None,
+            reject_duplicates: false,
+            admin: None,
+            job_fee: None,
+        };
+        instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+
+        // Two consumers request the same round
+
+        for (consumer, job_id) in [("lottery", "draw 1"), ("game", "dice")] {
+            let msg = ExecuteMsg::RequestBeacon {
+                network: MAINNET.into(),
+                round: 72785,
+                job_id: job_id.into(),
+            };
+            let response =
+                execute(deps.as_mut(), mock_env(), mock_info(consumer, &[]), msg).unwrap();
+            assert_eq!(response.messages.len(), 0);
+        }
+
+        let response: JobsResponse = from_binary(
+            &query(
+                deps.as_ref(),
+                mock_env(),
+                QueryMsg::Jobs {
+                    network: MAINNET.into(),
+                    round: 72785,
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(
+            response.jobs,
+            vec![
+                Job {
+                    requester: Addr::unchecked("lottery"),
+                    job_id: "draw 1".into(),
+                },
+                Job {
+                    requester: Addr::unchecked("game"),
+                    job_id: "dice".into(),
+                },
+            ]
+        );
+
+        // Adding the round dispatches the callbacks
+
+        let msg = ExecuteMsg::Add {
+            network: MAINNET.into(),
+            round: 72785,
+            previous_signature: fixtures::beacon(fixtures::MAINNET, 72785).previous_signature,
+            signature: fixtures::beacon(fixtures::MAINNET, 72785).signature,
+        };
+        let response = execute(
+            deps.as_mut(),
+            mock_env_later(),
+            mock_info("anyone", &[]),
+            msg,
+        )
+        .unwrap();
+        let randomness: Binary = fixtures::beacon(fixtures::MAINNET, 72785).randomness;
+        assert_eq!(
+            response.messages,
+            vec![
+                SubMsg::reply_on_error(
+                    BeaconCallback {
+                        job_id: "draw 1".into(),
+                        network: MAINNET.into(),
+                        round: 72785,
+                        randomness: randomness.clone(),
+                    }
+                    .into_cosmos_msg("lottery")
+                    .unwrap(),
+                    CALLBACK_REPLY_ID
+                )
+                .with_gas_limit(CALLBACK_GAS_LIMIT),
+                SubMsg::reply_on_error(
+                    BeaconCallback {
+                        job_id: "dice".into(),
+                        network: MAINNET.into(),
+                        round: 72785,
+                        randomness: randomness.clone(),
+                    }
+                    .into_cosmos_msg("game")
+                    .unwrap(),
+                    CALLBACK_REPLY_ID
+                )
+                .with_gas_limit(CALLBACK_GAS_LIMIT),
+            ]
+        );
+
+        // Jobs are removed
+
+        let response: JobsResponse = from_binary(
+            &query(
+                deps.as_ref(),
+                mock_env(),
+                QueryMsg::Jobs {
+                    network: MAINNET.into(),
+                    round: 72785,
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(response.jobs, vec![]);
+
+        // Requesting a known round sends the callback immediately
+
+        let msg = ExecuteMsg::RequestBeacon {
+            network: MAINNET.into(),
+            round: 72785,
+            job_id: "late".into(),
+        };
+        let response =
+            execute(deps.as_mut(), mock_env(), mock_info("latecomer", &[]), msg).unwrap();
+        assert_eq!(
+            response.messages,
+            vec![SubMsg::reply_on_error(
+                BeaconCallback {
+                    job_id: "late".into(),
+                    network: MAINNET.into(),
+                    round: 72785,
+                    randomness,
+                }
+                .into_cosmos_msg("latecomer")
+                .unwrap(),
+                CALLBACK_REPLY_ID
+            )
+            .with_gas_limit(CALLBACK_GAS_LIMIT)]
+        );
+    }
+
+    #[test]
+    fn request_beacon_limits_pending_jobs_per_round() {
+        let mut deps = mock_dependencies();
+
+        let msg = InstantiateMsg {
+            networks: vec![fixtures::network(fixtures::MAINNET).network_info()],
+            bounty_denoms: vec![BOUNTY_DENOM.into()],
+            clock_tolerance: 0,
+            bounty_expiry: 86_400,
+            claim_delay: None,
+            reject_duplicates: false,
+            admin: None,
+            job_fee: None,
+        };
+        instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+
+        let request = |round: u64, job_id: usize| ExecuteMsg::RequestBeacon {
+            network: MAINNET.into(),
+            round,
+            job_id: job_id.to_string(),
+        };
+        for job_id in 0..MAX_JOBS_PER_ROUND {
+            let msg = request(72785, job_id);
+            execute(deps.as_mut(), mock_env(), mock_info("spammer", &[]), msg).unwrap();
+        }
+
+        let msg = request(72785, MAX_JOBS_PER_ROUND);
+        let result = execute(deps.as_mut(), mock_env(), mock_info("lottery", &[]), msg);
+        match result.unwrap_err() {
+            ContractError::TooManyJobs { round, limit } => {
+                assert_eq!(round, 72785);
+                assert_eq!(limit, MAX_JOBS_PER_ROUND as u64);
+            }
+            err => panic!("Unexpected error: {:?}", err),
+        }
+
+        // Other rounds are not affected
+        let msg = request(72786, 0);
+        execute(deps.as_mut(), mock_env(), mock_info("lottery", &[]), msg).unwrap();
+    }
+
+    #[test]
+    fn request_beacon_charges_job_fee() {
+        let mut deps = mock_dependencies();
+
+        let msg = InstantiateMsg {
+            networks: vec![fixtures::network(fixtures::MAINNET).network_info()],
+            bounty_denoms: vec![BOUNTY_DENOM.into()],
+            clock_tolerance: 0,
+            bounty_expiry: 86_400,
+            claim_delay: None,
+            reject_duplicates: false,
+            admin: None,
+            job_fee: Some(coin(10, BOUNTY_DENOM)),
+        };
+        instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+
+        let request = |job_id: &str| ExecuteMsg::RequestBeacon {
+            network: MAINNET.into(),
+            round: 72785,
+            job_id: job_id.into(),
+        };
+
+        // Too little or nothing at all
+
+        for funds in [vec![], coins(9, BOUNTY_DENOM)] {
+            let info = mock_info("lottery", &funds);
+            let result = execute(deps.as_mut(), mock_env(), info, request("draw"));
+            match result.unwrap_err() {
+                ContractError::InsufficientJobFee { fee } => {
+                    assert_eq!(fee, coin(10, BOUNTY_DENOM))
+                }
+                err => panic!("Unexpected error: {:?}", err),
+            }
+        }
+
+        // The whole payment is added to the bounty of the round
+
+        let info = mock_info("lottery", &coins(25, BOUNTY_DENOM));
+        let response = execute(deps.as_mut(), mock_env(), info, request("draw")).unwrap();
+        assert_eq!(response.messages.len(), 0);
+        assert_eq!(
+            response.events,
+            bounty_set_events(
+                MAINNET,
+                72785,
+                &Addr::unchecked("lottery"),
+                &coins(25, BOUNTY_DENOM)
+            )
+        );
 
-    #[test]
-    fn add_verifies_and_stores_randomness() {
-        let mut deps = mock_dependencies();
+        let response: BountiesResponse = from_binary(
+            &query(
+                deps.as_ref(),
+                mock_env(),
+                QueryMsg::Bounties {
+                    network: MAINNET.into(),
+                    start_after: None,
+                    limit: None,
+                    min_round: None,
+                    max_round: None,
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(
+            response.bounties,
+            vec![Bounty {
+                round: 72785,
+                amount: coins(25, BOUNTY_DENOM),
+            }]
+        );
 
-        let info = mock_info("creator", &[]);
-        let msg = InstantiateMsg {
-            pubkey: pubkey_loe_mainnet(),
-            bounty_denom: BOUNTY_DENOM.into(),
-        };
-        instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+        // The relayer of the round earns the fee
 
-        let info = mock_info("anyone", &[]);
         let msg = ExecuteMsg::Add {
-            // curl -sS https://drand.cloudflare.com/public/72785
+            network: MAINNET.into(),
             round: 72785,
-            previous_signature: hex::decode("a609e19a03c2fcc559e8dae14900aaefe517cb55c840f6e69bc8e4f66c8d18e8a609685d9917efbfb0c37f058c2de88f13d297c7e19e0ab24813079efe57a182554ff054c7638153f9b26a60e7111f71a0ff63d9571704905d3ca6df0b031747").unwrap().into(),
-            signature: hex::decode("82f5d3d2de4db19d40a6980e8aa37842a0e55d1df06bd68bddc8d60002e8e959eb9cfa368b3c1b77d18f02a54fe047b80f0989315f83b12a74fd8679c4f12aae86eaf6ab5690b34f1fddd50ee3cc6f6cdf59e95526d5a5d82aaa84fa6f181e42").unwrap().into(),
+            previous_signature: fixtures::beacon(fixtures::MAINNET, 72785).previous_signature,
+            signature: fixtures::beacon(fixtures::MAINNET, 72785).signature,
         };
-        execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        let response = execute(
+            deps.as_mut(),
+            mock_env_later(),
+            mock_info("relayer", &[]),
+            msg,
+        )
+        .unwrap();
+        assert_eq!(
+            response.messages[0],
+            SubMsg::new(BankMsg::Send {
+                to_address: "relayer".into(),
+                amount: coins(25, BOUNTY_DENOM),
+            })
+        );
 
-        let response: GetResponse =
-            from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::Get { round: 72785 }).unwrap())
-                .unwrap();
+        // Known rounds are free and sent funds are returned
+
+        let info = mock_info("latecomer", &coins(10, BOUNTY_DENOM));
+        let response = execute(deps.as_mut(), mock_env(), info, request("late")).unwrap();
+        assert_eq!(response.messages.len(), 2);
         assert_eq!(
-            response.randomness,
-            hex::decode("8b676484b5fb1f37f9ec5c413d7d29883504e5b669f604a1ce68b3388e9ae3d9")
-                .unwrap()
+            response.messages[1],
+            SubMsg::new(BankMsg::Send {
+                to_address: "latecomer".into(),
+                amount: coins(10, BOUNTY_DENOM),
+            })
         );
     }
 
     #[test]
-    fn add_fails_when_pubkey_is_invalid() {
+    fn request_beacon_rejects_round_zero_and_old_rounds() {
         let mut deps = mock_dependencies();
 
-        let info = mock_info("creator", &[]);
-        let mut broken: Vec<u8> = pubkey_loe_mainnet().into();
-        broken.push(0xF9);
         let msg = InstantiateMsg {
-            pubkey: broken.into(),
-            bounty_denom: BOUNTY_DENOM.into(),
+            networks: vec![fixtures::network(fixtures::MAINNET).network_info()],
+            bounty_denoms: vec![BOUNTY_DENOM.into()],
+            clock_tolerance: 0,
+            bounty_expiry: 86_400,
+            claim_delay: None,
+            reject_duplicates: false,
+            admin: None,
+            job_fee: None,
         };
-        instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+        instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
 
-        let info = mock_info("anyone", &[]);
-        let msg = ExecuteMsg::Add {
-            // curl -sS https://drand.cloudflare.com/public/72785 | jq
-            round: 72785,
-            previous_signature: hex::decode("a609e19a03c2fcc559e8dae14900aaefe517cb55c840f6e69bc8e4f66c8d18e8a609685d9917efbfb0c37f058c2de88f13d297c7e19e0ab24813079efe57a182554ff054c7638153f9b26a60e7111f71a0ff63d9571704905d3ca6df0b031747").unwrap().into(),
-            signature: hex::decode("82f5d3d2de4db19d40a6980e8aa37842a0e55d1df06bd68bddc8d60002e8e959eb9cfa368b3c1b77d18f02a54fe047b80f0989315f83b12a74fd8679c4f12aae86eaf6ab5690b34f1fddd50ee3cc6f6cdf59e95526d5a5d82aaa84fa6f181e42").unwrap().into(),
+        let request = |round: u64| ExecuteMsg::RequestBeacon {
+            network: MAINNET.into(),
+            round,
+            job_id: "draw".into(),
         };
-        let result = execute(deps.as_mut(), mock_env(), info, msg);
+
+        let result = execute(
+            deps.as_mut(),
+            mock_env_later(),
+            mock_info("lottery", &[]),
+            request(0),
+        );
+        match result.unwrap_err() {
+            ContractError::InvalidRound { round } => assert_eq!(round, 0),
+            err => panic!("Unexpected error: {:?}", err),
+        }
+
+        // Round 3482752 is published one bounty expiry before the later mock time
+        let result = execute(
+            deps.as_mut(),
+            mock_env_later(),
+            mock_info("lottery", &[]),
+            request(72785),
+        );
         match result.unwrap_err() {
-            ContractError::InvalidPubkey {} => {}
+            ContractError::RoundTooOld { round, min_round } => {
+                assert_eq!(round, 72785);
+                assert_eq!(min_round, 3482753);
+            }
             err => panic!("Unexpected error: {:?}", err),
         }
+
+        execute(
+            deps.as_mut(),
+            mock_env_later(),
+            mock_info("lottery", &[]),
+            request(3482753),
+        )
+        .unwrap();
     }
 
     #[test]
-    fn add_fails_for_broken_signature() {
-        let mut deps = mock_dependencies();
+    fn add_batch_limits_callbacks_per_message() {
+        let setup = || {
+            let mut deps = mock_dependencies();
 
-        let info = mock_info("creator", &[]);
-        let msg = InstantiateMsg {
-            pubkey: pubkey_loe_mainnet(),
-            bounty_denom: BOUNTY_DENOM.into(),
+            let msg = InstantiateMsg {
+                networks: vec![fixtures::network(fixtures::MAINNET).network_info()],
+                bounty_denoms: vec![BOUNTY_DENOM.into()],
+                clock_tolerance: 0,
+                bounty_expiry: 86_400,
+                claim_delay: None,
+                reject_duplicates: false,
+                admin: None,
+                job_fee: None,
+            };
+            instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+
+            // Round 42 uses up the callback budget of a message
+            let jobs = (0..MAX_JOBS_PER_ROUND)
+                .map(|job_id| (42, job_id.to_string()))
+                .chain([(45, "draw".to_string())]);
+            for (round, job_id) in jobs {
+                let msg = ExecuteMsg::RequestBeacon {
+                    network: MAINNET.into(),
+                    round,
+                    job_id,
+                };
+                execute(deps.as_mut(), mock_env(), mock_info("lottery", &[]), msg).unwrap();
+            }
+            deps
         };
-        instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
 
-        let info = mock_info("anyone", &[]);
-        let msg = ExecuteMsg::Add {
-            // curl -sS https://drand.cloudflare.com/public/72785
-            round: 72785,
-            previous_signature: hex::decode("a609e19a03c2fcc559e8dae14900aaefe517cb55c840f6e69bc8e4f66c8d18e8a609685d9917efbfb0c37f058c2de88f13d297c7e19e0ab24813079efe57a182554ff054c7638153f9b26a60e7111f71a0ff63d9571704905d3ca6df0b031747").unwrap().into(),
-            signature: hex::decode("3cc6f6cdf59e95526d5a5d82aaa84fa6f181e4").unwrap().into(), // broken signature
+        let mut deps = setup();
+
+        let msg = ExecuteMsg::AddBatch {
+            network: MAINNET.into(),
+            beacons: batch_42_45(false),
+            mode: BatchMode::Strict,
         };
-        let result = execute(deps.as_mut(), mock_env(), info, msg);
+        let result = execute(
+            deps.as_mut(),
+            mock_env_later(),
+            mock_info("relayer", &[]),
+            msg,
+        );
         match result.unwrap_err() {
-            ContractError::InvalidSignature {} => {}
+            ContractError::TooManyCallbacks { round, limit } => {
+                assert_eq!(round, 45);
+                assert_eq!(limit, MAX_JOBS_PER_ROUND as u64);
+            }
             err => panic!("Unexpected error: {:?}", err),
         }
+
+        // Lenient mode leaves round 45 for the next message
+
+        let mut deps = setup();
+
+        let msg = ExecuteMsg::AddBatch {
+            network: MAINNET.into(),
+            beacons: batch_42_45(false),
+            mode: BatchMode::Lenient,
+        };
+        let response = execute(
+            deps.as_mut(),
+            mock_env_later(),
+            mock_info("relayer", &[]),
+            msg,
+        )
+        .unwrap();
+        assert_eq!(response.messages.len(), MAX_JOBS_PER_ROUND);
+        assert_eq!(response.attributes[0].value, "42");
+        assert_eq!(response.attributes[1].key, "failed");
+        assert_eq!(
+            response.attributes[1].value,
+            "45: Adding round 45 exceeds the limit of 10 callbacks per message"
+        );
+
+        let msg = ExecuteMsg::Add {
+            network: MAINNET.into(),
+            round: 45,
+            previous_signature: fixtures::beacon(fixtures::MAINNET, 45).previous_signature,
+            signature: fixtures::beacon(fixtures::MAINNET, 45).signature,
+        };
+        let response = execute(
+            deps.as_mut(),
+            mock_env_later(),
+            mock_info("relayer", &[]),
+            msg,
+        )
+        .unwrap();
+        assert_eq!(response.messages.len(), 1);
     }
 
     #[test]
-    fn add_fails_for_invalid_signature() {
+    fn request_beacon_after_uses_next_round() {
         let mut deps = mock_dependencies();
 
-        let info = mock_info("creator", &[]);
         let msg = InstantiateMsg {
-            pubkey: pubkey_loe_mainnet(),
-            bounty_denom: BOUNTY_DENOM.into(),
+            networks: vec![fixtures::network(fixtures::MAINNET).network_info()],
+            bounty_denoms: vec![BOUNTY_DENOM.into()],
+            clock_tolerance: 0,
+            bounty_expiry: 86_400,
+            claim_delay: None,
+            reject_duplicates: false,
+            admin: None,
+            job_fee: None,
         };
-        instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+        instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
 
-        let info = mock_info("anyone", &[]);
-        let msg = ExecuteMsg::Add {
-            // curl -sS https://drand.cloudflare.com/public/72785
-            round: 1111, // wrong round
-            previous_signature: hex::decode("a609e19a03c2fcc559e8dae14900aaefe517cb55c840f6e69bc8e4f66c8d18e8a609685d9917efbfb0c37f058c2de88f13d297c7e19e0ab24813079efe57a182554ff054c7638153f9b26a60e7111f71a0ff63d9571704905d3ca6df0b031747").unwrap().into(),
-            signature: hex::decode("82f5d3d2de4db19d40a6980e8aa37842a0e55d1df06bd68bddc8d60002e8e959eb9cfa368b3c1b77d18f02a54fe047b80f0989315f83b12a74fd8679c4f12aae86eaf6ab5690b34f1fddd50ee3cc6f6cdf59e95526d5a5d82aaa84fa6f181e42").unwrap().into(),
+        // One second before round 72785 is published
+        let msg = ExecuteMsg::RequestBeaconAfter {
+            network: MAINNET.into(),
+            after: Timestamp::from_seconds(1597614569),
+            job_id: "draw".into(),
         };
-        let result = execute(deps.as_mut(), mock_env(), info, msg);
+        let response = execute(deps.as_mut(), mock_env(), mock_info("lottery", &[]), msg).unwrap();
+        assert_eq!(response.attributes[0].value, "72785");
+
+        let response: JobsResponse = from_binary(
+            &query(
+                deps.as_ref(),
+                mock_env(),
+                QueryMsg::Jobs {
+                    network: MAINNET.into(),
+                    round: 72785,
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(
+            response.jobs,
+            vec![Job {
+                requester: Addr::unchecked("lottery"),
+                job_id: "draw".into(),
+            }]
+        );
+    }
+
+    #[test]
+    fn reply_swallows_failed_callbacks() {
+        let mut deps = mock_dependencies();
+
+        let response = reply(
+            deps.as_mut(),
+            mock_env(),
+            Reply {
+                id: CALLBACK_REPLY_ID,
+                result: SubMsgResult::Err("consumer is broken".into()),
+            },
+        )
+        .unwrap();
+        assert_eq!(response.messages.len(), 0);
+        assert_eq!(response.attributes[0].key, "callback_error");
+        assert_eq!(response.attributes[0].value, "consumer is broken");
+
+        let result = reply(
+            deps.as_mut(),
+            mock_env(),
+            Reply {
+                id: 42,
+                result: SubMsgResult::Ok(SubMsgExecutionResponse {
+                    events: vec![],
+                    data: None,
+                }),
+            },
+        );
         match result.unwrap_err() {
-            ContractError::InvalidSignature {} => {}
+            ContractError::UnknownReplyId { id: 42 } => {}
             err => panic!("Unexpected error: {:?}", err),
         }
     }
 
     #[test]
-    fn add_receives_bounty() {
+    fn query_get_works() {
         let mut deps = mock_dependencies();
 
         let info = mock_info("creator", &[]);
         let msg = InstantiateMsg {
-            pubkey: pubkey_loe_mainnet(),
-            bounty_denom: BOUNTY_DENOM.into(),
+            networks: vec![fixtures::network(fixtures::MAINNET).network_info()],
+            bounty_denoms: vec![BOUNTY_DENOM.into()],
+            clock_tolerance: 0,
+            bounty_expiry: 86_400,
+            claim_delay: None,
+            reject_duplicates: false,
+            admin: None,
+            job_fee: None,
         };
         instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
 
-        // Set bounty
+        // Beacon does not exist
 
-        let msg = ExecuteMsg::SetBounty { round: 72785 };
-        let info = mock_info(
-            "anyone",
-            &[Coin {
-                denom: BOUNTY_DENOM.into(),
-                amount: Uint128::new(4500),
-            }],
-        );
-        execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        let response: GetResponse = from_binary(
+            &query(
+                deps.as_ref(),
+                mock_env(),
+                QueryMsg::Get {
+                    network: MAINNET.into(),
+                    round: 42,
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(response.randomness, Binary::default());
 
-        // Claim bounty
+        // Beacon exists
 
-        let info = mock_info("claimer", &[]);
         let msg = ExecuteMsg::Add {
-            // curl -sS https://drand.cloudflare.com/public/72785
-            round: 72785,
-            previous_signature: hex::decode("a609e19a03c2fcc559e8dae14900aaefe517cb55c840f6e69bc8e4f66c8d18e8a609685d9917efbfb0c37f058c2de88f13d297c7e19e0ab24813079efe57a182554ff054c7638153f9b26a60e7111f71a0ff63d9571704905d3ca6df0b031747").unwrap().into(),
-            signature: hex::decode("82f5d3d2de4db19d40a6980e8aa37842a0e55d1df06bd68bddc8d60002e8e959eb9cfa368b3c1b77d18f02a54fe047b80f0989315f83b12a74fd8679c4f12aae86eaf6ab5690b34f1fddd50ee3cc6f6cdf59e95526d5a5d82aaa84fa6f181e42").unwrap().into(),
+            network: MAINNET.into(),
+            round: 42,
+            previous_signature: fixtures::beacon(fixtures::MAINNET, 42).previous_signature,
+            signature: fixtures::beacon(fixtures::MAINNET, 42).signature,
         };
-        let response = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
-        assert_eq!(response.messages.len(), 1);
+        execute(
+            deps.as_mut(),
+            mock_env_later(),
+            mock_info("anyone", &[]),
+            msg,
+        )
+        .unwrap();
+
+        let response: GetResponse = from_binary(
+            &query(
+                deps.as_ref(),
+                mock_env(),
+                QueryMsg::Get {
+                    network: MAINNET.into(),
+                    round: 42,
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap();
         assert_eq!(
-            response.messages[0],
-            SubMsg::new(BankMsg::Send {
-                to_address: Addr::unchecked("claimer").to_string(),
-                amount: coins(4500, BOUNTY_DENOM),
-            })
+            response.randomness,
+            fixtures::beacon(fixtures::MAINNET, 42).randomness.to_vec()
         );
+    }
 
-        // Cannot be claimed again
+    #[test]
+    fn query_round_time_mapping_works() {
+        let mut deps = mock_dependencies();
 
-        let info = mock_info("claimer2", &[]);
-        let msg = ExecuteMsg::Add {
-            // curl -sS https://drand.cloudflare.com/public/72785
-            round: 72785,
-            previous_signature: hex::decode("a609e19a03c2fcc559e8dae14900aaefe517cb55c840f6e69bc8e4f66c8d18e8a609685d9917efbfb0c37f058c2de88f13d297c7e19e0ab24813079efe57a182554ff054c7638153f9b26a60e7111f71a0ff63d9571704905d3ca6df0b031747").unwrap().into(),
-            signature: hex::decode("82f5d3d2de4db19d40a6980e8aa37842a0e55d1df06bd68bddc8d60002e8e959eb9cfa368b3c1b77d18f02a54fe047b80f0989315f83b12a74fd8679c4f12aae86eaf6ab5690b34f1fddd50ee3cc6f6cdf59e95526d5a5d82aaa84fa6f181e42").unwrap().into(),
+        let msg = InstantiateMsg {
+            networks: vec![fixtures::network(fixtures::MAINNET).network_info()],
+            bounty_denoms: vec![BOUNTY_DENOM.into()],
+            clock_tolerance: 0,
+            bounty_expiry: 86_400,
+            claim_delay: None,
+            reject_duplicates: false,
+            admin: None,
+            job_fee: None,
         };
-        let response = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
-        assert_eq!(response.messages.len(), 0);
+        instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+
+        let round_at = |seconds: u64| -> u64 {
+            let msg = QueryMsg::RoundAt {
+                network: MAINNET.into(),
+                timestamp: Timestamp::from_seconds(seconds),
+            };
+            let response: RoundResponse =
+                from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+            response.round
+        };
+        assert_eq!(round_at(1595431049), 0);
+        assert_eq!(round_at(1595431050), 1);
+        assert_eq!(round_at(1595431079), 1);
+        assert_eq!(round_at(1595431080), 2);
+        assert_eq!(round_at(1597614570), 72785);
+
+        let next_round_after = |seconds: u64| -> u64 {
+            let msg = QueryMsg::NextRoundAfter {
+                network: MAINNET.into(),
+                timestamp: Timestamp::from_seconds(seconds),
+            };
+            let response: RoundResponse =
+                from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+            response.round
+        };
+        assert_eq!(next_round_after(0), 1);
+        assert_eq!(next_round_after(1595431049), 1);
+        assert_eq!(next_round_after(1595431050), 2);
+        assert_eq!(next_round_after(1597614569), 72785);
+        assert_eq!(next_round_after(1597614570), 72786);
+
+        let time_of_round = |round: u64| -> Result<Timestamp, ContractError> {
+            let msg = QueryMsg::TimeOfRound {
+                network: MAINNET.into(),
+                round,
+            };
+            let response: TimeResponse = from_binary(&query(deps.as_ref(), mock_env(), msg)?)?;
+            Ok(response.time)
+        };
+        assert_eq!(
+            time_of_round(1).unwrap(),
+            Timestamp::from_seconds(1595431050)
+        );
+        assert_eq!(
+            time_of_round(72785).unwrap(),
+            Timestamp::from_seconds(1597614570)
+        );
+        match time_of_round(0).unwrap_err() {
+            ContractError::InvalidRound { round: 0 } => {}
+            err => panic!("Unexpected error: {:?}", err),
+        }
+        match time_of_round(u64::MAX).unwrap_err() {
+            ContractError::InvalidRound { .. } => {}
+            err => panic!("Unexpected error: {:?}", err),
+        }
     }
 
     #[test]
-    fn query_get_works() {
+    fn query_random_values_works() {
         let mut deps = mock_dependencies();
 
-        let info = mock_info("creator", &[]);
         let msg = InstantiateMsg {
-            pubkey: pubkey_loe_mainnet(),
-            bounty_denom: BOUNTY_DENOM.into(),
+            networks: vec![fixtures::network(fixtures::MAINNET).network_info()],
+            bounty_denoms: vec![BOUNTY_DENOM.into()],
+            clock_tolerance: 0,
+            bounty_expiry: 86_400,
+            claim_delay: None,
+            reject_duplicates: false,
+            admin: None,
+            job_fee: None,
         };
-        instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+        instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
 
         // Beacon does not exist
 
-        let response: GetResponse =
-            from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::Get { round: 42 }).unwrap())
-                .unwrap();
-        assert_eq!(response.randomness, Binary::default());
-
-        // Beacon exists
+        let result = query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::RandomInt {
+                network: MAINNET.into(),
+                round: 72785,
+                min: 1,
+                max: 100,
+                salt: Binary::from(b"salt"),
+            },
+        );
+        match result.unwrap_err() {
+            ContractError::NoBeacon {} => {}
+            err => panic!("Unexpected error: {:?}", err),
+        }
 
         let msg = ExecuteMsg::Add {
-            // curl -sS https://drand.cloudflare.com/public/42 | jq
-            round: 42,
-            previous_signature: hex::decode("a418fccbfaa0c84aba8cbcd4e3c0555170eb2382dfed108ecfc6df249ad43efe00078bdcb5060fe2deed4731ca5b4c740069aaf77927ba59c5870ab3020352aca3853adfdb9162d40ec64f71b121285898e28cdf237e982ac5c4deb287b0d57b").unwrap().into(),
-            signature: hex::decode("9469186f38e5acdac451940b1b22f737eb0de060b213f0326166c7882f2f82b92ce119bdabe385941ef46f72736a4b4d02ce206e1eb46cac53019caf870080fede024edcd1bd0225eb1335b83002ae1743393e83180e47d9948ab8ba7568dd99").unwrap().into(),
+            network: MAINNET.into(),
+            round: 72785,
+            previous_signature: fixtures::beacon(fixtures::MAINNET, 72785).previous_signature,
+            signature: fixtures::beacon(fixtures::MAINNET, 72785).signature,
         };
-        execute(deps.as_mut(), mock_env(), mock_info("anyone", &[]), msg).unwrap();
+        execute(
+            deps.as_mut(),
+            mock_env_later(),
+            mock_info("anyone", &[]),
+            msg,
+        )
+        .unwrap();
 
-        let response: GetResponse =
-            from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::Get { round: 42 }).unwrap())
-                .unwrap();
+        // RandomInt
+
+        let response: RandomIntResponse = from_binary(
+            &query(
+                deps.as_ref(),
+                mock_env(),
+                QueryMsg::RandomInt {
+                    network: MAINNET.into(),
+                    round: 72785,
+                    min: 1,
+                    max: 100,
+                    salt: Binary::from(b"salt"),
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        // Same as first value in randomness::tests::output_is_stable
+        assert_eq!(response.value, 90);
+
+        let result = query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::RandomInt {
+                network: MAINNET.into(),
+                round: 72785,
+                min: 5,
+                max: 4,
+                salt: Binary::default(),
+            },
+        );
+        match result.unwrap_err() {
+            ContractError::InvalidRange { min: 5, max: 4 } => {}
+            err => panic!("Unexpected error: {:?}", err),
+        }
+
+        // Shuffle
+
+        let response: ShuffleResponse = from_binary(
+            &query(
+                deps.as_ref(),
+                mock_env(),
+                QueryMsg::Shuffle {
+                    network: MAINNET.into(),
+                    round: 72785,
+                    n: 10,
+                    salt: Binary::default(),
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        let mut sorted = response.permutation.clone();
+        sorted.sort_unstable();
+        assert_eq!(sorted, (0..10).collect::<Vec<u32>>());
+
+        let result = query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::Shuffle {
+                network: MAINNET.into(),
+                round: 72785,
+                n: 1001,
+                salt: Binary::default(),
+            },
+        );
+        match result.unwrap_err() {
+            ContractError::TooManyElements {
+                count: 1001,
+                limit: 1000,
+            } => {}
+            err => panic!("Unexpected error: {:?}", err),
+        }
+
+        // Derive
+
+        let response: DeriveResponse = from_binary(
+            &query(
+                deps.as_ref(),
+                mock_env(),
+                QueryMsg::Derive {
+                    network: MAINNET.into(),
+                    round: 72785,
+                    namespace: "cosmos1lottery".into(),
+                    job_id: "draw 1".into(),
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap();
         assert_eq!(
-            response.randomness,
-            hex::decode("a9f12c5869d05e084d1741957130e1d0bf78a8ca9a8deb97c47cac29aae433c6")
+            response,
+            DeriveResponse {
+                randomness: hex::decode(
+                    "73ca20f84291b8962665600713cee154c8cf9497fead089a97f3b88392f19133"
+                )
                 .unwrap()
+                .into(),
+                derivation: "rand:derive:v1".into(),
+            }
         );
+
+        // Pick
+
+        let items: Vec<String> = ["alice", "bob", "carol", "dave"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        let response: PickResponse = from_binary(
+            &query(
+                deps.as_ref(),
+                mock_env(),
+                QueryMsg::Pick {
+                    network: MAINNET.into(),
+                    round: 72785,
+                    items: items.clone(),
+                    k: 2,
+                    salt: Binary::default(),
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(response.picked.len(), 2);
+        assert_ne!(response.picked[0], response.picked[1]);
+        assert!(response.picked.iter().all(|item| items.contains(item)));
     }
 
     #[test]
@@ -513,12 +4822,24 @@ mod tests {
 
         let info = mock_info("creator", &[]);
         let msg = InstantiateMsg {
-            pubkey: pubkey_loe_mainnet(),
-            bounty_denom: BOUNTY_DENOM.into(),
+            networks: vec![fixtures::network(fixtures::MAINNET).network_info()],
+            bounty_denoms: vec![BOUNTY_DENOM.into()],
+            clock_tolerance: 0,
+            bounty_expiry: 86_400,
+            claim_delay: None,
+            reject_duplicates: false,
+            admin: None,
+            job_fee: None,
         };
         instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
 
-        let result = query(deps.as_ref(), mock_env(), QueryMsg::Latest {});
+        let result = query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::Latest {
+                network: MAINNET.into(),
+            },
+        );
         match result.unwrap_err() {
             ContractError::NoBeacon {} => {}
             err => panic!("Unexpected error: {:?}", err),
@@ -531,66 +4852,195 @@ mod tests {
 
         let info = mock_info("creator", &[]);
         let msg = InstantiateMsg {
-            pubkey: pubkey_loe_mainnet(),
-            bounty_denom: BOUNTY_DENOM.into(),
+            networks: vec![fixtures::network(fixtures::MAINNET).network_info()],
+            bounty_denoms: vec![BOUNTY_DENOM.into()],
+            clock_tolerance: 0,
+            bounty_expiry: 86_400,
+            claim_delay: None,
+            reject_duplicates: false,
+            admin: None,
+            job_fee: None,
         };
         instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
 
         // Add first beacon
 
         let msg = ExecuteMsg::Add {
-            // curl -sS https://drand.cloudflare.com/public/42 | jq
+            network: MAINNET.into(),
             round: 42,
-            previous_signature: hex::decode("a418fccbfaa0c84aba8cbcd4e3c0555170eb2382dfed108ecfc6df249ad43efe00078bdcb5060fe2deed4731ca5b4c740069aaf77927ba59c5870ab3020352aca3853adfdb9162d40ec64f71b121285898e28cdf237e982ac5c4deb287b0d57b").unwrap().into(),
-            signature: hex::decode("9469186f38e5acdac451940b1b22f737eb0de060b213f0326166c7882f2f82b92ce119bdabe385941ef46f72736a4b4d02ce206e1eb46cac53019caf870080fede024edcd1bd0225eb1335b83002ae1743393e83180e47d9948ab8ba7568dd99").unwrap().into(),
+            previous_signature: fixtures::beacon(fixtures::MAINNET, 42).previous_signature,
+            signature: fixtures::beacon(fixtures::MAINNET, 42).signature,
         };
-        execute(deps.as_mut(), mock_env(), mock_info("anyone", &[]), msg).unwrap();
+        execute(
+            deps.as_mut(),
+            mock_env_later(),
+            mock_info("anyone", &[]),
+            msg,
+        )
+        .unwrap();
 
-        let latest: LatestResponse =
-            from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::Latest {}).unwrap()).unwrap();
+        let latest: LatestResponse = from_binary(
+            &query(
+                deps.as_ref(),
+                mock_env(),
+                QueryMsg::Latest {
+                    network: MAINNET.into(),
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap();
         assert_eq!(latest.round, 42);
         assert_eq!(
             latest.randomness,
-            hex::decode("a9f12c5869d05e084d1741957130e1d0bf78a8ca9a8deb97c47cac29aae433c6")
-                .unwrap()
+            fixtures::beacon(fixtures::MAINNET, 42).randomness.to_vec()
         );
 
         // Adding higher round updated the latest value
 
         let msg = ExecuteMsg::Add {
-            // curl -sS https://drand.cloudflare.com/public/45 | jq
+            network: MAINNET.into(),
             round: 45,
-            previous_signature: hex::decode("a45dadaa23a0e70b06c297256c1bbdbcb915185c4bd2e0b6841e62f1b44264b82c8fc2ab97194e26ad90da55992d7c1e0cf0e58e17f91849aaecf545713b91efdebcb4cce06d3a0fcbabd72a8ab06050a3971898131e9026f29513680b99952a").unwrap().into(),
-            signature: hex::decode("9280e40ac60dea6fcd936adbf69cae5c0add37fd161e036d34abd190099ddec975d15f9684d8875e4a69f5fe8ff9dde30fc29510fadde729a7d3b5522bbeddc4d2a08935025572daeee7d0130e55f51ff6d0dbbd15fc700151b420577072a801").unwrap().into(),
+            previous_signature: fixtures::beacon(fixtures::MAINNET, 45).previous_signature,
+            signature: fixtures::beacon(fixtures::MAINNET, 45).signature,
         };
-        execute(deps.as_mut(), mock_env(), mock_info("anyone", &[]), msg).unwrap();
+        execute(
+            deps.as_mut(),
+            mock_env_later(),
+            mock_info("anyone", &[]),
+            msg,
+        )
+        .unwrap();
 
-        let latest: LatestResponse =
-            from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::Latest {}).unwrap()).unwrap();
+        let latest: LatestResponse = from_binary(
+            &query(
+                deps.as_ref(),
+                mock_env(),
+                QueryMsg::Latest {
+                    network: MAINNET.into(),
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap();
         assert_eq!(latest.round, 45);
         assert_eq!(
             latest.randomness,
-            hex::decode("bfef28c6f445af5eedcf9de596a0bdd95b7e285aedefd17d70e1fac668c5f05b")
-                .unwrap()
+            fixtures::beacon(fixtures::MAINNET, 45).randomness.to_vec()
         );
 
         // Adding lower round does not affect latest
 
         let msg = ExecuteMsg::Add {
-            // curl -sS https://drand.cloudflare.com/public/40 | jq
+            network: MAINNET.into(),
             round: 40,
-            previous_signature: hex::decode("88756596758c8219b9973a496bf040a0962244c0a309695d92a9853ab03c1f5301ac9c02f8baeac6f84ce1a397f39eed1960be7f85b1c8bc64ac25567030a03673e08440d2a319319d883120a99822d0d6c23bd333725a1c4df269863a30b784").unwrap().into(),
-            signature: hex::decode("8ea1d9cf15546a6b1515803dfaccbb379966b74e553fd9faa22206828e26d4b13a0b4d81f4820256af9bd228e428e2cb13a2bf634af151e815f939005b6393b12c33a7eed68d6c019ea3885f0a18541a23fb5312aab061d7ec9ebc798726a774").unwrap().into(),
+            previous_signature: fixtures::beacon(fixtures::MAINNET, 40).previous_signature,
+            signature: fixtures::beacon(fixtures::MAINNET, 40).signature,
         };
-        execute(deps.as_mut(), mock_env(), mock_info("anyone", &[]), msg).unwrap();
+        execute(
+            deps.as_mut(),
+            mock_env_later(),
+            mock_info("anyone", &[]),
+            msg,
+        )
+        .unwrap();
 
-        let latest: LatestResponse =
-            from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::Latest {}).unwrap()).unwrap();
+        let latest: LatestResponse = from_binary(
+            &query(
+                deps.as_ref(),
+                mock_env(),
+                QueryMsg::Latest {
+                    network: MAINNET.into(),
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap();
         assert_eq!(latest.round, 45);
         assert_eq!(
             latest.randomness,
-            hex::decode("bfef28c6f445af5eedcf9de596a0bdd95b7e285aedefd17d70e1fac668c5f05b")
-                .unwrap()
+            fixtures::beacon(fixtures::MAINNET, 45).randomness.to_vec()
+        );
+    }
+
+    #[test]
+    fn query_beacon_returns_full_record() {
+        let mut deps = mock_dependencies();
+
+        let info = mock_info("creator", &[]);
+        let msg = InstantiateMsg {
+            networks: vec![
+                fixtures::network(fixtures::MAINNET).network_info(),
+                fixtures::network(fixtures::QUICKNET).network_info(),
+            ],
+            bounty_denoms: vec![BOUNTY_DENOM.into()],
+            clock_tolerance: 0,
+            bounty_expiry: 86_400,
+            claim_delay: None,
+            reject_duplicates: false,
+            admin: None,
+            job_fee: None,
+        };
+        instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let query_beacon = |deps: Deps, network: &str, round: u64| {
+            query(
+                deps,
+                mock_env(),
+                QueryMsg::Beacon {
+                    network: network.into(),
+                    round,
+                },
+            )
+            .map(|data| from_binary::<BeaconResponse>(&data).unwrap().beacon)
+        };
+        match query_beacon(deps.as_ref(), MAINNET, 72785).unwrap_err() {
+            ContractError::NoBeacon {} => {}
+            err => panic!("Unexpected error: {:?}", err),
+        }
+
+        let previous_signature = fixtures::beacon(fixtures::MAINNET, 72785)
+            .previous_signature
+            .to_vec();
+        let signature = fixtures::beacon(fixtures::MAINNET, 72785)
+            .signature
+            .to_vec();
+        let msg = ExecuteMsg::Add {
+            network: MAINNET.into(),
+            round: 72785,
+            previous_signature: previous_signature.clone().into(),
+            signature: signature.clone().into(),
+        };
+        let env = mock_env_later();
+        execute(deps.as_mut(), env.clone(), mock_info("relayer", &[]), msg).unwrap();
+        assert_eq!(
+            query_beacon(deps.as_ref(), MAINNET, 72785).unwrap(),
+            Beacon {
+                round: 72785,
+                signature: signature.into(),
+                previous_signature: previous_signature.into(),
+                randomness: fixtures::beacon(fixtures::MAINNET, 72785).randomness,
+                submitter: Addr::unchecked("relayer"),
+                block_height: env.block.height,
+                block_time: env.block.time,
+            }
+        );
+
+        // A previous signature sent for an unchained scheme is not part of the proof
+        let msg = ExecuteMsg::Add {
+            network: QUICKNET.into(),
+            round: 123,
+            previous_signature: vec![0xAA; 48].into(),
+            signature: fixtures::beacon(fixtures::QUICKNET, 123).signature,
+        };
+        execute(deps.as_mut(), env, mock_info("relayer", &[]), msg).unwrap();
+        let beacon = query_beacon(deps.as_ref(), QUICKNET, 123).unwrap();
+        assert_eq!(beacon.previous_signature, Binary::default());
+        assert_eq!(
+            beacon.randomness,
+            fixtures::beacon(fixtures::QUICKNET, 123)
+                .randomness
+                .to_vec()
         );
     }
 
@@ -600,20 +5050,42 @@ mod tests {
 
         let info = mock_info("creator", &[]);
         let msg = InstantiateMsg {
-            pubkey: pubkey_loe_mainnet(),
-            bounty_denom: BOUNTY_DENOM.into(),
+            networks: vec![fixtures::network(fixtures::MAINNET).network_info()],
+            bounty_denoms: vec![BOUNTY_DENOM.into()],
+            clock_tolerance: 0,
+            bounty_expiry: 86_400,
+            claim_delay: None,
+            reject_duplicates: false,
+            admin: None,
+            job_fee: None,
         };
         instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
 
         // It starts with an empty list
 
-        let response: BountiesResponse =
-            from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::Bounties {}).unwrap()).unwrap();
+        let response: BountiesResponse = from_binary(
+            &query(
+                deps.as_ref(),
+                mock_env(),
+                QueryMsg::Bounties {
+                    network: MAINNET.into(),
+                    start_after: None,
+                    limit: None,
+                    min_round: None,
+                    max_round: None,
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap();
         assert_eq!(response, BountiesResponse { bounties: vec![] });
 
         // Set first bounty and query again
 
-        let msg = ExecuteMsg::SetBounty { round: 72785 };
+        let msg = ExecuteMsg::SetBounty {
+            network: MAINNET.into(),
+            round: 72785,
+        };
         let info = mock_info(
             "anyone",
             &[Coin {
@@ -623,8 +5095,21 @@ mod tests {
         );
         execute(deps.as_mut(), mock_env(), info, msg).unwrap();
 
-        let response: BountiesResponse =
-            from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::Bounties {}).unwrap()).unwrap();
+        let response: BountiesResponse = from_binary(
+            &query(
+                deps.as_ref(),
+                mock_env(),
+                QueryMsg::Bounties {
+                    network: MAINNET.into(),
+                    start_after: None,
+                    limit: None,
+                    min_round: None,
+                    max_round: None,
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap();
         assert_eq!(
             response,
             BountiesResponse {
@@ -637,7 +5122,10 @@ mod tests {
 
         // Set second bounty and query again
 
-        let msg = ExecuteMsg::SetBounty { round: 72786 };
+        let msg = ExecuteMsg::SetBounty {
+            network: MAINNET.into(),
+            round: 72786,
+        };
         let info = mock_info(
             "anyone",
             &[Coin {
@@ -647,8 +5135,21 @@ mod tests {
         );
         execute(deps.as_mut(), mock_env(), info, msg).unwrap();
 
-        let response: BountiesResponse =
-            from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::Bounties {}).unwrap()).unwrap();
+        let response: BountiesResponse = from_binary(
+            &query(
+                deps.as_ref(),
+                mock_env(),
+                QueryMsg::Bounties {
+                    network: MAINNET.into(),
+                    start_after: None,
+                    limit: None,
+                    min_round: None,
+                    max_round: None,
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap();
         assert_eq!(
             response,
             BountiesResponse {
@@ -667,7 +5168,10 @@ mod tests {
 
         // Set third bounty and query again
 
-        let msg = ExecuteMsg::SetBounty { round: 72784 };
+        let msg = ExecuteMsg::SetBounty {
+            network: MAINNET.into(),
+            round: 72784,
+        };
         let info = mock_info(
             "anyone",
             &[Coin {
@@ -677,8 +5181,21 @@ mod tests {
         );
         execute(deps.as_mut(), mock_env(), info, msg).unwrap();
 
-        let response: BountiesResponse =
-            from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::Bounties {}).unwrap()).unwrap();
+        let response: BountiesResponse = from_binary(
+            &query(
+                deps.as_ref(),
+                mock_env(),
+                QueryMsg::Bounties {
+                    network: MAINNET.into(),
+                    start_after: None,
+                    limit: None,
+                    min_round: None,
+                    max_round: None,
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap();
         assert_eq!(
             response,
             BountiesResponse {
@@ -699,4 +5216,156 @@ mod tests {
             }
         );
     }
+
+    #[test]
+    fn query_bounties_paginates_and_filters() {
+        let mut deps = mock_dependencies();
+
+        let info = mock_info("creator", &[]);
+        let msg = InstantiateMsg {
+            networks: vec![fixtures::network(fixtures::MAINNET).network_info()],
+            bounty_denoms: vec![BOUNTY_DENOM.into()],
+            clock_tolerance: 0,
+            bounty_expiry: 86_400,
+            claim_delay: None,
+            reject_duplicates: false,
+            admin: None,
+            job_fee: None,
+        };
+        instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        for round in 7001..=7010 {
+            let msg = ExecuteMsg::SetBounty {
+                network: MAINNET.into(),
+                round,
+            };
+            let info = mock_info("anyone", &coins(round.into(), BOUNTY_DENOM));
+            execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        }
+
+        let rounds = |start_after, limit, min_round, max_round| -> Vec<u64> {
+            let response: BountiesResponse = from_binary(
+                &query(
+                    deps.as_ref(),
+                    mock_env(),
+                    QueryMsg::Bounties {
+                        network: MAINNET.into(),
+                        start_after,
+                        limit,
+                        min_round,
+                        max_round,
+                    },
+                )
+                .unwrap(),
+            )
+            .unwrap();
+            response.bounties.into_iter().map(|b| b.round).collect()
+        };
+
+        assert_eq!(rounds(None, Some(3), None, None), vec![7001, 7002, 7003]);
+        assert_eq!(
+            rounds(Some(7003), Some(3), None, None),
+            vec![7004, 7005, 7006]
+        );
+        assert_eq!(rounds(Some(7009), Some(3), None, None), vec![7010]);
+        assert_eq!(rounds(Some(u64::MAX), None, None, None), Vec::<u64>::new());
+        assert_eq!(
+            rounds(None, None, Some(7004), Some(7006)),
+            vec![7004, 7005, 7006]
+        );
+        assert_eq!(rounds(Some(7002), None, Some(7005), None).len(), 6);
+        assert_eq!(
+            rounds(Some(7005), None, Some(7002), Some(7007)),
+            vec![7006, 7007]
+        );
+        assert_eq!(
+            rounds(None, None, Some(7008), Some(7003)),
+            Vec::<u64>::new()
+        );
+        assert_eq!(rounds(None, None, None, Some(u64::MAX)).len(), 10);
+    }
+
+    #[test]
+    fn query_beacons_works() {
+        let mut deps = mock_dependencies();
+
+        let info = mock_info("creator", &[]);
+        let msg = InstantiateMsg {
+            networks: vec![fixtures::network(fixtures::MAINNET).network_info()],
+            bounty_denoms: vec![BOUNTY_DENOM.into()],
+            clock_tolerance: 0,
+            bounty_expiry: 86_400,
+            claim_delay: None,
+            reject_duplicates: false,
+            admin: None,
+            job_fee: None,
+        };
+        instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        // Store some rounds directly to avoid verifying a signature for each of them
+        for round in [3u64, 5, 8, 13, 21] {
+            let beacon = Beacon {
+                round,
+                signature: Binary::default(),
+                previous_signature: Binary::default(),
+                randomness: vec![round as u8; 32].into(),
+                submitter: Addr::unchecked("anyone"),
+                block_height: 12345,
+                block_time: Timestamp::from_seconds(1_700_000_000),
+            };
+            beacons_storage(&mut deps.storage, MAINNET)
+                .save(&round.to_be_bytes(), &beacon)
+                .unwrap();
+        }
+
+        let beacons = |start_after, limit, order| -> Vec<RoundRandomness> {
+            let response: BeaconsResponse = from_binary(
+                &query(
+                    deps.as_ref(),
+                    mock_env(),
+                    QueryMsg::Beacons {
+                        network: MAINNET.into(),
+                        start_after,
+                        limit,
+                        order,
+                    },
+                )
+                .unwrap(),
+            )
+            .unwrap();
+            response.beacons
+        };
+
+        assert_eq!(
+            beacons(None, Some(2), SortOrder::Ascending),
+            vec![
+                RoundRandomness {
+                    round: 3,
+                    randomness: vec![3; 32].into(),
+                },
+                RoundRandomness {
+                    round: 5,
+                    randomness: vec![5; 32].into(),
+                },
+            ]
+        );
+        let rounds = |start_after, limit, order| -> Vec<u64> {
+            beacons(start_after, limit, order)
+                .into_iter()
+                .map(|b| b.round)
+                .collect()
+        };
+        assert_eq!(rounds(Some(5), None, SortOrder::Ascending), vec![8, 13, 21]);
+        assert_eq!(rounds(Some(6), Some(1), SortOrder::Ascending), vec![8]);
+        assert_eq!(rounds(None, Some(2), SortOrder::Descending), vec![21, 13]);
+        assert_eq!(rounds(Some(13), None, SortOrder::Descending), vec![8, 5, 3]);
+        assert_eq!(
+            rounds(Some(0), None, SortOrder::Descending),
+            Vec::<u64>::new()
+        );
+        assert_eq!(
+            rounds(Some(u64::MAX), None, SortOrder::Ascending),
+            Vec::<u64>::new()
+        );
+    }
 }
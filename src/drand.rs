@@ -0,0 +1,94 @@
+//! The JSON format of the drand HTTP API, shared by the off-chain tools.
+//!
+//! Binary values are lowercase hex strings in the API, which are decoded on deserialization
+//! and encoded on serialization.
+
+use cosmwasm_std::Binary;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::msg::{BeaconSubmission, NetworkInfo, Scheme};
+
+/// The response of `/{chain_hash}/info`
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ChainInfo {
+    #[serde(serialize_with = "to_hex", deserialize_with = "from_hex")]
+    pub public_key: Binary,
+    pub period: u64,
+    pub genesis_time: u64,
+    /// The chain hash
+    pub hash: String,
+    /// Missing for networks created before drand 1.4, which are all pedersen-bls-chained
+    #[serde(rename = "schemeID", default, skip_serializing_if = "Option::is_none")]
+    pub scheme_id: Option<String>,
+}
+
+impl ChainInfo {
+    pub fn scheme(&self) -> Result<Scheme, String> {
+        match self.scheme_id.as_deref() {
+            None => Ok(Scheme::PedersenBlsChained),
+            Some(id) => parse_scheme_id(id).ok_or_else(|| format!("Unknown scheme {}", id)),
+        }
+    }
+
+    /// The network as registered in the contract
+    pub fn network_info(&self) -> Result<NetworkInfo, String> {
+        Ok(NetworkInfo {
+            chain_hash: self.hash.clone(),
+            pubkey: self.public_key.clone(),
+            scheme: self.scheme()?,
+            genesis_time: self.genesis_time,
+            period: self.period,
+            sample_beacon: None,
+        })
+    }
+}
+
+/// The response of `/{chain_hash}/public/{round}` and `/{chain_hash}/public/latest`
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct DrandBeacon {
+    pub round: u64,
+    #[serde(serialize_with = "to_hex", deserialize_with = "from_hex")]
+    pub randomness: Binary,
+    #[serde(serialize_with = "to_hex", deserialize_with = "from_hex")]
+    pub signature: Binary,
+    /// Only present for chained schemes
+    #[serde(
+        default,
+        serialize_with = "to_hex",
+        deserialize_with = "from_hex",
+        skip_serializing_if = "<[u8]>::is_empty"
+    )]
+    pub previous_signature: Binary,
+}
+
+impl From<DrandBeacon> for BeaconSubmission {
+    fn from(beacon: DrandBeacon) -> Self {
+        BeaconSubmission {
+            round: beacon.round,
+            previous_signature: beacon.previous_signature,
+            signature: beacon.signature,
+        }
+    }
+}
+
+/// Maps the `schemeID` of the drand API to the scheme
+pub fn parse_scheme_id(id: &str) -> Option<Scheme> {
+    match id {
+        "pedersen-bls-chained" => Some(Scheme::PedersenBlsChained),
+        "pedersen-bls-unchained" => Some(Scheme::PedersenBlsUnchained),
+        "bls-unchained-on-g1" => Some(Scheme::BlsUnchainedOnG1),
+        "bls-unchained-g1-rfc9380" => Some(Scheme::BlsUnchainedG1Rfc9380),
+        _ => None,
+    }
+}
+
+fn from_hex<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Binary, D::Error> {
+    let hex = String::deserialize(deserializer)?;
+    hex::decode(&hex)
+        .map(Binary::from)
+        .map_err(serde::de::Error::custom)
+}
+
+fn to_hex<S: Serializer>(value: &Binary, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&hex::encode(value.as_slice()))
+}
@@ -1,16 +1,96 @@
-use cosmwasm_std::StdError;
+use cosmwasm_std::{Coin, StdError};
 use thiserror::Error;
 
 #[derive(Error, Debug)]
 pub enum ContractError {
     #[error("StdError: {0}")]
     StdError(#[from] StdError),
-    #[error("Could not load pubkey into point in G1")]
+    #[error("Could not load pubkey into a curve point for the configured scheme")]
     InvalidPubkey {},
+    #[error("Invalid pubkey for network {chain_hash}: {reason}")]
+    MalformedPubkey { chain_hash: String, reason: String },
+    #[error("The sample beacon of round {round} does not verify against the pubkey of network {chain_hash}")]
+    InvalidSampleBeacon { chain_hash: String, round: u64 },
     #[error("Signature verification failed")]
     InvalidSignature {},
-    #[error("No funds were sent with the expected token: {expected_denom}")]
-    NoFundsSent { expected_denom: String },
+    #[error("No funds were sent with any of the accepted tokens: {expected_denoms}")]
+    NoFundsSent { expected_denoms: String },
+    #[error("Bounties cannot be paid in {denom}")]
+    UnacceptedDenom { denom: String },
     #[error("No beacon exists in the database")]
     NoBeacon {},
+    #[error("Unknown drand network: {network}")]
+    UnknownNetwork { network: String },
+    #[error("Invalid chain hash (must be 32 bytes in lowercase hex): {chain_hash}")]
+    InvalidChainHash { chain_hash: String },
+    #[error("Network registered more than once: {chain_hash}")]
+    DuplicateNetwork { chain_hash: String },
+    #[error("The period of a network must not be zero")]
+    InvalidPeriod {},
+    #[error("Round {round} has no representable publish time")]
+    InvalidRound { round: u64 },
+    #[error("Beacon for round {round} was migrated without a signature and cannot be delivered")]
+    MissingSignature { round: u64 },
+    #[error("Beacon for round {round} already exists")]
+    BeaconExists { round: u64 },
+    #[error("Round {round} cannot exist yet. The latest possible round is {latest_round}.")]
+    RoundInFuture { round: u64, latest_round: u64 },
+    #[error("Round {round} was already published")]
+    RoundAlreadyPublished { round: u64 },
+    #[error("Round {round} is too far ahead. The latest round accepting bounties is {max_round}.")]
+    RoundTooFarAhead { round: u64, max_round: u64 },
+    #[error("Invalid range: min ({min}) must not exceed max ({max})")]
+    InvalidRange { min: u64, max: u64 },
+    #[error("Too many elements: {count} exceeds the limit of {limit}")]
+    TooManyElements { count: u64, limit: u64 },
+    #[error("Round {round} has the maximum of {limit} pending jobs")]
+    TooManyJobs { round: u64, limit: u64 },
+    #[error("Adding round {round} exceeds the limit of {limit} callbacks per message")]
+    TooManyCallbacks { round: u64, limit: u64 },
+    #[error("Requesting a round that is not known yet costs at least {fee}")]
+    InsufficientJobFee { fee: Coin },
+    #[error(
+        "Round {round} is too old to be requested. The oldest round accepting jobs is {min_round}."
+    )]
+    RoundTooOld { round: u64, min_round: u64 },
+    #[error(
+        "The bounty on round {round} can only be withdrawn from {expires_at} (seconds since epoch)"
+    )]
+    BountyNotExpired { round: u64, expires_at: u64 },
+    #[error("Sender has no withdrawable contribution to the bounty on round {round}")]
+    NoContribution { round: u64 },
+    #[error("Cannot migrate from a different contract: {contract}")]
+    ForeignContract { contract: String },
+    #[error("Cannot migrate from version {from} to the older version {to}")]
+    CannotDowngrade { from: String, to: String },
+    #[error("Invalid contract version: {version}")]
+    InvalidVersion { version: String },
+    #[error("Migrating from a single-network version requires legacy_network to be set")]
+    MissingLegacyNetwork {},
+    #[error("Address {address} has not added any beacons")]
+    UnknownRelayer { address: String },
+    #[error("Commit-reveal bounty claims are not enabled")]
+    CommitRevealDisabled {},
+    #[error("Round {round} has no bounty")]
+    NoBounty { round: u64 },
+    #[error("Sender already committed to round {round}")]
+    AlreadyCommitted { round: u64 },
+    #[error("Sender has no commitment for round {round}")]
+    NoCommitment { round: u64 },
+    #[error("Commitment does not match the sender and signature of round {round}")]
+    CommitmentMismatch { round: u64 },
+    #[error("Commitment can only be revealed from height {reveal_height}")]
+    CommitmentTooRecent { reveal_height: u64 },
+    #[error("The bounty of round {round} is reserved for an earlier commitment until height {until_height}")]
+    ClaimReserved { round: u64, until_height: u64 },
+    #[error("Only unordered IBC channels are supported")]
+    InvalidIbcOrder {},
+    #[error("Unsupported IBC channel version {version}, expected {expected}")]
+    InvalidIbcVersion { version: String, expected: String },
+    #[error("Unsupported packet")]
+    UnsupportedPacket {},
+    #[error("Unauthorized")]
+    Unauthorized {},
+    #[error("Unknown reply ID: {id}")]
+    UnknownReplyId { id: u64 },
 }
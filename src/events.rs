@@ -0,0 +1,78 @@
+//! Typed events for indexers.
+//!
+//! The event types and attribute keys below are a stable interface. New attributes may be
+//! added, but existing ones are neither renamed nor removed. The chain prefixes custom event
+//! types with `wasm-`, so indexers see e.g. `wasm-drand_beacon`.
+//!
+//! | Event                | Attributes                                           |
+//! |----------------------|------------------------------------------------------|
+//! | `wasm-drand_beacon`  | `network`, `round`, `randomness`, `submitter`        |
+//! | `wasm-bounty_set`    | `network`, `round`, `funder`, `amount`, `denom`      |
+//! | `wasm-bounty_paid`   | `network`, `round`, `recipient`, `amount`, `denom`   |
+//!
+//! `randomness` is lower case hex. `amount` is a decimal integer in units of `denom`.
+//! Bounty events are emitted once per denom, i.e. a bounty paid in two denoms results
+//! in two `wasm-bounty_paid` events for the same round.
+
+use cosmwasm_std::{Addr, Coin, Event};
+
+/// Emitted for every beacon that is stored
+pub const DRAND_BEACON_EVENT: &str = "drand_beacon";
+/// Emitted for every denom sent to a bounty
+pub const BOUNTY_SET_EVENT: &str = "bounty_set";
+/// Emitted for every denom of a bounty paid to a relayer
+pub const BOUNTY_PAID_EVENT: &str = "bounty_paid";
+
+pub fn drand_beacon_event(
+    network: &str,
+    round: u64,
+    randomness: &[u8; 32],
+    submitter: &Addr,
+) -> Event {
+    Event::new(DRAND_BEACON_EVENT)
+        .add_attribute("network", network)
+        .add_attribute("round", round.to_string())
+        .add_attribute("randomness", hex::encode(randomness))
+        .add_attribute("submitter", submitter)
+}
+
+pub fn bounty_set_events(network: &str, round: u64, funder: &Addr, amount: &[Coin]) -> Vec<Event> {
+    bounty_events(BOUNTY_SET_EVENT, "funder", network, round, funder, amount)
+}
+
+pub fn bounty_paid_events(
+    network: &str,
+    round: u64,
+    recipient: &Addr,
+    amount: &[Coin],
+) -> Vec<Event> {
+    bounty_events(
+        BOUNTY_PAID_EVENT,
+        "recipient",
+        network,
+        round,
+        recipient,
+        amount,
+    )
+}
+
+fn bounty_events(
+    ty: &str,
+    account_key: &str,
+    network: &str,
+    round: u64,
+    account: &Addr,
+    amount: &[Coin],
+) -> Vec<Event> {
+    amount
+        .iter()
+        .map(|coin| {
+            Event::new(ty)
+                .add_attribute("network", network)
+                .add_attribute("round", round.to_string())
+                .add_attribute(account_key, account)
+                .add_attribute("amount", coin.amount.to_string())
+                .add_attribute("denom", &coin.denom)
+        })
+        .collect()
+}
@@ -0,0 +1,206 @@
+//! drand beacons for tests, checked in as JSON in `tests/fixtures`.
+//!
+//! Every network has a file with the chain info and beacons in the format of the drand HTTP API.
+//! `malformed.json` contains beacons that must be rejected, together with the reason.
+//! Use `tests/fixtures/fetch.sh` to add more rounds of real networks.
+//!
+//! Besides a few real beacons per network there is a synthetic network for every scheme with
+//! 40 consecutive rounds, written by `examples/synthetic_fixtures.rs` with a well known key.
+
+use serde::Deserialize;
+
+use crate::drand::{ChainInfo, DrandBeacon};
+use crate::msg::{BeaconSubmission, NetworkInfo};
+
+/// pedersen-bls-chained
+pub const MAINNET: &str = "mainnet";
+/// pedersen-bls-unchained
+pub const TESTNET_UNCHAINED: &str = "testnet-unchained";
+/// bls-unchained-on-g1
+pub const FASTNET: &str = "fastnet";
+/// bls-unchained-g1-rfc9380
+pub const QUICKNET: &str = "quicknet";
+/// Synthetic pedersen-bls-chained
+pub const SYNTHETIC_CHAINED: &str = "synthetic-chained";
+/// Synthetic pedersen-bls-unchained
+pub const SYNTHETIC_UNCHAINED: &str = "synthetic-unchained";
+/// Synthetic bls-unchained-on-g1
+pub const SYNTHETIC_G1: &str = "synthetic-g1";
+/// Synthetic bls-unchained-g1-rfc9380
+pub const SYNTHETIC_RFC9380: &str = "synthetic-rfc9380";
+
+const NETWORKS: [(&str, &str); 8] = [
+    (MAINNET, include_str!("../tests/fixtures/mainnet.json")),
+    (
+        TESTNET_UNCHAINED,
+        include_str!("../tests/fixtures/testnet-unchained.json"),
+    ),
+    (FASTNET, include_str!("../tests/fixtures/fastnet.json")),
+    (QUICKNET, include_str!("../tests/fixtures/quicknet.json")),
+    (
+        SYNTHETIC_CHAINED,
+        include_str!("../tests/fixtures/synthetic-chained.json"),
+    ),
+    (
+        SYNTHETIC_UNCHAINED,
+        include_str!("../tests/fixtures/synthetic-unchained.json"),
+    ),
+    (
+        SYNTHETIC_G1,
+        include_str!("../tests/fixtures/synthetic-g1.json"),
+    ),
+    (
+        SYNTHETIC_RFC9380,
+        include_str!("../tests/fixtures/synthetic-rfc9380.json"),
+    ),
+];
+
+const MALFORMED: &str = include_str!("../tests/fixtures/malformed.json");
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct NetworkFixture {
+    #[serde(skip)]
+    pub name: &'static str,
+    pub info: ChainInfo,
+    /// Sorted by round
+    pub beacons: Vec<DrandBeacon>,
+}
+
+impl NetworkFixture {
+    pub fn chain_hash(&self) -> &str {
+        &self.info.hash
+    }
+
+    pub fn network_info(&self) -> NetworkInfo {
+        self.info.network_info().unwrap()
+    }
+
+    /// Panics if the round is not in the fixtures
+    pub fn beacon(&self, round: u64) -> &DrandBeacon {
+        self.beacons
+            .iter()
+            .find(|beacon| beacon.round == round)
+            .unwrap_or_else(|| panic!("No fixture for round {} of {}", round, self.name))
+    }
+
+    pub fn submissions(&self) -> Vec<BeaconSubmission> {
+        self.beacons.iter().cloned().map(Into::into).collect()
+    }
+}
+
+/// Why a malformed beacon must be rejected
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum Rejection {
+    /// Not valid drand JSON, e.g. because of bad hex
+    InvalidJson,
+    /// The signature does not verify
+    InvalidSignature,
+    /// The signature verifies but the randomness is not derived from it
+    InvalidRandomness,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct MalformedBeacon {
+    /// The name of the network the beacon claims to belong to
+    pub network: String,
+    pub description: String,
+    pub error: Rejection,
+    /// Kept as raw JSON because it does not necessarily parse
+    pub beacon: serde_json::Value,
+}
+
+impl MalformedBeacon {
+    pub fn parse(&self) -> Result<DrandBeacon, serde_json::Error> {
+        serde_json::from_value(self.beacon.clone())
+    }
+}
+
+pub fn networks() -> Vec<NetworkFixture> {
+    NETWORKS
+        .iter()
+        .map(|(name, json)| {
+            let mut fixture: NetworkFixture = serde_json::from_str(json)
+                .unwrap_or_else(|err| panic!("Invalid fixture {}: {}", name, err));
+            fixture.name = name;
+            fixture
+        })
+        .collect()
+}
+
+/// Panics if there is no fixture for the network
+pub fn network(name: &str) -> NetworkFixture {
+    networks()
+        .into_iter()
+        .find(|fixture| fixture.name == name)
+        .unwrap_or_else(|| panic!("No fixture for network {}", name))
+}
+
+/// Panics if the round is not in the fixtures of the network
+pub fn beacon(network: &str, round: u64) -> DrandBeacon {
+    self::network(network).beacon(round).clone()
+}
+
+pub fn malformed() -> Vec<MalformedBeacon> {
+    serde_json::from_str(MALFORMED).unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::verify::verify_beacon;
+    use drand_verify::derive_randomness;
+
+    fn verify(network: &NetworkFixture, beacon: &DrandBeacon) -> Result<(), Rejection> {
+        let info = network.network_info();
+        let valid = verify_beacon(
+            info.scheme,
+            &info.pubkey,
+            beacon.round,
+            &beacon.previous_signature,
+            &beacon.signature,
+        )
+        .unwrap();
+        if !valid {
+            return Err(Rejection::InvalidSignature);
+        }
+        if beacon.randomness.as_slice() != derive_randomness(&beacon.signature) {
+            return Err(Rejection::InvalidRandomness);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn beacons_are_valid() {
+        for network in networks() {
+            assert!(!network.beacons.is_empty());
+            for beacon in &network.beacons {
+                assert_eq!(
+                    verify(&network, beacon),
+                    Ok(()),
+                    "round {} of {}",
+                    beacon.round,
+                    network.name
+                );
+            }
+            let rounds: Vec<u64> = network.beacons.iter().map(|b| b.round).collect();
+            assert!(
+                rounds.windows(2).all(|pair| pair[0] < pair[1]),
+                "{} is not sorted",
+                network.name
+            );
+        }
+    }
+
+    #[test]
+    fn malformed_beacons_are_rejected() {
+        for case in malformed() {
+            let network = network(&case.network);
+            let result = match case.parse() {
+                Ok(beacon) => verify(&network, &beacon),
+                Err(_) => Err(Rejection::InvalidJson),
+            };
+            assert_eq!(result, Err(case.error), "{}", case.description);
+        }
+    }
+}
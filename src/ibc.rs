@@ -0,0 +1,446 @@
+//! IBC entry points serving verified beacons to remote chains.
+//!
+//! A consumer opens an unordered channel with version [`IBC_VERSION`] and sends
+//! `RandPacket::RequestBeacon`. If the round is stored already, the beacon is delivered
+//! right away. Otherwise the request is kept and the beacon is delivered as soon as a
+//! relayer adds it. Deliveries that time out are not retried, the consumer can request
+//! the round again. Beacons migrated from 0.2.0 have no signature and cannot be delivered.
+
+#[cfg(not(feature = "library"))]
+use cosmwasm_std::entry_point;
+use cosmwasm_std::{
+    from_binary, to_binary, Binary, DepsMut, Env, IbcBasicResponse, IbcChannel, IbcChannelCloseMsg,
+    IbcChannelConnectMsg, IbcChannelOpenMsg, IbcMsg, IbcOrder, IbcPacketAckMsg,
+    IbcPacketReceiveMsg, IbcPacketTimeoutMsg, IbcReceiveResponse, IbcTimeout, StdResult, Storage,
+    SubMsg,
+};
+
+use crate::contract::load_network;
+use crate::errors::ContractError;
+use crate::msg::{RandAck, RandPacket, IBC_VERSION};
+use crate::state::{
+    beacons_storage_read, ibc_channels_storage, ibc_channels_storage_read, ibc_requests_storage,
+    ibc_requests_storage_read, Beacon,
+};
+
+/// Seconds after which a delivery packet times out
+const DELIVERY_TIMEOUT: u64 = 24 * 60 * 60;
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn ibc_channel_open(
+    _deps: DepsMut,
+    _env: Env,
+    msg: IbcChannelOpenMsg,
+) -> Result<(), ContractError> {
+    validate_channel(msg.channel(), msg.counterparty_version())
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn ibc_channel_connect(
+    deps: DepsMut,
+    _env: Env,
+    msg: IbcChannelConnectMsg,
+) -> Result<IbcBasicResponse, ContractError> {
+    let channel = msg.channel();
+    validate_channel(channel, msg.counterparty_version())?;
+    ibc_channels_storage(deps.storage).save(channel.endpoint.channel_id.as_bytes(), channel)?;
+
+    Ok(IbcBasicResponse::new()
+        .add_attribute("action", "ibc_connect")
+        .add_attribute("channel_id", &channel.endpoint.channel_id))
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn ibc_channel_close(
+    deps: DepsMut,
+    _env: Env,
+    msg: IbcChannelCloseMsg,
+) -> Result<IbcBasicResponse, ContractError> {
+    let channel_id = &msg.channel().endpoint.channel_id;
+    // Pending requests of the channel are skipped on delivery
+    ibc_channels_storage(deps.storage).remove(channel_id.as_bytes());
+
+    Ok(IbcBasicResponse::new()
+        .add_attribute("action", "ibc_close")
+        .add_attribute("channel_id", channel_id))
+}
+
+/// Failures are returned as error acknowledgements rather than errors, such that the
+/// consumer learns about them.
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn ibc_packet_receive(
+    deps: DepsMut,
+    env: Env,
+    msg: IbcPacketReceiveMsg,
+) -> Result<IbcReceiveResponse, ContractError> {
+    let channel_id = msg.packet.dest.channel_id;
+    let result = from_binary(&msg.packet.data)
+        .map_err(ContractError::from)
+        .and_then(|packet| match packet {
+            RandPacket::RequestBeacon { network, round } => {
+                receive_request(deps, &env, channel_id, network, round)
+            }
+            RandPacket::DeliverBeacon { .. } => Err(ContractError::UnsupportedPacket {}),
+        });
+
+    Ok(result.unwrap_or_else(|err| {
+        IbcReceiveResponse::new()
+            .set_ack(ack_error(err.to_string()))
+            .add_attribute("action", "ibc_receive")
+            .add_attribute("error", err.to_string())
+    }))
+}
+
+fn receive_request(
+    deps: DepsMut,
+    env: &Env,
+    channel_id: String,
+    network: String,
+    round: u64,
+) -> Result<IbcReceiveResponse, ContractError> {
+    load_network(deps.storage, &network)?;
+    if round == 0 {
+        return Err(ContractError::InvalidRound { round });
+    }
+
+    let response = IbcReceiveResponse::new()
+        .set_ack(ack_success())
+        .add_attribute("action", "ibc_receive")
+        .add_attribute("round", round.to_string());
+
+    let beacon = beacons_storage_read(deps.storage, &network).may_load(&round.to_be_bytes())?;
+    if let Some(beacon) = beacon {
+        // The consumer verifies the signature, which beacons migrated from 0.2.0 lack
+        if beacon.signature.is_empty() {
+            return Err(ContractError::MissingSignature { round });
+        }
+        let msg = delivery_msg(env, channel_id, network, &beacon)?;
+        return Ok(response.add_message(msg));
+    }
+
+    ibc_requests_storage(deps.storage, &network).update(
+        &round.to_be_bytes(),
+        |channels| -> StdResult<_> {
+            let mut channels = channels.unwrap_or_default();
+            if !channels.contains(&channel_id) {
+                channels.push(channel_id);
+            }
+            Ok(channels)
+        },
+    )?;
+    Ok(response)
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn ibc_packet_ack(
+    _deps: DepsMut,
+    _env: Env,
+    msg: IbcPacketAckMsg,
+) -> Result<IbcBasicResponse, ContractError> {
+    // Nothing to roll back, a failed delivery only concerns the consumer
+    let response = IbcBasicResponse::new().add_attribute("action", "ibc_ack");
+    Ok(match from_binary(&msg.acknowledgement.data)? {
+        RandAck::Result(_) => response,
+        RandAck::Error(err) => response.add_attribute("error", err),
+    })
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn ibc_packet_timeout(
+    _deps: DepsMut,
+    _env: Env,
+    _msg: IbcPacketTimeoutMsg,
+) -> Result<IbcBasicResponse, ContractError> {
+    Ok(IbcBasicResponse::new().add_attribute("action", "ibc_timeout"))
+}
+
+/// Checks the order and version of a channel opened with the rand protocol
+pub fn validate_channel(
+    channel: &IbcChannel,
+    counterparty_version: Option<&str>,
+) -> Result<(), ContractError> {
+    if channel.order != IbcOrder::Unordered {
+        return Err(ContractError::InvalidIbcOrder {});
+    }
+    for version in std::iter::once(channel.version.as_str()).chain(counterparty_version) {
+        if version != IBC_VERSION {
+            return Err(ContractError::InvalidIbcVersion {
+                version: version.to_string(),
+                expected: IBC_VERSION.to_string(),
+            });
+        }
+    }
+    Ok(())
+}
+
+/// Creates the delivery packets for all open channels waiting for the beacon and
+/// removes their requests. Only called for beacons that were just added, so the
+/// signature is always set.
+pub(crate) fn take_deliveries(
+    storage: &mut dyn Storage,
+    env: &Env,
+    network: &str,
+    beacon: &Beacon,
+) -> StdResult<Vec<SubMsg>> {
+    let round_key = beacon.round.to_be_bytes();
+    let channels = ibc_requests_storage_read(storage, network)
+        .may_load(&round_key)?
+        .unwrap_or_default();
+    if channels.is_empty() {
+        return Ok(vec![]);
+    }
+    ibc_requests_storage(storage, network).remove(&round_key);
+
+    let open_channels = ibc_channels_storage_read(storage);
+    let mut deliveries = vec![];
+    for channel_id in channels {
+        if open_channels.may_load(channel_id.as_bytes())?.is_some() {
+            let msg = delivery_msg(env, channel_id, network.to_string(), beacon)?;
+            deliveries.push(SubMsg::new(msg));
+        }
+    }
+    Ok(deliveries)
+}
+
+fn delivery_msg(
+    env: &Env,
+    channel_id: String,
+    network: String,
+    beacon: &Beacon,
+) -> StdResult<IbcMsg> {
+    let packet = RandPacket::DeliverBeacon {
+        network,
+        round: beacon.round,
+        previous_signature: beacon.previous_signature.clone(),
+        signature: beacon.signature.clone(),
+        randomness: beacon.randomness.clone(),
+    };
+    Ok(IbcMsg::SendPacket {
+        channel_id,
+        data: to_binary(&packet)?,
+        timeout: IbcTimeout::with_timestamp(env.block.time.plus_seconds(DELIVERY_TIMEOUT)),
+    })
+}
+
+pub fn ack_success() -> Binary {
+    to_binary(&RandAck::Result(Binary::default())).unwrap()
+}
+
+pub fn ack_error(err: String) -> Binary {
+    to_binary(&RandAck::Error(err)).unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::contract::{execute, instantiate};
+    use crate::fixtures;
+    use crate::msg::{ExecuteMsg, InstantiateMsg};
+    use crate::state::beacons_storage;
+    use cosmwasm_std::testing::{
+        mock_dependencies, mock_env, mock_ibc_channel_close_init, mock_ibc_channel_connect_ack,
+        mock_ibc_channel_open_init, mock_ibc_channel_open_try, mock_ibc_packet_recv, mock_info,
+        MockApi, MockQuerier, MockStorage,
+    };
+    use cosmwasm_std::{OwnedDeps, Timestamp};
+
+    const MAINNET: &str = "8990e7a9aaed2ffed73dbd7092123d6f289930540d7651336225dc172e51b2ce";
+
+    /// A block time after all rounds used in the tests were published
+    fn mock_env_later() -> Env {
+        let mut env = mock_env();
+        env.block.time = Timestamp::from_seconds(1_700_000_000);
+        env
+    }
+
+    fn add_72785() -> ExecuteMsg {
+        let beacon = fixtures::beacon(fixtures::MAINNET, 72785);
+        ExecuteMsg::Add {
+            network: MAINNET.into(),
+            round: 72785,
+            previous_signature: beacon.previous_signature,
+            signature: beacon.signature,
+        }
+    }
+
+    fn delivery_72785(channel_id: &str) -> SubMsg {
+        let beacon = fixtures::beacon(fixtures::MAINNET, 72785);
+        let packet = RandPacket::DeliverBeacon {
+            network: MAINNET.into(),
+            round: 72785,
+            previous_signature: beacon.previous_signature,
+            signature: beacon.signature,
+            randomness: beacon.randomness,
+        };
+        SubMsg::new(IbcMsg::SendPacket {
+            channel_id: channel_id.into(),
+            data: to_binary(&packet).unwrap(),
+            timeout: IbcTimeout::with_timestamp(
+                mock_env_later().block.time.plus_seconds(DELIVERY_TIMEOUT),
+            ),
+        })
+    }
+
+    fn setup() -> OwnedDeps<MockStorage, MockApi, MockQuerier> {
+        let mut deps = mock_dependencies();
+        let msg = InstantiateMsg {
+            networks: vec![fixtures::network(fixtures::MAINNET).network_info()],
+            bounty_denoms: vec!["ucosm".into()],
+            clock_tolerance: 0,
+            bounty_expiry: 86_400,
+            claim_delay: None,
+            reject_duplicates: false,
+            admin: None,
+            job_fee: None,
+        };
+        instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+        deps
+    }
+
+    fn connect(mut deps: DepsMut, channel_id: &str) {
+        let msg = mock_ibc_channel_open_init(channel_id, IbcOrder::Unordered, IBC_VERSION);
+        ibc_channel_open(deps.branch(), mock_env(), msg).unwrap();
+        let msg = mock_ibc_channel_connect_ack(channel_id, IbcOrder::Unordered, IBC_VERSION);
+        ibc_channel_connect(deps, mock_env(), msg).unwrap();
+    }
+
+    fn request(network: &str, round: u64) -> RandPacket {
+        RandPacket::RequestBeacon {
+            network: network.into(),
+            round,
+        }
+    }
+
+    #[test]
+    fn channel_open_validates_order_and_version() {
+        let mut deps = setup();
+
+        let msg = mock_ibc_channel_open_init("channel-1", IbcOrder::Ordered, IBC_VERSION);
+        match ibc_channel_open(deps.as_mut(), mock_env(), msg).unwrap_err() {
+            ContractError::InvalidIbcOrder {} => {}
+            err => panic!("Unexpected error: {:?}", err),
+        }
+
+        let msg = mock_ibc_channel_open_try("channel-1", IbcOrder::Unordered, "ics20-1");
+        match ibc_channel_open(deps.as_mut(), mock_env(), msg).unwrap_err() {
+            ContractError::InvalidIbcVersion { version, expected } => {
+                assert_eq!(version, "ics20-1");
+                assert_eq!(expected, IBC_VERSION);
+            }
+            err => panic!("Unexpected error: {:?}", err),
+        }
+
+        let msg = mock_ibc_channel_open_try("channel-1", IbcOrder::Unordered, IBC_VERSION);
+        ibc_channel_open(deps.as_mut(), mock_env(), msg).unwrap();
+    }
+
+    #[test]
+    fn known_rounds_are_delivered_immediately() {
+        let mut deps = setup();
+        connect(deps.as_mut(), "channel-1");
+        execute(
+            deps.as_mut(),
+            mock_env_later(),
+            mock_info("relayer", &[]),
+            add_72785(),
+        )
+        .unwrap();
+
+        let msg = mock_ibc_packet_recv("channel-1", &request(MAINNET, 72785)).unwrap();
+        let response = ibc_packet_receive(deps.as_mut(), mock_env_later(), msg).unwrap();
+        assert_eq!(response.acknowledgement, ack_success());
+        assert_eq!(response.messages, vec![delivery_72785("channel-1")]);
+    }
+
+    #[test]
+    fn requested_rounds_are_delivered_when_added() {
+        let mut deps = setup();
+        connect(deps.as_mut(), "channel-1");
+        connect(deps.as_mut(), "channel-2");
+        connect(deps.as_mut(), "channel-3");
+
+        for channel_id in ["channel-1", "channel-2", "channel-2", "channel-3"] {
+            let msg = mock_ibc_packet_recv(channel_id, &request(MAINNET, 72785)).unwrap();
+            let response = ibc_packet_receive(deps.as_mut(), mock_env(), msg).unwrap();
+            assert_eq!(response.acknowledgement, ack_success());
+            assert_eq!(response.messages, vec![]);
+        }
+
+        let msg = mock_ibc_channel_close_init("channel-3", IbcOrder::Unordered, IBC_VERSION);
+        ibc_channel_close(deps.as_mut(), mock_env(), msg).unwrap();
+
+        // Every open channel gets the beacon once
+        let response = execute(
+            deps.as_mut(),
+            mock_env_later(),
+            mock_info("relayer", &[]),
+            add_72785(),
+        )
+        .unwrap();
+        assert_eq!(
+            response.messages,
+            vec![delivery_72785("channel-1"), delivery_72785("channel-2")]
+        );
+        assert_eq!(
+            ibc_requests_storage_read(&deps.storage, MAINNET)
+                .may_load(&72785u64.to_be_bytes())
+                .unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn invalid_packets_are_acknowledged_with_error() {
+        let mut deps = setup();
+        connect(deps.as_mut(), "channel-1");
+
+        let msg = mock_ibc_packet_recv("channel-1", &request("unknown", 72785)).unwrap();
+        let response = ibc_packet_receive(deps.as_mut(), mock_env(), msg).unwrap();
+        assert_eq!(
+            response.acknowledgement,
+            ack_error("Unknown drand network: unknown".into())
+        );
+
+        let msg = mock_ibc_packet_recv("channel-1", &request(MAINNET, 0)).unwrap();
+        let response = ibc_packet_receive(deps.as_mut(), mock_env(), msg).unwrap();
+        assert_eq!(
+            response.acknowledgement,
+            ack_error(ContractError::InvalidRound { round: 0 }.to_string())
+        );
+
+        let beacon = fixtures::beacon(fixtures::MAINNET, 72785);
+        let packet = RandPacket::DeliverBeacon {
+            network: MAINNET.into(),
+            round: 72785,
+            previous_signature: beacon.previous_signature,
+            signature: beacon.signature,
+            randomness: Binary::default(),
+        };
+        let msg = mock_ibc_packet_recv("channel-1", &packet).unwrap();
+        let response = ibc_packet_receive(deps.as_mut(), mock_env(), msg).unwrap();
+        assert_eq!(
+            response.acknowledgement,
+            ack_error("Unsupported packet".into())
+        );
+        // Beacons migrated from 0.2.0 have no signature the consumer could verify
+        let beacon = Beacon {
+            round: 42,
+            signature: Binary::default(),
+            previous_signature: Binary::default(),
+            randomness: vec![0x42; 32].into(),
+            submitter: mock_env().contract.address,
+            block_height: 1,
+            block_time: Timestamp::from_seconds(1),
+        };
+        beacons_storage(&mut deps.storage, MAINNET)
+            .save(&42u64.to_be_bytes(), &beacon)
+            .unwrap();
+        let msg = mock_ibc_packet_recv("channel-1", &request(MAINNET, 42)).unwrap();
+        let response = ibc_packet_receive(deps.as_mut(), mock_env(), msg).unwrap();
+        assert_eq!(response.messages, vec![]);
+        assert_eq!(
+            response.acknowledgement,
+            ack_error(ContractError::MissingSignature { round: 42 }.to_string())
+        );
+    }
+}
@@ -1,4 +1,12 @@
 pub mod contract;
-mod errors;
+pub mod drand;
+pub mod errors;
+pub mod events;
+#[cfg(feature = "fixtures")]
+pub mod fixtures;
+pub mod ibc;
+mod migrations;
 pub mod msg;
+pub mod randomness;
 pub mod state;
+pub mod verify;
@@ -0,0 +1,422 @@
+//! Data migrations between storage layouts of different contract versions
+
+use cosmwasm_std::{coins, Binary, Env, Order, Storage};
+use cosmwasm_storage::{singleton, singleton_read, PrefixedStorage, ReadonlyPrefixedStorage};
+use serde::{Deserialize, Serialize};
+
+use crate::contract::add_network;
+use crate::errors::ContractError;
+use crate::msg::{default_bounty_expiry, MigrateMsg, NetworkInfo, Scheme};
+use crate::state::{beacons_storage, bounties_storage, config, Beacon, Config};
+
+/// A data migration that converts the storage of the previous version. Returns false if
+/// `MigrateMsg::limit` was reached before everything was converted.
+type Migration = fn(&mut dyn Storage, &Env, &MigrateMsg) -> Result<bool, ContractError>;
+
+/// All data migrations in ascending order. A migration is run when migrating from a version
+/// lower than the one it is listed with.
+pub const MIGRATIONS: &[(&str, Migration)] = &[("0.3.0", migrate_to_0_3_0)];
+
+const LEGACY_CONFIG_KEY: &[u8] = b"config";
+const LEGACY_BEACONS_KEY: &[u8] = b"beacons";
+const LEGACY_BOUNTIES_KEY: &[u8] = b"bounties";
+const PROGRESS_0_3_0_KEY: &[u8] = b"migration_0_3_0";
+
+/// Keys of per-network stores start with the length prefix of the 64 character chain hash,
+/// while legacy keys are rounds, which start with two zero bytes. Ranging up to this key
+/// finds the remaining legacy entries without reading the already converted ones.
+const LEGACY_ROUNDS_END: &[u8] = &[0, 64];
+
+/// The config of 0.2.0, which supported a single network and denom
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+struct LegacyConfig {
+    pubkey: Binary,
+    bounty_denom: String,
+}
+
+/// Stored while a 0.3.0 migration is incomplete. The legacy config is overwritten by the
+/// new one in the first step, so later steps need these from here.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+struct Progress0_3_0 {
+    network: String,
+    bounty_denom: String,
+}
+
+/// Moves the single network of 0.2.0 into the multi-network layout. Beacons and bounties are
+/// converted from raw stores keyed by round into the per-network stores. 0.2.0 did not keep
+/// signatures, so migrated beacons have empty signatures, cannot be delivered over IBC and
+/// record the contract as submitter at the migration block. Bounties set in 0.2.0 have no recorded contributors and can only be
+/// claimed by adding the round.
+///
+/// Beacons are converted before bounties, at most `MigrateMsg::limit` entries per call. The
+/// config is converted in the first call, which stores the progress for the following ones.
+fn migrate_to_0_3_0(
+    storage: &mut dyn Storage,
+    env: &Env,
+    msg: &MigrateMsg,
+) -> Result<bool, ContractError> {
+    let progress = match singleton_read(storage, PROGRESS_0_3_0_KEY).may_load()? {
+        Some(progress) => progress,
+        None => convert_config_0_2_0(storage, msg)?,
+    };
+    let Progress0_3_0 {
+        network,
+        bounty_denom,
+    } = &progress;
+
+    let mut limit = msg.limit.map_or(usize::MAX, |limit| limit as usize);
+    let beacons = take_legacy_rounds(storage, LEGACY_BEACONS_KEY, limit);
+    limit -= beacons.len();
+    for (key, randomness) in beacons {
+        let beacon = Beacon {
+            round: u64::from_be_bytes(Binary(key.clone()).to_array()?),
+            signature: Binary::default(),
+            previous_signature: Binary::default(),
+            randomness: randomness.into(),
+            submitter: env.contract.address.clone(),
+            block_height: env.block.height,
+            block_time: env.block.time,
+        };
+        beacons_storage(storage, network).save(&key, &beacon)?;
+    }
+    for (round, amount) in take_legacy_rounds(storage, LEGACY_BOUNTIES_KEY, limit) {
+        let amount = u128::from_be_bytes(Binary(amount).to_array()?);
+        bounties_storage(storage, network).save(&round, &coins(amount, bounty_denom))?;
+    }
+
+    let complete = !has_legacy_rounds(storage, LEGACY_BEACONS_KEY)
+        && !has_legacy_rounds(storage, LEGACY_BOUNTIES_KEY);
+    if complete {
+        singleton::<Progress0_3_0>(storage, PROGRESS_0_3_0_KEY).remove();
+    } else {
+        singleton(storage, PROGRESS_0_3_0_KEY).save(&progress)?;
+    }
+    Ok(complete)
+}
+
+/// Replaces the legacy config by the new config and network
+fn convert_config_0_2_0(
+    storage: &mut dyn Storage,
+    msg: &MigrateMsg,
+) -> Result<Progress0_3_0, ContractError> {
+    let legacy_network = msg
+        .legacy_network
+        .clone()
+        .ok_or(ContractError::MissingLegacyNetwork {})?;
+    let LegacyConfig {
+        pubkey,
+        bounty_denom,
+    } = singleton_read(storage, LEGACY_CONFIG_KEY).load()?;
+
+    let network = legacy_network.chain_hash.clone();
+    add_network(
+        storage,
+        NetworkInfo {
+            chain_hash: legacy_network.chain_hash,
+            pubkey,
+            scheme: Scheme::PedersenBlsChained,
+            genesis_time: legacy_network.genesis_time,
+            period: legacy_network.period,
+            sample_beacon: None,
+        },
+    )?;
+    config(storage).save(&Config {
+        bounty_denoms: vec![bounty_denom.clone()],
+        clock_tolerance: 0,
+        bounty_expiry: default_bounty_expiry(),
+        claim_delay: None,
+        reject_duplicates: false,
+        admin: None,
+        job_fee: None,
+    })?;
+    Ok(Progress0_3_0 {
+        network,
+        bounty_denom,
+    })
+}
+
+/// Removes up to `limit` entries of a raw 0.2.0 store keyed by round and returns them.
+/// The prefix is shared with the per-network stores, which have longer keys.
+fn take_legacy_rounds(
+    storage: &mut dyn Storage,
+    prefix: &[u8],
+    limit: usize,
+) -> Vec<(Vec<u8>, Vec<u8>)> {
+    let entries: Vec<_> = ReadonlyPrefixedStorage::new(storage, prefix)
+        .range(None, Some(LEGACY_ROUNDS_END), Order::Ascending)
+        .filter(|(key, _)| key.len() == 8)
+        .take(limit)
+        .collect();
+    let mut store = PrefixedStorage::new(storage, prefix);
+    for (key, _) in &entries {
+        store.remove(key);
+    }
+    entries
+}
+
+fn has_legacy_rounds(storage: &dyn Storage, prefix: &[u8]) -> bool {
+    ReadonlyPrefixedStorage::new(storage, prefix)
+        .range(None, Some(LEGACY_ROUNDS_END), Order::Ascending)
+        .any(|(key, _)| key.len() == 8)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::contract::{migrate, query};
+    use crate::fixtures;
+    use crate::msg::{
+        BeaconResponse, BountiesResponse, Bounty, ConfigResponse, GetResponse, LatestResponse,
+        LegacyNetwork, NetworksResponse, QueryMsg,
+    };
+    use crate::state::beacons_storage_read;
+    use cosmwasm_std::testing::{mock_dependencies, mock_env};
+    use cosmwasm_std::{from_binary, Deps};
+    use cosmwasm_storage::singleton;
+    use cw2::{get_contract_version, set_contract_version};
+
+    const MAINNET: &str = "8990e7a9aaed2ffed73dbd7092123d6f289930540d7651336225dc172e51b2ce";
+
+    /// Randomness of mainnet round 72785
+    fn randomness_72785() -> Binary {
+        fixtures::beacon(fixtures::MAINNET, 72785).randomness
+    }
+
+    fn legacy_network() -> LegacyNetwork {
+        LegacyNetwork {
+            chain_hash: MAINNET.into(),
+            genesis_time: 1595431050,
+            period: 30,
+        }
+    }
+
+    /// Writes the state of a 0.2.0 contract with two beacons and two bounties
+    fn setup_0_2_0(storage: &mut dyn Storage) {
+        set_contract_version(storage, "crates.io:rand", "0.2.0").unwrap();
+        singleton(storage, LEGACY_CONFIG_KEY)
+            .save(&LegacyConfig {
+                pubkey: fixtures::network(fixtures::MAINNET).network_info().pubkey,
+                bounty_denom: "ucosm".into(),
+            })
+            .unwrap();
+        let mut beacons = PrefixedStorage::new(storage, LEGACY_BEACONS_KEY);
+        beacons.set(&72784u64.to_be_bytes(), &[0x11; 32]);
+        beacons.set(&72785u64.to_be_bytes(), &randomness_72785());
+        let mut bounties = PrefixedStorage::new(storage, LEGACY_BOUNTIES_KEY);
+        bounties.set(&72786u64.to_be_bytes(), &4500u128.to_be_bytes());
+        bounties.set(&80000u64.to_be_bytes(), &12u128.to_be_bytes());
+    }
+
+    fn query_as<T: serde::de::DeserializeOwned>(deps: Deps, msg: QueryMsg) -> T {
+        from_binary(&query(deps, mock_env(), msg).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn migrate_from_0_2_0_works() {
+        let mut deps = mock_dependencies();
+        setup_0_2_0(&mut deps.storage);
+
+        let msg = MigrateMsg {
+            legacy_network: Some(legacy_network()),
+            limit: None,
+        };
+        let response = migrate(deps.as_mut(), mock_env(), msg).unwrap();
+        assert_eq!(response.attributes[0].value, "0.2.0");
+
+        let version = get_contract_version(&deps.storage).unwrap();
+        assert_eq!(version.contract, "crates.io:rand");
+        assert_eq!(version.version, env!("CARGO_PKG_VERSION"));
+
+        let config: ConfigResponse = query_as(deps.as_ref(), QueryMsg::Config {});
+        assert_eq!(config.bounty_denoms, vec!["ucosm".to_string()]);
+        let networks: NetworksResponse = query_as(deps.as_ref(), QueryMsg::Networks {});
+        assert_eq!(
+            networks.networks,
+            vec![fixtures::network(fixtures::MAINNET).network_info()]
+        );
+
+        let latest: LatestResponse = query_as(
+            deps.as_ref(),
+            QueryMsg::Latest {
+                network: MAINNET.into(),
+            },
+        );
+        assert_eq!(latest.round, 72785);
+        assert_eq!(latest.randomness, randomness_72785());
+        let get: GetResponse = query_as(
+            deps.as_ref(),
+            QueryMsg::Get {
+                network: MAINNET.into(),
+                round: 72784,
+            },
+        );
+        assert_eq!(get.randomness, vec![0x11; 32]);
+        let beacon: BeaconResponse = query_as(
+            deps.as_ref(),
+            QueryMsg::Beacon {
+                network: MAINNET.into(),
+                round: 72785,
+            },
+        );
+        assert_eq!(beacon.beacon.signature, Binary::default());
+        assert_eq!(beacon.beacon.submitter, mock_env().contract.address);
+
+        let bounties: BountiesResponse = query_as(
+            deps.as_ref(),
+            QueryMsg::Bounties {
+                network: MAINNET.into(),
+                start_after: None,
+                limit: None,
+                min_round: None,
+                max_round: None,
+            },
+        );
+        assert_eq!(
+            bounties.bounties,
+            vec![
+                Bounty {
+                    round: 72786,
+                    amount: coins(4500, "ucosm"),
+                },
+                Bounty {
+                    round: 80000,
+                    amount: coins(12, "ucosm"),
+                },
+            ]
+        );
+
+        // The legacy stores are gone
+        let legacy_beacons = ReadonlyPrefixedStorage::new(&deps.storage, LEGACY_BEACONS_KEY);
+        assert!(legacy_beacons.get(&72785u64.to_be_bytes()).is_none());
+        let legacy_bounties = ReadonlyPrefixedStorage::new(&deps.storage, LEGACY_BOUNTIES_KEY);
+        assert!(legacy_bounties.get(&72786u64.to_be_bytes()).is_none());
+    }
+
+    #[test]
+    fn migrate_from_0_2_0_can_be_paged() {
+        let mut deps = mock_dependencies();
+        setup_0_2_0(&mut deps.storage);
+        // 2 + 250 beacons and 2 + 100 bounties
+        let mut beacons = PrefixedStorage::new(&mut deps.storage, LEGACY_BEACONS_KEY);
+        for round in 1..=250u64 {
+            beacons.set(&round.to_be_bytes(), &[round as u8; 32]);
+        }
+        let mut bounties = PrefixedStorage::new(&mut deps.storage, LEGACY_BOUNTIES_KEY);
+        for round in 100_001..=100_100u64 {
+            bounties.set(&round.to_be_bytes(), &7u128.to_be_bytes());
+        }
+
+        let msg = MigrateMsg {
+            legacy_network: Some(legacy_network()),
+            limit: Some(100),
+        };
+        for _ in 0..3 {
+            let response = migrate(deps.as_mut(), mock_env(), msg.clone()).unwrap();
+            assert_eq!(response.attributes[1].key, "pending_migration");
+            assert_eq!(response.attributes[1].value, "0.3.0");
+            let version = get_contract_version(&deps.storage).unwrap();
+            assert_eq!(version.version, "0.2.0");
+        }
+        // The network is only added once, so resuming does not fail with DuplicateNetwork
+        let networks: NetworksResponse = query_as(deps.as_ref(), QueryMsg::Networks {});
+        assert_eq!(networks.networks.len(), 1);
+
+        // The remaining 54 entries fit into the last call
+        let response = migrate(deps.as_mut(), mock_env(), msg).unwrap();
+        assert_eq!(response.attributes[1].key, "to_version");
+        let version = get_contract_version(&deps.storage).unwrap();
+        assert_eq!(version.version, env!("CARGO_PKG_VERSION"));
+        assert!(
+            singleton_read::<Progress0_3_0>(&deps.storage, PROGRESS_0_3_0_KEY)
+                .may_load()
+                .unwrap()
+                .is_none()
+        );
+
+        for round in [1u64, 250, 72784, 72785] {
+            beacons_storage_read(&deps.storage, MAINNET)
+                .load(&round.to_be_bytes())
+                .unwrap();
+        }
+        let bounties = |min_round, max_round| -> BountiesResponse {
+            query_as(
+                deps.as_ref(),
+                QueryMsg::Bounties {
+                    network: MAINNET.into(),
+                    start_after: None,
+                    limit: Some(100),
+                    min_round,
+                    max_round,
+                },
+            )
+        };
+        assert_eq!(bounties(None, Some(100_000)).bounties.len(), 2);
+        assert_eq!(bounties(Some(100_001), None).bounties.len(), 100);
+        assert!(!has_legacy_rounds(&deps.storage, LEGACY_BEACONS_KEY));
+        assert!(!has_legacy_rounds(&deps.storage, LEGACY_BOUNTIES_KEY));
+    }
+
+    #[test]
+    fn migrate_from_0_2_0_requires_legacy_network() {
+        let mut deps = mock_dependencies();
+        setup_0_2_0(&mut deps.storage);
+
+        let msg = MigrateMsg {
+            legacy_network: None,
+            limit: None,
+        };
+        match migrate(deps.as_mut(), mock_env(), msg).unwrap_err() {
+            ContractError::MissingLegacyNetwork {} => {}
+            err => panic!("Unexpected error: {:?}", err),
+        }
+    }
+
+    #[test]
+    fn migrate_rejects_foreign_contracts_and_downgrades() {
+        let mut deps = mock_dependencies();
+        set_contract_version(&mut deps.storage, "crates.io:cw20-base", "0.2.0").unwrap();
+        let msg = MigrateMsg {
+            legacy_network: Some(legacy_network()),
+            limit: None,
+        };
+        match migrate(deps.as_mut(), mock_env(), msg.clone()).unwrap_err() {
+            ContractError::ForeignContract { contract } => {
+                assert_eq!(contract, "crates.io:cw20-base")
+            }
+            err => panic!("Unexpected error: {:?}", err),
+        }
+
+        set_contract_version(&mut deps.storage, "crates.io:rand", "99.0.0").unwrap();
+        match migrate(deps.as_mut(), mock_env(), msg.clone()).unwrap_err() {
+            ContractError::CannotDowngrade { from, to } => {
+                assert_eq!(from, "99.0.0");
+                assert_eq!(to, env!("CARGO_PKG_VERSION"));
+            }
+            err => panic!("Unexpected error: {:?}", err),
+        }
+
+        set_contract_version(&mut deps.storage, "crates.io:rand", "latest").unwrap();
+        match migrate(deps.as_mut(), mock_env(), msg).unwrap_err() {
+            ContractError::InvalidVersion { version } => assert_eq!(version, "latest"),
+            err => panic!("Unexpected error: {:?}", err),
+        }
+    }
+
+    #[test]
+    fn migrate_from_current_version_runs_no_migrations() {
+        let mut deps = mock_dependencies();
+        set_contract_version(
+            &mut deps.storage,
+            "crates.io:rand",
+            env!("CARGO_PKG_VERSION"),
+        )
+        .unwrap();
+
+        // Would fail with MissingLegacyNetwork if the 0.3.0 migration ran
+        let msg = MigrateMsg {
+            legacy_network: None,
+            limit: None,
+        };
+        migrate(deps.as_mut(), mock_env(), msg).unwrap();
+    }
+}
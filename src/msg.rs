@@ -1,39 +1,396 @@
-use cosmwasm_std::{Binary, Coin};
+use cosmwasm_std::{to_binary, Binary, Coin, CosmosMsg, StdResult, Timestamp, WasmMsg};
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 
+use crate::state::{Beacon, Job, RelayerStats};
+
+/// The drand signature scheme of a network, as reported in the `schemeID` field of
+/// the network's `/info` endpoint.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Scheme {
+    /// pedersen-bls-chained: pubkey on G1, signatures on G2, every signature covers the
+    /// previous one. This is the classic League of Entropy mainnet.
+    PedersenBlsChained,
+    /// pedersen-bls-unchained: pubkey on G1, signatures on G2, no previous signature.
+    PedersenBlsUnchained,
+    /// bls-unchained-on-g1: pubkey on G2, signatures on G1, no previous signature (fastnet).
+    BlsUnchainedOnG1,
+    /// bls-unchained-g1-rfc9380: pubkey on G2, signatures on G1, no previous signature (quicknet).
+    BlsUnchainedG1Rfc9380,
+}
+
+// Implemented by hand because `#[default]` on enum variants requires Rust 1.62
+#[allow(clippy::derivable_impls)]
+impl Default for Scheme {
+    fn default() -> Self {
+        Scheme::PedersenBlsChained
+    }
+}
+
+impl Scheme {
+    /// Returns true if signatures of this scheme include the previous signature.
+    pub fn is_chained(&self) -> bool {
+        matches!(self, Scheme::PedersenBlsChained)
+    }
+
+    /// Returns true if the public key of this scheme is a G1 point and false if it is a G2 point.
+    pub fn pubkey_on_g1(&self) -> bool {
+        matches!(
+            self,
+            Scheme::PedersenBlsChained | Scheme::PedersenBlsUnchained
+        )
+    }
+}
+
+/// A drand network the contract accepts beacons from.
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
-pub struct InstantiateMsg {
+pub struct NetworkInfo {
+    /// The chain hash of the network in lowercase hex, as reported by the network's `/info` endpoint.
+    /// It is used as the network identifier in all messages.
+    pub chain_hash: String,
     pub pubkey: Binary,
-    /// The denom in which bounties are paid. This is typically the fee token of the chain.
-    pub bounty_denom: String,
+    /// The signature scheme of the drand network. Defaults to pedersen-bls-chained.
+    #[serde(default)]
+    pub scheme: Scheme,
+    /// The time of round 1 in seconds since epoch (`genesis_time` in the `/info` endpoint)
+    pub genesis_time: u64,
+    /// The time between two rounds in seconds (`period` in the `/info` endpoint)
+    pub period: u64,
+    /// A known-good beacon of the network. If set, it is verified against the pubkey when the
+    /// network is registered to catch a wrong key or scheme early. It is not stored.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sample_beacon: Option<BeaconSubmission>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct InstantiateMsg {
+    pub networks: Vec<NetworkInfo>,
+    /// The denoms in which bounties can be set, e.g. the fee token of the chain and IBC tokens.
+    /// Funds in other denoms are rejected.
+    pub bounty_denoms: Vec<String>,
+    /// The number of seconds a submitted round may be ahead of the block time.
+    /// This compensates for clock differences between the chain and drand. Defaults to 0.
+    #[serde(default)]
+    pub clock_tolerance: u64,
+    /// The number of seconds after the publish time of a round after which contributors
+    /// of an unclaimed bounty can withdraw their share. Defaults to one day.
+    #[serde(default = "default_bounty_expiry")]
+    pub bounty_expiry: u64,
+    /// Enables commit-reveal bounty claims to protect relayers from being front-run. If set,
+    /// the bounty of a round is only paid to a relayer that sent a `CommitBounty` at least
+    /// this many blocks before adding the beacon or sending `ClaimBounty`. For another
+    /// `claim_delay` blocks after that, only the earliest matching commitment can claim.
+    ///
+    /// This protects against copied `Add` messages but only partly against copied commitments:
+    /// once the signature is public anybody can compute a valid commitment, so a relayer that
+    /// gets its own commitment into the same block as the earliest one shares its claim rights.
+    #[serde(default)]
+    pub claim_delay: Option<u64>,
+    /// Adding a round that is already stored is a no-op with an `already_known` attribute by
+    /// default. If set, it fails with `BeaconExists` instead.
+    #[serde(default)]
+    pub reject_duplicates: bool,
+    /// The address allowed to update the config. Without an admin the config is immutable.
+    #[serde(default)]
+    pub admin: Option<String>,
+    /// If set, requesting a round that is not known yet costs at least this amount, such that
+    /// the limited job slots of a round cannot be filled for free. Must be in a bounty denom.
+    #[serde(default)]
+    pub job_fee: Option<Coin>,
+}
+
+pub(crate) fn default_bounty_expiry() -> u64 {
+    86_400
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct MigrateMsg {
+    /// The network of the beacons stored by a single-network deployment (0.2.0).
+    /// Required when migrating from such a version and ignored otherwise.
+    #[serde(default)]
+    pub legacy_network: Option<LegacyNetwork>,
+    /// The maximum number of stored entries a data migration converts in this call, such that
+    /// large stores fit into the gas limit. If entries are left, the contract keeps its old
+    /// version and migrating again with the same code resumes the migration. Unlimited if
+    /// not set.
+    #[serde(default)]
+    pub limit: Option<u32>,
+}
+
+/// The drand network of a 0.2.0 deployment. Its pubkey is taken from the stored config and
+/// the scheme is always pedersen-bls-chained.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct LegacyNetwork {
+    pub chain_hash: String,
+    pub genesis_time: u64,
+    pub period: u64,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum ExecuteMsg {
-    /// Sets a bounty as sent in sent_funds on the given round.
-    SetBounty { round: u64 },
+    /// Sets a bounty as sent in sent_funds on the given round. All sent coins must be in
+    /// one of the accepted bounty denoms. The round must be published within a year.
+    SetBounty { network: String, round: u64 },
     Add {
+        /// The chain hash of the network
+        network: String,
         round: u64,
+        /// The signature of the previous round. Only used for chained schemes and
+        /// can be omitted otherwise.
+        #[serde(default)]
         previous_signature: Binary,
         signature: Binary,
     },
+    /// Adds multiple beacons of one network at once. Bounties of all added rounds
+    /// are paid out in a single transfer.
+    AddBatch {
+        network: String,
+        beacons: Vec<BeaconSubmission>,
+        #[serde(default)]
+        mode: BatchMode,
+    },
+    /// Requests a beacon of the given round. Once the round is added, the sender receives
+    /// a `ReceiverExecuteMsg::ReceiveBeacon` callback. If the round is already known, the
+    /// callback is sent immediately and sent funds are returned. Otherwise the request must
+    /// pay the configured `job_fee` and all sent funds are added to the bounty of the round.
+    /// Callbacks run with a limited amount of gas and a round accepts a limited number of
+    /// pending jobs. Rounds whose bounty would already be expired cannot be requested.
+    RequestBeacon {
+        network: String,
+        round: u64,
+        /// An identifier chosen by the requester that is passed back in the callback
+        job_id: String,
+    },
+    /// Like `RequestBeacon` but for the first round published after the given time
+    RequestBeaconAfter {
+        network: String,
+        after: Timestamp,
+        /// An identifier chosen by the requester that is passed back in the callback
+        job_id: String,
+    },
+    /// Returns the sender's share of the bounty on a round that was not added in time.
+    /// Only possible once `bounty_expiry` seconds passed since the publish time of the round.
+    WithdrawBounty { network: String, round: u64 },
+    /// Updates the config. Only fields that are set are changed. Admin only.
+    UpdateConfig {
+        bounty_denoms: Option<Vec<String>>,
+        clock_tolerance: Option<u64>,
+        bounty_expiry: Option<u64>,
+        /// A zero amount removes the fee
+        job_fee: Option<Coin>,
+        /// Zero disables commit-reveal claims. Existing commitments are kept but cannot be used
+        /// until it is enabled again.
+        claim_delay: Option<u64>,
+        reject_duplicates: Option<bool>,
+        /// Networks to add. A network with an existing chain hash replaces the stored one.
+        #[serde(default)]
+        networks: Vec<NetworkInfo>,
+    },
+    /// Makes another address the admin. Admin only.
+    TransferAdmin { admin: String },
+    /// Removes the admin, which makes the config immutable. Admin only.
+    RenounceAdmin {},
+    /// Commits to the signature of a round with a bounty. See `bounty_commitment` for how the
+    /// commitment is computed. Only available if `claim_delay` is configured.
+    CommitBounty {
+        network: String,
+        round: u64,
+        commitment: Binary,
+    },
+    /// Claims the bounty of an added round using an earlier `CommitBounty`. This is needed if
+    /// somebody else added the beacon without having committed to it.
+    ClaimBounty { network: String, round: u64 },
+}
+
+/// The commitment a relayer sends in `CommitBounty`:
+/// `sha256("rand:commit:v1" || len(sender) || sender || round || signature)`
+/// with lengths and round as 8 byte big endian integers.
+/// Binding the sender makes the commitment bytes useless to anybody copying them from the
+/// mempool. It does not hide the signature, which is public anyway.
+pub fn bounty_commitment(sender: &str, round: u64, signature: &[u8]) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(b"rand:commit:v1");
+    hasher.update((sender.len() as u64).to_be_bytes());
+    hasher.update(sender.as_bytes());
+    hasher.update(round.to_be_bytes());
+    hasher.update(signature);
+    hasher.finalize().into()
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct BeaconSubmission {
+    pub round: u64,
+    /// The signature of the previous round. Only used for chained schemes and
+    /// can be omitted otherwise.
+    #[serde(default)]
+    pub previous_signature: Binary,
+    pub signature: Binary,
+}
+
+/// How `AddBatch` handles beacons that cannot be added
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum BatchMode {
+    /// The whole batch fails if one beacon cannot be added
+    Strict,
+    /// Invalid beacons are skipped and reported in a `failed` attribute
+    Lenient,
+}
+
+#[allow(clippy::derivable_impls)]
+impl Default for BatchMode {
+    fn default() -> Self {
+        BatchMode::Strict
+    }
+}
+
+/// The order of paginated query results
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SortOrder {
+    Ascending,
+    Descending,
+}
+
+#[allow(clippy::derivable_impls)]
+impl Default for SortOrder {
+    fn default() -> Self {
+        SortOrder::Ascending
+    }
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum QueryMsg {
     Config {},
-    Get { round: u64 },
-    Latest {},
-    Bounties {},
+    /// The current admin, if any
+    Admin {},
+    /// Lists all registered drand networks
+    Networks {},
+    Get {
+        network: String,
+        round: u64,
+    },
+    Latest {
+        network: String,
+    },
+    /// The full record of a stored beacon, including the signatures needed to verify it again
+    Beacon {
+        network: String,
+        round: u64,
+    },
+    /// Lists bounties in ascending round order. `min_round` and `max_round` are inclusive filters.
+    Bounties {
+        network: String,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+        min_round: Option<u64>,
+        max_round: Option<u64>,
+    },
+    /// Lists stored rounds with their randomness
+    Beacons {
+        network: String,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+        #[serde(default)]
+        order: SortOrder,
+    },
+    /// Lists the jobs waiting for the given round
+    Jobs {
+        network: String,
+        round: u64,
+    },
+    /// The latest round published at or before the given time. Returns round 0 for times before genesis.
+    RoundAt {
+        network: String,
+        timestamp: Timestamp,
+    },
+    /// The time at which the given round is published
+    TimeOfRound {
+        network: String,
+        round: u64,
+    },
+    /// The first round published strictly after the given time
+    NextRoundAfter {
+        network: String,
+        timestamp: Timestamp,
+    },
+    /// A uniformly distributed integer in the inclusive range `[min, max]`
+    /// derived from the randomness of the given round. See the `randomness` module.
+    RandomInt {
+        network: String,
+        round: u64,
+        min: u64,
+        max: u64,
+        #[serde(default)]
+        salt: Binary,
+    },
+    /// A random permutation of the numbers `0..n` derived from the randomness of the given round
+    Shuffle {
+        network: String,
+        round: u64,
+        n: u32,
+        #[serde(default)]
+        salt: Binary,
+    },
+    /// Randomness of the given round that is unique to the namespace and job ID.
+    /// The namespace is typically the address of the consumer.
+    /// See `randomness::derive_sub_randomness` for the derivation scheme.
+    Derive {
+        network: String,
+        round: u64,
+        namespace: String,
+        job_id: String,
+    },
+    /// `k` distinct items randomly picked from `items` using the randomness of the given round
+    Pick {
+        network: String,
+        round: u64,
+        items: Vec<String>,
+        k: u32,
+        #[serde(default)]
+        salt: Binary,
+    },
+    /// Statistics of an address that added beacons
+    Relayer {
+        address: String,
+    },
+    /// Lists relayer statistics ordered by address
+    Relayers {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    /// Lists the expired bounty shares the given address can currently withdraw. At most
+    /// `limit` expired bounties after `start_after` are checked per page.
+    Withdrawable {
+        network: String,
+        address: String,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct ConfigResponse {
-    pub pubkey: Binary,
-    pub bounty_denom: String,
+    pub bounty_denoms: Vec<String>,
+    pub clock_tolerance: u64,
+    pub bounty_expiry: u64,
+    pub claim_delay: Option<u64>,
+    pub reject_duplicates: bool,
+    pub job_fee: Option<Coin>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct AdminResponse {
+    pub admin: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct NetworksResponse {
+    pub networks: Vec<NetworkInfo>,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -58,3 +415,137 @@ pub struct Bounty {
 pub struct BountiesResponse {
     pub bounties: Vec<Bounty>,
 }
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct RoundResponse {
+    pub round: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct TimeResponse {
+    pub time: Timestamp,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct DeriveResponse {
+    pub randomness: Binary,
+    /// The derivation scheme used, e.g. "rand:derive:v1"
+    pub derivation: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct RandomIntResponse {
+    pub value: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ShuffleResponse {
+    pub permutation: Vec<u32>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PickResponse {
+    pub picked: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct BeaconResponse {
+    pub beacon: Beacon,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct RoundRandomness {
+    pub round: u64,
+    pub randomness: Binary,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct BeaconsResponse {
+    pub beacons: Vec<RoundRandomness>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct RelayerResponse {
+    pub address: String,
+    pub stats: RelayerStats,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct RelayersResponse {
+    pub relayers: Vec<RelayerResponse>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct WithdrawableResponse {
+    /// The withdrawable shares by round
+    pub withdrawable: Vec<Bounty>,
+    /// The last round checked if more expired bounties may follow. Use it as `start_after`
+    /// to query the next page.
+    pub last_round: Option<u64>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct JobsResponse {
+    pub jobs: Vec<Job>,
+}
+
+/// The payload of the callback sent to the requester of a beacon
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct BeaconCallback {
+    pub job_id: String,
+    pub network: String,
+    pub round: u64,
+    pub randomness: Binary,
+}
+
+impl BeaconCallback {
+    /// Creates a message executing the callback on the given contract
+    pub fn into_cosmos_msg(self, contract_addr: impl Into<String>) -> StdResult<CosmosMsg> {
+        let msg = to_binary(&ReceiverExecuteMsg::ReceiveBeacon(self))?;
+        Ok(WasmMsg::Execute {
+            contract_addr: contract_addr.into(),
+            msg,
+            funds: vec![],
+        }
+        .into())
+    }
+}
+
+/// The execute message a contract requesting beacons must be able to handle.
+/// This is typically embedded as a variant of the consumer's own `ExecuteMsg`.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ReceiverExecuteMsg {
+    ReceiveBeacon(BeaconCallback),
+}
+
+/// The channel version of the IBC protocol between this contract and remote consumers
+pub const IBC_VERSION: &str = "rand-1";
+
+/// Packets of the IBC protocol. Consumers send `RequestBeacon` and this contract answers with
+/// `DeliverBeacon` as soon as the round is stored. Channels must be unordered.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum RandPacket {
+    RequestBeacon {
+        network: String,
+        round: u64,
+    },
+    /// Contains everything needed to verify the beacon on the receiving side
+    DeliverBeacon {
+        network: String,
+        round: u64,
+        /// Empty for unchained schemes
+        previous_signature: Binary,
+        signature: Binary,
+        randomness: Binary,
+    },
+}
+
+/// The acknowledgement of a `RandPacket`, following the ICS-20 convention
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum RandAck {
+    Result(Binary),
+    Error(String),
+}
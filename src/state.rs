@@ -1,42 +1,260 @@
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-use cosmwasm_std::{Binary, Storage};
+use cosmwasm_std::{Addr, Binary, Coin, IbcChannel, Storage, Timestamp};
 use cosmwasm_storage::{
-    prefixed, prefixed_read, singleton, singleton_read, PrefixedStorage, ReadonlyPrefixedStorage,
-    ReadonlySingleton, Singleton,
+    bucket, bucket_read, singleton, singleton_read, Bucket, ReadonlyBucket, ReadonlySingleton,
+    Singleton,
 };
 
+use crate::msg::{default_bounty_expiry, Scheme};
+
 const CONFIG_KEY: &[u8] = b"config";
+const NETWORKS_KEY: &[u8] = b"networks";
 const BEACONS_KEY: &[u8] = b"beacons";
 const BOUNTIES_KEY: &[u8] = b"bounties";
+const JOBS_KEY: &[u8] = b"jobs";
+const CONTRIBUTIONS_KEY: &[u8] = b"contributions";
+const RELAYERS_KEY: &[u8] = b"relayers";
+const COMMITMENTS_KEY: &[u8] = b"commitments";
+const IBC_CHANNELS_KEY: &[u8] = b"ibc_channels";
+const IBC_REQUESTS_KEY: &[u8] = b"ibc_requests";
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct Config {
+    /// The denoms accepted for bounties
+    pub bounty_denoms: Vec<String>,
+    /// Seconds a submitted round may be ahead of the block time
+    #[serde(default)]
+    pub clock_tolerance: u64,
+    /// Seconds after the publish time of a round after which its bounty can be withdrawn
+    #[serde(default = "default_bounty_expiry")]
+    pub bounty_expiry: u64,
+    /// If set, bounties are only paid to relayers that committed to the beacon at least
+    /// this many blocks before revealing it
+    #[serde(default)]
+    pub claim_delay: Option<u64>,
+    /// Whether adding a known round fails with `BeaconExists` instead of being a no-op
+    #[serde(default)]
+    pub reject_duplicates: bool,
+    /// The address allowed to update the config
+    #[serde(default)]
+    pub admin: Option<Addr>,
+    /// The minimum payment for requesting a round that is not known yet
+    #[serde(default)]
+    pub job_fee: Option<Coin>,
+}
+
+/// A registered drand network, stored under its chain hash
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Network {
     pub pubkey: Binary,
-    pub bounty_denom: String,
+    pub scheme: Scheme,
+    /// Time of round 1 in seconds since epoch
+    pub genesis_time: u64,
+    /// Seconds between two rounds
+    pub period: u64,
+}
+
+impl Network {
+    /// The latest round published at or before `time`, or 0 if `time` is before genesis
+    pub fn round_at(&self, time: Timestamp) -> u64 {
+        match time.seconds().checked_sub(self.genesis_time) {
+            Some(since_genesis) => since_genesis / self.period + 1,
+            None => 0,
+        }
+    }
+
+    /// The first round published strictly after `time`
+    pub fn next_round_after(&self, time: Timestamp) -> u64 {
+        self.round_at(time) + 1
+    }
+
+    /// The publish time of `round`. Returns None for round 0 and for times not
+    /// representable as a `Timestamp`.
+    pub fn time_of_round(&self, round: u64) -> Option<Timestamp> {
+        let seconds = round
+            .checked_sub(1)?
+            .checked_mul(self.period)?
+            .checked_add(self.genesis_time)?;
+        seconds
+            .checked_mul(1_000_000_000)
+            .map(Timestamp::from_nanos)
+    }
+}
+
+/// A verified beacon along with the data needed to verify it again
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Beacon {
+    pub round: u64,
+    pub signature: Binary,
+    /// The signature of the previous round. Empty for unchained schemes.
+    pub previous_signature: Binary,
+    /// The randomness derived from the signature
+    pub randomness: Binary,
+    /// The address that added the beacon
+    pub submitter: Addr,
+    /// Height of the block in which the beacon was added
+    pub block_height: u64,
+    /// Time of the block in which the beacon was added
+    pub block_time: Timestamp,
+}
+
+/// Statistics of an address that added beacons, across all networks
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, JsonSchema)]
+pub struct RelayerStats {
+    pub rounds_submitted: u64,
+    /// The sum of all bounties paid to the relayer, sorted by denom
+    pub bounties_earned: Vec<Coin>,
+    /// None if the address only claimed bounties of beacons added by others
+    pub first_submission_height: Option<u64>,
+    pub last_submission_height: Option<u64>,
+}
+
+/// A relayer's commitment to the signature of a round, see `msg::bounty_commitment`
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Commitment {
+    pub commitment: Binary,
+    /// Height of the block in which the commitment was made
+    pub height: u64,
+}
+
+/// A request for a beacon that is answered with a callback once the round is added
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Job {
+    /// The contract receiving the callback
+    pub requester: Addr,
+    /// An identifier chosen by the requester
+    pub job_id: String,
 }
 
-pub fn config(storage: &mut dyn Storage) -> Singleton<Config> {
+pub fn config(storage: &mut dyn Storage) -> Singleton<'_, Config> {
     singleton(storage, CONFIG_KEY)
 }
 
-pub fn config_read(storage: &dyn Storage) -> ReadonlySingleton<Config> {
+pub fn config_read(storage: &dyn Storage) -> ReadonlySingleton<'_, Config> {
     singleton_read(storage, CONFIG_KEY)
 }
 
-pub fn beacons_storage(storage: &mut dyn Storage) -> PrefixedStorage {
-    prefixed(storage, BEACONS_KEY)
+pub fn networks_storage(storage: &mut dyn Storage) -> Bucket<'_, Network> {
+    bucket(storage, NETWORKS_KEY)
+}
+
+pub fn networks_storage_read(storage: &dyn Storage) -> ReadonlyBucket<'_, Network> {
+    bucket_read(storage, NETWORKS_KEY)
+}
+
+/// Beacons of one network, keyed by round
+pub fn beacons_storage<'a>(storage: &'a mut dyn Storage, network: &str) -> Bucket<'a, Beacon> {
+    Bucket::multilevel(storage, &[BEACONS_KEY, network.as_bytes()])
+}
+
+pub fn beacons_storage_read<'a>(
+    storage: &'a dyn Storage,
+    network: &str,
+) -> ReadonlyBucket<'a, Beacon> {
+    ReadonlyBucket::multilevel(storage, &[BEACONS_KEY, network.as_bytes()])
+}
+
+/// Bounties of one network, keyed by round. Coins are sorted by denom.
+pub fn bounties_storage<'a>(storage: &'a mut dyn Storage, network: &str) -> Bucket<'a, Vec<Coin>> {
+    Bucket::multilevel(storage, &[BOUNTIES_KEY, network.as_bytes()])
+}
+
+pub fn bounties_storage_read<'a>(
+    storage: &'a dyn Storage,
+    network: &str,
+) -> ReadonlyBucket<'a, Vec<Coin>> {
+    ReadonlyBucket::multilevel(storage, &[BOUNTIES_KEY, network.as_bytes()])
+}
+
+/// Pending jobs of one network, keyed by round
+pub fn jobs_storage<'a>(storage: &'a mut dyn Storage, network: &str) -> Bucket<'a, Vec<Job>> {
+    Bucket::multilevel(storage, &[JOBS_KEY, network.as_bytes()])
+}
+
+pub fn jobs_storage_read<'a>(
+    storage: &'a dyn Storage,
+    network: &str,
+) -> ReadonlyBucket<'a, Vec<Job>> {
+    ReadonlyBucket::multilevel(storage, &[JOBS_KEY, network.as_bytes()])
+}
+
+/// Bounty contributions to one round, keyed by contributor address. Coins are sorted by denom.
+pub fn contributions_storage<'a>(
+    storage: &'a mut dyn Storage,
+    network: &str,
+    round: u64,
+) -> Bucket<'a, Vec<Coin>> {
+    Bucket::multilevel(
+        storage,
+        &[CONTRIBUTIONS_KEY, network.as_bytes(), &round.to_be_bytes()],
+    )
+}
+
+pub fn contributions_storage_read<'a>(
+    storage: &'a dyn Storage,
+    network: &str,
+    round: u64,
+) -> ReadonlyBucket<'a, Vec<Coin>> {
+    ReadonlyBucket::multilevel(
+        storage,
+        &[CONTRIBUTIONS_KEY, network.as_bytes(), &round.to_be_bytes()],
+    )
+}
+
+/// Relayer statistics, keyed by address
+pub fn relayers_storage(storage: &mut dyn Storage) -> Bucket<'_, RelayerStats> {
+    bucket(storage, RELAYERS_KEY)
+}
+
+pub fn relayers_storage_read(storage: &dyn Storage) -> ReadonlyBucket<'_, RelayerStats> {
+    bucket_read(storage, RELAYERS_KEY)
+}
+
+/// Bounty commitments to one round, keyed by relayer address
+pub fn commitments_storage<'a>(
+    storage: &'a mut dyn Storage,
+    network: &str,
+    round: u64,
+) -> Bucket<'a, Commitment> {
+    Bucket::multilevel(
+        storage,
+        &[COMMITMENTS_KEY, network.as_bytes(), &round.to_be_bytes()],
+    )
+}
+
+pub fn commitments_storage_read<'a>(
+    storage: &'a dyn Storage,
+    network: &str,
+    round: u64,
+) -> ReadonlyBucket<'a, Commitment> {
+    ReadonlyBucket::multilevel(
+        storage,
+        &[COMMITMENTS_KEY, network.as_bytes(), &round.to_be_bytes()],
+    )
+}
+
+/// Connected IBC channels, keyed by channel ID
+pub fn ibc_channels_storage(storage: &mut dyn Storage) -> Bucket<'_, IbcChannel> {
+    bucket(storage, IBC_CHANNELS_KEY)
 }
 
-pub fn beacons_storage_read(storage: &dyn Storage) -> ReadonlyPrefixedStorage {
-    prefixed_read(storage, BEACONS_KEY)
+pub fn ibc_channels_storage_read(storage: &dyn Storage) -> ReadonlyBucket<'_, IbcChannel> {
+    bucket_read(storage, IBC_CHANNELS_KEY)
 }
 
-pub fn bounties_storage(storage: &mut dyn Storage) -> PrefixedStorage {
-    prefixed(storage, BOUNTIES_KEY)
+/// IDs of the channels waiting for a round, keyed by round
+pub fn ibc_requests_storage<'a>(
+    storage: &'a mut dyn Storage,
+    network: &str,
+) -> Bucket<'a, Vec<String>> {
+    Bucket::multilevel(storage, &[IBC_REQUESTS_KEY, network.as_bytes()])
 }
 
-pub fn bounties_storage_read(storage: &dyn Storage) -> ReadonlyPrefixedStorage {
-    prefixed_read(storage, BOUNTIES_KEY)
+pub fn ibc_requests_storage_read<'a>(
+    storage: &'a dyn Storage,
+    network: &str,
+) -> ReadonlyBucket<'a, Vec<String>> {
+    ReadonlyBucket::multilevel(storage, &[IBC_REQUESTS_KEY, network.as_bytes()])
 }
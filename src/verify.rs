@@ -0,0 +1,50 @@
+//! Beacon verification, shared by the contract, the IBC consumer and the off-chain tools
+//! such that all of them accept exactly the same beacons.
+
+use drand_verify::{G1Pubkey, G2PubkeyFastnet, G2PubkeyRfc, InvalidPoint, Pubkey};
+
+use crate::msg::Scheme;
+
+/// Verifies a beacon against the pubkey using the given scheme.
+///
+/// The previous signature is ignored for unchained schemes.
+/// Returns an error if the pubkey cannot be loaded and false if the signature is invalid.
+pub fn verify_beacon(
+    scheme: Scheme,
+    pubkey: &[u8],
+    round: u64,
+    previous_signature: &[u8],
+    signature: &[u8],
+) -> Result<bool, InvalidPoint> {
+    let previous_signature = if scheme.is_chained() {
+        previous_signature
+    } else {
+        b""
+    };
+    let result = match scheme {
+        Scheme::PedersenBlsChained | Scheme::PedersenBlsUnchained => {
+            G1Pubkey::from_variable(pubkey)?.verify(round, previous_signature, signature)
+        }
+        Scheme::BlsUnchainedOnG1 => {
+            G2PubkeyFastnet::from_variable(pubkey)?.verify(round, previous_signature, signature)
+        }
+        Scheme::BlsUnchainedG1Rfc9380 => {
+            G2PubkeyRfc::from_variable(pubkey)?.verify(round, previous_signature, signature)
+        }
+    };
+    Ok(result.unwrap_or(false))
+}
+
+/// Checks that the pubkey is a valid point on the curve the scheme expects it on.
+/// Returns a description of the problem otherwise.
+pub fn check_pubkey(scheme: Scheme, pubkey: &[u8]) -> Result<(), String> {
+    let result = match scheme {
+        Scheme::PedersenBlsChained | Scheme::PedersenBlsUnchained => {
+            G1Pubkey::from_variable(pubkey).map(|_| ())
+        }
+        Scheme::BlsUnchainedOnG1 => G2PubkeyFastnet::from_variable(pubkey).map(|_| ()),
+        Scheme::BlsUnchainedG1Rfc9380 => G2PubkeyRfc::from_variable(pubkey).map(|_| ()),
+    };
+    let group = if scheme.pubkey_on_g1() { "G1" } else { "G2" };
+    result.map_err(|err| format!("expected a {} point: {}", group, err))
+}
@@ -0,0 +1,33 @@
+#!/bin/bash
+# Adds beacons from the drand HTTP API to a fixture file.
+#
+# Usage: ./fetch.sh <name> <chain url> <round>...
+#
+# Example: ./fetch.sh quicknet https://api.drand.sh/52db9ba70e0cc0f6eaf7803dd07447a1f5477735fd3f661792ba94600c84e971 1 2 3
+#
+# Writes <name>.json next to this script. Existing beacons are kept, so the corpus only grows.
+# Check the result with `cargo test fixtures`.
+set -o errexit -o nounset -o pipefail
+
+if [ "$#" -lt 3 ]; then
+  echo "Usage: $0 <name> <chain url> <round>..." >&2
+  exit 2
+fi
+
+NAME="$1"
+URL="${2%/}"
+shift 2
+
+FILE="$(dirname "$0")/$NAME.json"
+if [ ! -f "$FILE" ]; then
+  echo '{"beacons":[]}' >"$FILE"
+fi
+
+INFO="$(curl -sSf "$URL/info")"
+BEACONS="$(for ROUND in "$@"; do curl -sSf "$URL/public/$ROUND"; done | jq -s .)"
+
+jq --argjson info "$INFO" --argjson beacons "$BEACONS" \
+  '.info = $info | .beacons = (.beacons + $beacons | unique_by(.round))' \
+  "$FILE" >"$FILE.tmp"
+mv "$FILE.tmp" "$FILE"
+echo "$FILE has $(jq '.beacons | length' "$FILE") beacons"
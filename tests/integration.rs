@@ -2,24 +2,24 @@
 //! It depends on a Wasm build being available, which you can create with `cargo wasm`.
 //! Then running `cargo integration-test` will validate we can properly call into that generated Wasm.
 
-use cosmwasm_std::{from_binary, Binary, ContractResult, Response};
+use cosmwasm_std::{from_binary, ContractResult, Env, Response, Timestamp};
 use cosmwasm_vm::testing::{
     execute, instantiate, mock_env, mock_info, mock_instance, mock_instance_with_gas_limit, query,
 };
 use std::time::Instant;
 
-use rand::msg::{ExecuteMsg, InstantiateMsg, LatestResponse, QueryMsg};
+use rand::fixtures;
+use rand::msg::{ExecuteMsg, GetResponse, InstantiateMsg, LatestResponse, QueryMsg};
 static WASM: &[u8] = include_bytes!("../target/wasm32-unknown-unknown/release/rand.wasm");
 
-fn pubkey_loe_mainnet() -> Binary {
-    vec![
-        134, 143, 0, 94, 184, 230, 228, 202, 10, 71, 200, 167, 124, 234, 165, 48, 154, 71, 151,
-        138, 124, 113, 188, 92, 206, 150, 54, 107, 93, 122, 86, 153, 55, 197, 41, 238, 218, 102,
-        199, 41, 55, 132, 169, 64, 40, 1, 175, 49,
-    ]
-    .into()
+/// A block time after all rounds used in the tests were published
+fn mock_env_later() -> Env {
+    let mut env = mock_env();
+    env.block.time = Timestamp::from_seconds(1_700_000_000);
+    env
 }
 
+const MAINNET: &str = "8990e7a9aaed2ffed73dbd7092123d6f289930540d7651336225dc172e51b2ce";
 const BOUNTY_DENOM: &str = "ucosm";
 
 #[test]
@@ -27,8 +27,14 @@ fn proper_initialization() {
     let mut deps = mock_instance(WASM, &[]);
 
     let msg = InstantiateMsg {
-        pubkey: pubkey_loe_mainnet(),
-        bounty_denom: BOUNTY_DENOM.into(),
+        networks: vec![fixtures::network(fixtures::MAINNET).network_info()],
+        bounty_denoms: vec![BOUNTY_DENOM.into()],
+        clock_tolerance: 0,
+        bounty_expiry: 86_400,
+        claim_delay: None,
+        reject_duplicates: false,
+        admin: None,
+        job_fee: None,
     };
     let info = mock_info("creator", &[]);
     // we can just call .unwrap() to assert this was a success
@@ -41,8 +47,14 @@ fn verify_valid() {
     let mut deps = mock_instance_with_gas_limit(WASM, 1_000_000_000_000_000);
 
     let msg = InstantiateMsg {
-        pubkey: pubkey_loe_mainnet(),
-        bounty_denom: BOUNTY_DENOM.into(),
+        networks: vec![fixtures::network(fixtures::MAINNET).network_info()],
+        bounty_denoms: vec![BOUNTY_DENOM.into()],
+        clock_tolerance: 0,
+        bounty_expiry: 86_400,
+        claim_delay: None,
+        reject_duplicates: false,
+        admin: None,
+        job_fee: None,
     };
     let info = mock_info("creator", &[]);
     let _res: Response = instantiate(&mut deps, mock_env(), info.clone(), msg).unwrap();
@@ -51,23 +63,35 @@ fn verify_valid() {
     let gas_before = deps.get_gas_left();
 
     let msg = ExecuteMsg::Add {
+        network: MAINNET.into(),
         round: 72785,
-        previous_signature: hex::decode("a609e19a03c2fcc559e8dae14900aaefe517cb55c840f6e69bc8e4f66c8d18e8a609685d9917efbfb0c37f058c2de88f13d297c7e19e0ab24813079efe57a182554ff054c7638153f9b26a60e7111f71a0ff63d9571704905d3ca6df0b031747").unwrap().into(),
-        signature: hex::decode("82f5d3d2de4db19d40a6980e8aa37842a0e55d1df06bd68bddc8d60002e8e959eb9cfa368b3c1b77d18f02a54fe047b80f0989315f83b12a74fd8679c4f12aae86eaf6ab5690b34f1fddd50ee3cc6f6cdf59e95526d5a5d82aaa84fa6f181e42").unwrap().into(),
+        previous_signature: fixtures::beacon(fixtures::MAINNET, 72785).previous_signature,
+        signature: fixtures::beacon(fixtures::MAINNET, 72785).signature,
     };
 
-    let _res: Response = execute(&mut deps, mock_env(), info, msg).unwrap();
+    let _res: Response = execute(&mut deps, mock_env_later(), info, msg).unwrap();
     let gas_used = gas_before - deps.get_gas_left();
     println!("Gas used: {}", gas_used);
     println!("Time elapsed: {:.2?}", time_before.elapsed());
 
-    let latest: LatestResponse =
-        from_binary(&query(&mut deps, mock_env(), QueryMsg::Latest {}).unwrap()).unwrap();
+    let latest: LatestResponse = from_binary(
+        &query(
+            &mut deps,
+            mock_env(),
+            QueryMsg::Latest {
+                network: MAINNET.into(),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
     assert_eq!(latest.round, 72785);
 
     assert_eq!(
         latest.randomness,
-        hex::decode("8b676484b5fb1f37f9ec5c413d7d29883504e5b669f604a1ce68b3388e9ae3d9").unwrap()
+        fixtures::beacon(fixtures::MAINNET, 72785)
+            .randomness
+            .to_vec()
     );
 }
 
@@ -76,8 +100,14 @@ fn verify_invalid() {
     let mut deps = mock_instance_with_gas_limit(WASM, 1_000_000_000_000_000);
 
     let msg = InstantiateMsg {
-        pubkey: pubkey_loe_mainnet(),
-        bounty_denom: BOUNTY_DENOM.into(),
+        networks: vec![fixtures::network(fixtures::MAINNET).network_info()],
+        bounty_denoms: vec![BOUNTY_DENOM.into()],
+        clock_tolerance: 0,
+        bounty_expiry: 86_400,
+        claim_delay: None,
+        reject_duplicates: false,
+        admin: None,
+        job_fee: None,
     };
     let info = mock_info("creator", &[]);
     let _res: Response = instantiate(&mut deps, mock_env(), info, msg).unwrap();
@@ -85,16 +115,62 @@ fn verify_invalid() {
     let gas_before = deps.get_gas_left();
     let info = mock_info("anyone", &[]);
     let msg = ExecuteMsg::Add {
-        // curl -sS https://drand.cloudflare.com/public/72785
+        network: MAINNET.into(),
+        // Beacon of round 72785
         round: 42,
-        previous_signature: hex::decode("a609e19a03c2fcc559e8dae14900aaefe517cb55c840f6e69bc8e4f66c8d18e8a609685d9917efbfb0c37f058c2de88f13d297c7e19e0ab24813079efe57a182554ff054c7638153f9b26a60e7111f71a0ff63d9571704905d3ca6df0b031747").unwrap().into(),
-        signature: hex::decode("82f5d3d2de4db19d40a6980e8aa37842a0e55d1df06bd68bddc8d60002e8e959eb9cfa368b3c1b77d18f02a54fe047b80f0989315f83b12a74fd8679c4f12aae86eaf6ab5690b34f1fddd50ee3cc6f6cdf59e95526d5a5d82aaa84fa6f181e42").unwrap().into(),
+        previous_signature: fixtures::beacon(fixtures::MAINNET, 72785).previous_signature,
+        signature: fixtures::beacon(fixtures::MAINNET, 72785).signature,
     };
 
-    let res: ContractResult<Response> = execute(&mut deps, mock_env(), info, msg);
+    let res: ContractResult<Response> = execute(&mut deps, mock_env_later(), info, msg);
     let err = res.unwrap_err();
 
     assert_eq!(err, "Signature verification failed");
     let gas_used = gas_before - deps.get_gas_left();
     println!("Gas used: {}", gas_used);
 }
+
+#[test]
+fn add_works_for_fixtures() {
+    let mut deps = mock_instance_with_gas_limit(WASM, 1_000_000_000_000_000);
+    let networks = fixtures::networks();
+
+    let msg = InstantiateMsg {
+        networks: networks.iter().map(|n| n.network_info()).collect(),
+        bounty_denoms: vec![BOUNTY_DENOM.into()],
+        clock_tolerance: 0,
+        bounty_expiry: 86_400,
+        claim_delay: None,
+        reject_duplicates: false,
+        admin: None,
+        job_fee: None,
+    };
+    let info = mock_info("creator", &[]);
+    let _res: Response = instantiate(&mut deps, mock_env(), info.clone(), msg).unwrap();
+
+    for network in networks {
+        for beacon in &network.beacons {
+            let msg = ExecuteMsg::Add {
+                network: network.chain_hash().into(),
+                round: beacon.round,
+                previous_signature: beacon.previous_signature.clone(),
+                signature: beacon.signature.clone(),
+            };
+            let _res: Response = execute(&mut deps, mock_env_later(), info.clone(), msg).unwrap();
+
+            let response: GetResponse = from_binary(
+                &query(
+                    &mut deps,
+                    mock_env(),
+                    QueryMsg::Get {
+                        network: network.chain_hash().into(),
+                        round: beacon.round,
+                    },
+                )
+                .unwrap(),
+            )
+            .unwrap();
+            assert_eq!(response.randomness, beacon.randomness);
+        }
+    }
+}
@@ -0,0 +1,459 @@
+//! End-to-end tests of the rand contract together with consumer contracts and the bank module.
+//!
+//! Unlike the unit tests, messages returned by the contracts are executed here, so bounty
+//! payouts, refunds and callbacks are checked by the balances they result in.
+
+use cosmwasm_std::{
+    coin, coins, to_binary, Addr, Binary, Coin, Deps, DepsMut, Empty, Env, MessageInfo, Response,
+    StdError, StdResult, Timestamp, WasmMsg,
+};
+use cw_multi_test::{App, AppResponse, Contract, ContractWrapper, Executor};
+use serde::{Deserialize, Serialize};
+
+use rand::fixtures::{self, NetworkFixture};
+use rand::msg::{
+    BatchMode, BeaconCallback, BeaconSubmission, ExecuteMsg, InstantiateMsg, QueryMsg,
+    RoundResponse,
+};
+use rand::randomness::{derive_sub_randomness, RandomStream};
+use rand_lottery::msg::{
+    ExecuteMsg as LotteryExecuteMsg, InstantiateMsg as LotteryInstantiateMsg, LotteryResponse,
+    QueryMsg as LotteryQueryMsg,
+};
+
+const DENOM: &str = "ucosm";
+const OTHER_DENOM: &str = "uatom";
+const BOUNTY_EXPIRY: u64 = 86_400;
+const TICKET_PRICE: u128 = 100;
+
+const PLAYERS: [&str; 3] = ["alice", "bob", "carol"];
+const FUNDER: &str = "funder";
+const RELAYER: &str = "relayer";
+const INITIAL_BALANCE: u128 = 1_000;
+
+fn rand_contract() -> Box<dyn Contract<Empty>> {
+    let contract = ContractWrapper::new(
+        rand::contract::execute,
+        rand::contract::instantiate,
+        rand::contract::query,
+    )
+    .with_reply(rand::contract::reply);
+    Box::new(contract)
+}
+
+fn lottery_contract() -> Box<dyn Contract<Empty>> {
+    let contract = ContractWrapper::new(
+        rand_lottery::contract::execute,
+        rand_lottery::contract::instantiate,
+        rand_lottery::contract::query,
+    );
+    Box::new(contract)
+}
+
+/// Messages of a consumer that forwards messages to other contracts and rejects every callback
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+enum RejectingMsg {
+    Forward { contract: String, msg: Binary },
+    ReceiveBeacon(BeaconCallback),
+}
+
+fn rejecting_contract() -> Box<dyn Contract<Empty>> {
+    fn execute(_: DepsMut, _: Env, info: MessageInfo, msg: RejectingMsg) -> StdResult<Response> {
+        match msg {
+            RejectingMsg::Forward { contract, msg } => {
+                Ok(Response::new().add_message(WasmMsg::Execute {
+                    contract_addr: contract,
+                    msg,
+                    funds: info.funds,
+                }))
+            }
+            RejectingMsg::ReceiveBeacon(_) => Err(StdError::generic_err("callback rejected")),
+        }
+    }
+    fn instantiate(_: DepsMut, _: Env, _: MessageInfo, _: Empty) -> StdResult<Response> {
+        Ok(Response::default())
+    }
+    fn query(_: Deps, _: Env, _: Empty) -> StdResult<Binary> {
+        to_binary(&Empty {})
+    }
+    Box::new(ContractWrapper::new(execute, instantiate, query))
+}
+
+struct Suite {
+    app: App,
+    rand: Addr,
+    lottery: Addr,
+    mainnet: NetworkFixture,
+}
+
+impl Suite {
+    fn new() -> Self {
+        let mut app = App::new(|router, _, storage| {
+            for account in PLAYERS.iter().chain(&[FUNDER]) {
+                let balance = vec![
+                    coin(INITIAL_BALANCE, DENOM),
+                    coin(INITIAL_BALANCE, OTHER_DENOM),
+                ];
+                router
+                    .bank
+                    .init_balance(storage, &Addr::unchecked(*account), balance)
+                    .unwrap();
+            }
+        });
+        let mainnet = fixtures::network(fixtures::MAINNET);
+
+        let rand_id = app.store_code(rand_contract());
+        let rand = app
+            .instantiate_contract(
+                rand_id,
+                Addr::unchecked("creator"),
+                &InstantiateMsg {
+                    networks: vec![mainnet.network_info()],
+                    bounty_denoms: vec![DENOM.into(), OTHER_DENOM.into()],
+                    clock_tolerance: 0,
+                    bounty_expiry: BOUNTY_EXPIRY,
+                    claim_delay: None,
+                    reject_duplicates: false,
+                    admin: None,
+                    job_fee: None,
+                },
+                &[],
+                "rand",
+                None,
+            )
+            .unwrap();
+
+        let lottery_id = app.store_code(lottery_contract());
+        let lottery = app
+            .instantiate_contract(
+                lottery_id,
+                Addr::unchecked("creator"),
+                &LotteryInstantiateMsg {
+                    rand: rand.to_string(),
+                    network: mainnet.chain_hash().into(),
+                    ticket_price: coin(TICKET_PRICE, DENOM),
+                },
+                &[],
+                "lottery",
+                None,
+            )
+            .unwrap();
+
+        Suite {
+            app,
+            rand,
+            lottery,
+            mainnet,
+        }
+    }
+
+    /// Sets the block time to the publish time of the round plus the given offset in seconds
+    fn set_time(&mut self, round: u64, offset: i64) {
+        let time = self.time_of_round(round) as i64 + offset;
+        self.app
+            .update_block(|block| block.time = Timestamp::from_seconds(time as u64));
+    }
+
+    fn time_of_round(&self, round: u64) -> u64 {
+        let info = self.mainnet.network_info();
+        info.genesis_time + (round - 1) * info.period
+    }
+
+    fn balance(&self, address: impl Into<String>, denom: &str) -> u128 {
+        self.app
+            .wrap()
+            .query_balance(address, denom)
+            .unwrap()
+            .amount
+            .u128()
+    }
+
+    fn set_bounty(&mut self, funder: &str, round: u64, amount: &[Coin]) {
+        let msg = ExecuteMsg::SetBounty {
+            network: self.mainnet.chain_hash().into(),
+            round,
+        };
+        self.app
+            .execute_contract(Addr::unchecked(funder), self.rand.clone(), &msg, amount)
+            .unwrap();
+    }
+
+    fn add(&mut self, round: u64) -> AppResponse {
+        let beacon = self.mainnet.beacon(round).clone();
+        let msg = ExecuteMsg::Add {
+            network: self.mainnet.chain_hash().into(),
+            round,
+            previous_signature: beacon.previous_signature,
+            signature: beacon.signature,
+        };
+        self.app
+            .execute_contract(Addr::unchecked(RELAYER), self.rand.clone(), &msg, &[])
+            .unwrap()
+    }
+
+    fn withdraw_bounty(&mut self, funder: &str, round: u64) -> anyhow::Result<AppResponse> {
+        let msg = ExecuteMsg::WithdrawBounty {
+            network: self.mainnet.chain_hash().into(),
+            round,
+        };
+        self.app
+            .execute_contract(Addr::unchecked(funder), self.rand.clone(), &msg, &[])
+    }
+
+    fn lottery(&self) -> LotteryResponse {
+        self.app
+            .wrap()
+            .query_wasm_smart(&self.lottery, &LotteryQueryMsg::Lottery {})
+            .unwrap()
+    }
+
+    /// The player the lottery must pick for the given draw
+    fn expected_winner(&self, round: u64, job_id: &str) -> &'static str {
+        let randomness = &self.mainnet.beacon(round).randomness;
+        let randomness = derive_sub_randomness(randomness, self.lottery.as_str(), job_id);
+        let index = RandomStream::new(&randomness, b"").int_in_range(0, PLAYERS.len() as u64 - 1);
+        PLAYERS[index as usize]
+    }
+}
+
+fn has_attribute(response: &AppResponse, key: &str) -> bool {
+    response
+        .events
+        .iter()
+        .flat_map(|event| &event.attributes)
+        .any(|attribute| attribute.key == key)
+}
+
+#[test]
+fn lottery_pays_winner_and_relayer_gets_bounty() {
+    let mut suite = Suite::new();
+    let round = 72785;
+    suite.set_time(round, -10);
+
+    for player in PLAYERS {
+        suite
+            .app
+            .execute_contract(
+                Addr::unchecked(player),
+                suite.lottery.clone(),
+                &LotteryExecuteMsg::BuyTicket {},
+                &coins(TICKET_PRICE, DENOM),
+            )
+            .unwrap();
+    }
+    assert_eq!(suite.balance(&suite.lottery, DENOM), 300);
+
+    // Wrong ticket price
+    let err = suite
+        .app
+        .execute_contract(
+            Addr::unchecked("alice"),
+            suite.lottery.clone(),
+            &LotteryExecuteMsg::BuyTicket {},
+            &coins(TICKET_PRICE - 1, DENOM),
+        )
+        .unwrap_err();
+    assert_eq!(
+        err.root_cause().to_string(),
+        "A ticket costs exactly 100ucosm"
+    );
+    assert_eq!(suite.balance("alice", DENOM), 900);
+
+    // The draw requests the next round and puts the funds on it as a bounty
+    suite
+        .app
+        .execute_contract(
+            Addr::unchecked(FUNDER),
+            suite.lottery.clone(),
+            &LotteryExecuteMsg::Draw {},
+            &coins(50, DENOM),
+        )
+        .unwrap();
+    assert_eq!(suite.lottery().draw_round, Some(round));
+    assert_eq!(suite.balance(FUNDER, DENOM), 950);
+    assert_eq!(suite.balance(&suite.rand, DENOM), 50);
+
+    suite.set_time(round, 0);
+    suite.add(round);
+
+    // Bounty goes to the relayer, the pot to the winner
+    assert_eq!(suite.balance(RELAYER, DENOM), 50);
+    assert_eq!(suite.balance(&suite.rand, DENOM), 0);
+    assert_eq!(suite.balance(&suite.lottery, DENOM), 0);
+    let winner = suite.expected_winner(round, "draw-1");
+    for player in PLAYERS {
+        let expected = if player == winner { 1_200 } else { 900 };
+        assert_eq!(suite.balance(player, DENOM), expected, "{}", player);
+    }
+
+    let lottery = suite.lottery();
+    assert_eq!(lottery.last_winner, Some(Addr::unchecked(winner)));
+    assert_eq!(lottery.draw_round, None);
+    assert!(lottery.players.is_empty());
+}
+
+#[test]
+fn add_batch_pays_bounties_of_all_rounds() {
+    let mut suite = Suite::new();
+    suite.set_time(40, 0);
+
+    suite.set_bounty(FUNDER, 41, &coins(10, DENOM));
+    suite.set_bounty(FUNDER, 42, &[coin(20, DENOM), coin(5, OTHER_DENOM)]);
+    suite.set_bounty("alice", 42, &coins(30, DENOM));
+    suite.set_bounty("bob", 72785, &coins(40, OTHER_DENOM));
+    assert_eq!(suite.balance(&suite.rand, DENOM), 60);
+    assert_eq!(suite.balance(&suite.rand, OTHER_DENOM), 45);
+
+    // Round 45 has no bounty, so relaying it earns nothing
+    suite.set_time(72785, 0);
+    let beacons: Vec<BeaconSubmission> = [41, 42, 45, 72785]
+        .iter()
+        .map(|round| suite.mainnet.beacon(*round).clone().into())
+        .collect();
+    let msg = ExecuteMsg::AddBatch {
+        network: suite.mainnet.chain_hash().into(),
+        beacons,
+        mode: BatchMode::Strict,
+    };
+    suite
+        .app
+        .execute_contract(Addr::unchecked(RELAYER), suite.rand.clone(), &msg, &[])
+        .unwrap();
+
+    assert_eq!(suite.balance(RELAYER, DENOM), 60);
+    assert_eq!(suite.balance(RELAYER, OTHER_DENOM), 45);
+    assert_eq!(suite.balance(&suite.rand, DENOM), 0);
+    assert_eq!(suite.balance(&suite.rand, OTHER_DENOM), 0);
+
+    // Paid bounties cannot be withdrawn anymore
+    suite.set_time(72785, BOUNTY_EXPIRY as i64);
+    let err = suite.withdraw_bounty("bob", 72785).unwrap_err();
+    assert_eq!(
+        err.root_cause().to_string(),
+        "Sender has no withdrawable contribution to the bounty on round 72785"
+    );
+    assert_eq!(suite.balance("bob", OTHER_DENOM), 960);
+}
+
+#[test]
+fn expired_bounties_are_refunded() {
+    let mut suite = Suite::new();
+    let round = 72785;
+    suite.set_time(round, -10);
+
+    suite.set_bounty(FUNDER, round, &[coin(100, DENOM), coin(10, OTHER_DENOM)]);
+    suite.set_bounty("alice", round, &coins(50, DENOM));
+    assert_eq!(suite.balance(&suite.rand, DENOM), 150);
+
+    // Too early
+    suite.set_time(round, BOUNTY_EXPIRY as i64 - 1);
+    let err = suite.withdraw_bounty(FUNDER, round).unwrap_err();
+    assert_eq!(
+        err.root_cause().to_string(),
+        format!(
+            "The bounty on round {} can only be withdrawn from {} (seconds since epoch)",
+            round,
+            suite.time_of_round(round) + BOUNTY_EXPIRY
+        )
+    );
+    assert_eq!(suite.balance(FUNDER, DENOM), 900);
+
+    // Everybody gets back their own share
+    suite.set_time(round, BOUNTY_EXPIRY as i64);
+    suite.withdraw_bounty(FUNDER, round).unwrap();
+    assert_eq!(suite.balance(FUNDER, DENOM), INITIAL_BALANCE);
+    assert_eq!(suite.balance(FUNDER, OTHER_DENOM), INITIAL_BALANCE);
+    assert_eq!(suite.balance(&suite.rand, DENOM), 50);
+
+    let err = suite.withdraw_bounty(FUNDER, round).unwrap_err();
+    assert_eq!(
+        err.root_cause().to_string(),
+        "Sender has no withdrawable contribution to the bounty on round 72785"
+    );
+
+    suite.withdraw_bounty("alice", round).unwrap();
+    assert_eq!(suite.balance("alice", DENOM), INITIAL_BALANCE);
+    assert_eq!(suite.balance(&suite.rand, DENOM), 0);
+
+    // A late relayer gets nothing
+    suite.add(round);
+    assert_eq!(suite.balance(RELAYER, DENOM), 0);
+}
+
+#[test]
+fn rejected_callback_does_not_block_payouts() {
+    let mut suite = Suite::new();
+    let round = 72785;
+    suite.set_time(round, -10);
+
+    let rejecting_id = suite.app.store_code(rejecting_contract());
+    let rejecting = suite
+        .app
+        .instantiate_contract(
+            rejecting_id,
+            Addr::unchecked("creator"),
+            &Empty {},
+            &[],
+            "rejecting",
+            None,
+        )
+        .unwrap();
+    let next_round: RoundResponse = suite
+        .app
+        .wrap()
+        .query_wasm_smart(
+            &suite.rand,
+            &QueryMsg::NextRoundAfter {
+                network: suite.mainnet.chain_hash().into(),
+                timestamp: suite.app.block_info().time,
+            },
+        )
+        .unwrap();
+    assert_eq!(next_round.round, round);
+
+    // Both consumers wait for the same round, the rejecting one funds the bounty
+    let request = ExecuteMsg::RequestBeacon {
+        network: suite.mainnet.chain_hash().into(),
+        round,
+        job_id: "rejected".into(),
+    };
+    let msg = RejectingMsg::Forward {
+        contract: suite.rand.to_string(),
+        msg: to_binary(&request).unwrap(),
+    };
+    suite
+        .app
+        .execute_contract(Addr::unchecked(FUNDER), rejecting.clone(), &msg, &[])
+        .unwrap();
+    suite.set_bounty(FUNDER, round, &coins(70, DENOM));
+
+    suite
+        .app
+        .execute_contract(
+            Addr::unchecked("alice"),
+            suite.lottery.clone(),
+            &LotteryExecuteMsg::BuyTicket {},
+            &coins(TICKET_PRICE, DENOM),
+        )
+        .unwrap();
+    suite
+        .app
+        .execute_contract(
+            Addr::unchecked("alice"),
+            suite.lottery.clone(),
+            &LotteryExecuteMsg::Draw {},
+            &[],
+        )
+        .unwrap();
+
+    suite.set_time(round, 0);
+    let response = suite.add(round);
+    assert!(has_attribute(&response, "callback_error"));
+
+    // The relayer is paid and the other consumer still got its callback
+    assert_eq!(suite.balance(RELAYER, DENOM), 70);
+    assert_eq!(suite.balance(&suite.rand, DENOM), 0);
+    assert_eq!(suite.balance("alice", DENOM), INITIAL_BALANCE);
+    assert_eq!(suite.lottery().last_winner, Some(Addr::unchecked("alice")));
+}